@@ -1,13 +1,16 @@
+use log::warn;
+
 use crate::basics::card::{CardStatus, ConvData};
 use crate::basics::game::frame::Frame;
 use crate::basics::identity_set::IdentitySet;
 
 use self::clue::{BaseClue, CardClue};
-use self::card::{Card, Identity, Thought};
+use self::card::{Card, Identifiable, Identity, Thought};
 use self::game::{Game};
+use self::player::Player;
+use self::state::State;
 use self::action::{ClueAction, DiscardAction, DrawAction, PlayAction};
 use self::variant::{touch_possibilities};
-use std::cmp::min;
 
 pub mod action;
 pub mod card;
@@ -21,6 +24,9 @@ pub mod variant;
 pub mod util;
 pub mod identity_set;
 
+#[cfg(test)]
+pub(crate) mod test_util;
+
 pub fn on_clue(game: &mut Game, action: &ClueAction) {
 	let Game { common, state, meta, deck_ids, .. } = game;
 	let &ClueAction { target, clue, ref list, giver } = action;
@@ -43,8 +49,7 @@ pub fn on_clue(game: &mut Game, action: &ClueAction) {
 			thought.possible = possible.intersect(&new_possible);
 
 			// Write identity if fully known
-			if thought.possible.len() == 1 {
-				let id = thought.possible.iter().next().unwrap();
+			if let Some(id) = thought.known_id() {
 				card.base = Some(id);
 				deck_ids[order] = Some(id);
 			}
@@ -59,8 +64,12 @@ pub fn on_clue(game: &mut Game, action: &ClueAction) {
 		}
 	}
 
-	state.endgame_turns = state.endgame_turns.map(|turns| turns - 1);
-	state.clue_tokens -= 1;
+	if state.endgame_turns == Some(0) {
+		warn!("received a clue after the final turn of the endgame!");
+	}
+
+	state.endgame_turns = state.endgame_turns.map(|turns| turns.saturating_sub(1));
+	state.spend_clue();
 }
 
 pub fn on_discard(game: &mut Game, action: &DiscardAction) {
@@ -71,17 +80,12 @@ pub fn on_discard(game: &mut Game, action: &DiscardAction) {
 		let id = Identity { suit_index: suit_index as usize, rank: rank as usize };
 
 		state.hands[player_index].retain(|&o| o != order);
-		state.discard_stacks[id.suit_index][id.rank - 1].push(order);
+		state.discard_to_stack(order, id);
 
 		// Assign identity
 		state.deck[order].base = Some(id);
 		deck_ids[order] = Some(id);
 
-		// Discarded all copies of an identity
-		if state.discard_stacks[id.suit_index][id.rank - 1].len() == state.card_count(id) {
-			state.max_ranks[id.suit_index] = min(state.max_ranks[id.suit_index], id.rank - 1);
-		}
-
 		let thought = &mut common.thoughts[order];
 		thought.possible = IdentitySet::single(id);
 		thought.inferred = IdentitySet::single(id);
@@ -89,7 +93,7 @@ pub fn on_discard(game: &mut Game, action: &DiscardAction) {
 
 	let Game { state, .. } = game;
 
-	state.endgame_turns = state.endgame_turns.map(|turns| turns - 1);
+	state.endgame_turns = state.endgame_turns.map(|turns| turns.saturating_sub(1));
 
 	if failed {
 		state.strikes += 1;
@@ -103,6 +107,11 @@ pub fn on_draw(game: &mut Game, action: &DrawAction) {
 	let Game { common, state, meta, players, deck_ids, .. } = game;
 	let &DrawAction { order, player_index, suit_index, rank } = action;
 
+	if state.cards_left == 0 {
+		warn!("tried to draw order {order} with no cards left!");
+		return;
+	}
+
 	let id = if suit_index != -1 {
 		if let Some(Some(deck_id)) = deck_ids.get(order) {
 			assert_eq!(*deck_id, Identity { suit_index: suit_index as usize, rank: rank as usize });
@@ -162,7 +171,7 @@ pub fn on_play(game: &mut Game, action: &PlayAction) {
 	if suit_index != -1 && rank != -1 {
 		let id = Identity { suit_index: suit_index as usize, rank: rank as usize };
 
-		state.play_stacks[id.suit_index] = id.rank;
+		state.play_to_stack(id);
 
 		// Assign identity
 		state.deck[order].base = Some(id);
@@ -176,13 +185,20 @@ pub fn on_play(game: &mut Game, action: &PlayAction) {
 
 	let Game { state, .. } = game;
 
-	state.endgame_turns = state.endgame_turns.map(|turns| turns - 1);
+	state.endgame_turns = state.endgame_turns.map(|turns| turns.saturating_sub(1));
 
-	if rank == 5 {
+	if rank != -1 && state.regains_clue(rank as usize) {
 		state.regain_clue();
 	}
 }
 
+/** Returns the orders of visible cards whose possibilities (from the player's perspective) no longer contain their actual identity. */
+fn inconsistent_empathy(state: &State, player: &Player) -> Vec<usize> {
+	state.hands.concat().into_iter().filter(|&order|
+		state.deck[order].id().is_some_and(|id| !player.thoughts[order].possible.contains(id))
+	).collect()
+}
+
 pub fn elim(game: &mut Game, good_touch: bool) {
 	let Game { common, state, players, meta, .. } = game;
 
@@ -204,6 +220,12 @@ pub fn elim(game: &mut Game, good_touch: bool) {
 	common.refresh_links(&frame, good_touch);
 	common.update_hypo_stacks(&frame, &[]);
 
+	if cfg!(debug_assertions) {
+		for order in inconsistent_empathy(state, common) {
+			warn!("inconsistent empathy! card {order} is {} but has possibilities {}", state.log_iden(&state.deck[order]), common.str_poss(state, order));
+		}
+	}
+
 	for player in players {
 		for (i, thought) in player.thoughts.iter_mut().enumerate() {
 			let Thought { possible, inferred, info_lock, reset, .. } = &common.thoughts[i];
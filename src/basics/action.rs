@@ -6,10 +6,10 @@ use crate::reactor::ClueInterp;
 
 use super::clue::BaseClue;
 use ahash::AHasher;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StatusAction {
 	pub clues: usize,
@@ -17,14 +17,14 @@ pub struct StatusAction {
 	pub max_score: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TurnAction {
 	pub num: usize,
 	pub current_player_index: i32,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ClueAction {
 	pub giver: usize,
 	pub target: usize,
@@ -32,7 +32,7 @@ pub struct ClueAction {
 	pub clue: BaseClue
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DrawAction {
 	pub player_index: usize,
@@ -41,7 +41,7 @@ pub struct DrawAction {
 	pub rank: i32
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlayAction {
 	pub player_index: usize,
@@ -50,7 +50,7 @@ pub struct PlayAction {
 	pub rank: i32
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DiscardAction {
 	pub player_index: usize,
@@ -60,7 +60,7 @@ pub struct DiscardAction {
 	pub failed: bool
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StrikeAction {
 	pub num: usize,
@@ -68,19 +68,75 @@ pub struct StrikeAction {
 	pub order: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GameOverAction {
 	pub end_condition: usize,
 	pub player_index: usize
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+/** Why a game ended, from the end condition codes used by hanab.live. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndCondition {
+	InProgress,
+	Normal,
+	Strikeout,
+	Timeout,
+	Terminated,
+	SpeedrunFail,
+	IdleTimeout,
+	CharacterSoftlock,
+	AllOrNothingFail,
+	AllOrNothingSoftlock,
+	TerminatedByVote,
+	Unknown(usize),
+}
+
+impl From<usize> for EndCondition {
+	fn from(end_condition: usize) -> Self {
+		match end_condition {
+			0 => EndCondition::InProgress,
+			1 => EndCondition::Normal,
+			2 => EndCondition::Strikeout,
+			3 => EndCondition::Timeout,
+			4 => EndCondition::Terminated,
+			5 => EndCondition::SpeedrunFail,
+			6 => EndCondition::IdleTimeout,
+			7 => EndCondition::CharacterSoftlock,
+			8 => EndCondition::AllOrNothingFail,
+			9 => EndCondition::AllOrNothingSoftlock,
+			10 => EndCondition::TerminatedByVote,
+			_ => EndCondition::Unknown(end_condition),
+		}
+	}
+}
+
+impl EndCondition {
+	/** Returns a human-readable reason for the game ending. */
+	pub fn reason(&self) -> String {
+		match self {
+			EndCondition::InProgress => "the game is still in progress".to_string(),
+			EndCondition::Normal => "the deck ran out or every stack was completed".to_string(),
+			EndCondition::Strikeout => "the team struck out".to_string(),
+			EndCondition::Timeout => "a player ran out of time".to_string(),
+			EndCondition::Terminated => "a player terminated the game".to_string(),
+			EndCondition::SpeedrunFail => "the speedrun failed".to_string(),
+			EndCondition::IdleTimeout => "the game was idle for too long".to_string(),
+			EndCondition::CharacterSoftlock => "a character softlocked the game".to_string(),
+			EndCondition::AllOrNothingFail => "the max score could no longer be reached".to_string(),
+			EndCondition::AllOrNothingSoftlock => "the team was softlocked in an all or nothing game".to_string(),
+			EndCondition::TerminatedByVote => "the players voted to terminate the game".to_string(),
+			EndCondition::Unknown(code) => format!("unknown end condition {code}"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InterpAction {
 	pub interp: ClueInterp
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Action {
 	#[serde(rename = "status")]
@@ -155,6 +155,8 @@ pub struct ConvData {
 	pub focused: bool,
 	pub urgent: bool,
 	pub trash: bool,
+	/** Whether the card's good touch inferences have been playable since the start of a turn other than its holder's. */
+	pub queued: bool,
 	pub status: CardStatus,
 	pub reasoning: Vec<usize>,
 	pub by: Option<usize>,
@@ -167,6 +169,7 @@ impl ConvData {
 			focused: false,
 			urgent: false,
 			trash: false,
+			queued: false,
 			status: CardStatus::None,
 			reasoning: Vec::new(),
 			by: None,
@@ -199,6 +202,11 @@ impl Thought {
 		}
 	}
 
+	/** Returns the card's identity if only one is possible. */
+	pub fn known_id(&self) -> Option<Identity> {
+		(self.possible.len() == 1).then(|| self.possible.iter().next().unwrap())
+	}
+
 	pub fn possibilities(&self) -> IdentitySet {
 		if self.inferred.is_empty() { self.possible } else { self.inferred }
 	}
@@ -214,8 +222,8 @@ impl Thought {
 
 impl Identifiable for Thought {
 	fn identity(&self, options: &IdOptions) -> Option<Identity> {
-		if self.possible.len() == 1 {
-			return Some(self.possible.iter().next().unwrap())
+		if let Some(id) = self.known_id() {
+			return Some(id);
 		}
 
 		if !options.symmetric && self.base.is_some() {
@@ -225,3 +233,23 @@ impl Identifiable for Thought {
 		(options.infer && self.inferred.len() == 1).then(|| self.inferred.iter().next().unwrap())
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn it_knows_ids_with_one_possibility() {
+		let r1 = Identity { suit_index: 0, rank: 1 };
+		let b3 = Identity { suit_index: 3, rank: 3 };
+
+		let mut thought = Thought::new(0, None, IdentitySet::from_iter([r1, b3]));
+		thought.inferred = IdentitySet::single(r1);
+		assert_eq!(thought.known_id(), None);
+		assert_eq!(thought.identity(&IdOptions { infer: true, ..Default::default() }), Some(r1));
+
+		thought.possible = IdentitySet::single(b3);
+		assert_eq!(thought.known_id(), Some(b3));
+		assert_eq!(thought.identity(&IdOptions::default()), Some(b3));
+	}
+}
@@ -1,4 +1,4 @@
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
 use crate::basics::state::State;
@@ -26,7 +26,19 @@ impl<'de> Deserialize<'de> for ClueKind {
 	}
 }
 
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+impl Serialize for ClueKind {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_u64(match self {
+			ClueKind::COLOUR => 0,
+			ClueKind::RANK => 1,
+		})
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub struct BaseClue {
 	#[serde(rename="type")]
 	pub kind: ClueKind,
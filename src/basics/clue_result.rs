@@ -84,10 +84,8 @@ pub fn bad_touch_result(prev: &Game, game: &Game, giver: usize, target: usize) -
 			continue;
 		}
 
-		if let Some(id) = state.deck[order].id() {
-			if state.is_basic_trash(id) {
-				bad_touch.push(order);
-			}
+		if let Some(id) = state.deck[order].id() && state.is_basic_trash(id) {
+			bad_touch.push(order);
 		}
 	}
 
@@ -100,7 +98,7 @@ pub fn bad_touch_result(prev: &Game, game: &Game, giver: usize, target: usize) -
 		for (i, hand) in state.hands.iter().enumerate() {
 			for &o in hand {
 				let duplicated = (prev.frame().is_touched(o) || game.frame().is_touched(o)) &&
-					game.me().thoughts[o].matches(&state.deck[order], &MatchOptions { infer: true, ..Default::default() }) &&
+					game.us().thoughts[o].matches(&state.deck[order], &MatchOptions { infer: true, ..Default::default() }) &&
 					(i != target || o < order);
 
 				if duplicated {
@@ -122,8 +120,8 @@ pub fn playables_result(prev: &Game, game: &Game) -> PlayablesResult {
 	let mut blind_plays = Vec::new();
 	let mut playables = Vec::new();
 
-	for &order in &game.me().hypo_plays {
-		if prev.me().hypo_plays.contains(&order) {
+	for &order in &game.us().hypo_plays {
+		if prev.us().hypo_plays.contains(&order) {
 			continue;
 		}
 
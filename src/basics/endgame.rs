@@ -1,6 +1,9 @@
+use ahash::AHasher;
 use colored::Colorize;
-use fraction::{ConstOne, ConstZero, GenericFraction};
+use fraction::{ConstOne, ConstZero, GenericFraction, ToPrimitive};
 use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hasher;
 use std::time::{Duration, Instant};
 use itertools::Itertools;
 use log::info;
@@ -21,6 +24,30 @@ type WinnableResult = Result<(Vec<PerformAction>, Frac), &'static str>;
 const UNWINNABLE: WinnableResult = Err("");
 const TIMEOUT: WinnableResult = Err("timeout");
 
+/** Why the endgame solver couldn't return a winning action. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolverError {
+	/** The time limit was reached before the search finished. */
+	Timeout,
+	/** Too many useful identities are unaccounted for to search (e.g. "r5,g5,b5"). */
+	TooManyUnknowns(String),
+	/** Every arrangement was searched and none of the solver's candidate lines reach the max score. */
+	Unwinnable,
+	/** No win was found, but only a sample of the arrangements was searched (Monte Carlo mode). */
+	NoWinFound,
+}
+
+impl fmt::Display for SolverError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SolverError::Timeout => write!(f, "timed out"),
+			SolverError::TooManyUnknowns(ids) => write!(f, "couldn't find any {ids}!"),
+			SolverError::Unwinnable => write!(f, "couldn't find a winning strategy."),
+			SolverError::NoWinFound => write!(f, "couldn't find any winning actions"),
+		}
+	}
+}
+
 pub fn remove_remaining(remaining: &RemainingMap, id: Identity) -> RemainingMap {
 	let RemainingEntry { missing, .. } = &remaining[&id];
 	let mut new_remaining = remaining.clone();
@@ -45,6 +72,7 @@ pub struct EndgameSolver {
 	simple_cache: FastMap<WinnableResult>,
 	simpler_cache: FastMap<bool>,
 	clueless_cache: FastMap<Option<PerformAction>>,
+	known_cache: FastMap<bool>,
 	if_cache: HashMap<String, SimpleResult>,
 	success_rate: Vec<HashMap<PerformAction, (Frac, usize)>>,
 	monte_carlo: bool,
@@ -56,28 +84,47 @@ impl EndgameSolver {
 			simple_cache: FastMap::default(),
 			simpler_cache: FastMap::default(),
 			clueless_cache: FastMap::default(),
+			known_cache: FastMap::default(),
 			if_cache: HashMap::new(),
 			success_rate: Vec::new(),
 			monte_carlo,
 		}
 	}
 
-	pub fn solve_game(&mut self, game: &Game) -> Result<(PerformAction, Frac), String> {
+	/** Solves the endgame with the default time limit of 1 second. */
+	pub fn solve_game_default(&mut self, game: &Game) -> Result<(PerformAction, Frac), SolverError> {
+		self.solve_game(game, Duration::from_millis(1000))
+	}
+
+	pub fn solve_game(&mut self, game: &Game, time_limit: Duration) -> Result<(PerformAction, Frac), SolverError> {
+		self.solve(game, time_limit, false).map(|(line, winrate)| (line[0], winrate))
+	}
+
+	/**
+	 * Returns the sequence of actions that the solver believes leads to the win, starting with our best action.
+	 * Unknown cards (in our hand and in the deck) are assumed to be the most likely identities that keep the game winnable.
+	 */
+	pub fn solve_game_line(&mut self, game: &Game, time_limit: Duration) -> Result<(Vec<PerformAction>, Frac), SolverError> {
+		self.solve(game, time_limit, true)
+	}
+
+	/** Finds our best action, followed by the rest of the winning line if requested (which takes extra searches). */
+	fn solve(&mut self, game: &Game, time_limit: Duration, full_line: bool) -> Result<(Vec<PerformAction>, Frac), SolverError> {
 		let Game { state, .. } = game;
 		if state.score() + 1 == state.max_score() {
 			let winning_play = state.our_hand().iter().find(|&&o|
-				game.me().thoughts[o].identity(&IdOptions { infer: true, ..Default::default() }).is_some_and(|i| state.is_playable(i)));
+				game.us().thoughts[o].identity(&IdOptions { infer: true, ..Default::default() }).is_some_and(|i| state.is_playable(i)));
 
 			if let Some(order) = winning_play {
-				return Ok((PerformAction::Play { target: *order }, Frac::ONE));
+				return Ok((vec![PerformAction::Play { target: *order }], Frac::ONE));
 			}
 		}
 
-		let deadline = Instant::now() + Duration::from_millis(1000);
+		let deadline = Instant::now() + time_limit;
 		let (remaining_ids, own_ids) = find_remaining_ids(game);
 
 		if remaining_ids.iter().filter(|(id, v)| !state.is_basic_trash(**id) && v.all).count() > 2 {
-			return Err(format!("couldn't find any {}!", remaining_ids.keys().filter_map(|i|
+			return Err(SolverError::TooManyUnknowns(remaining_ids.keys().filter_map(|i|
 				(!state.is_basic_trash(*i)).then_some(state.log_id(*i))).join(",")));
 		}
 
@@ -86,7 +133,7 @@ impl EndgameSolver {
 
 		let mut hypo_game = game.clone();
 		let mut unknown_own = Vec::new();
-		let linked_orders = game.me().linked_orders(state);
+		let linked_orders = game.us().linked_orders(state);
 
 		for (order, id) in &own_ids {
 			if let Some(id) = id {
@@ -103,14 +150,20 @@ impl EndgameSolver {
 
 		if total_unknown == 0 {
 			match self.winnable(&hypo_game, state.our_player_index, &remaining_ids, 0, &deadline) {
-				Err(_) => {
+				Err(msg) => {
 					log::set_max_level(level);
-					return Err("couldn't find a winning strategy.".to_owned());
+					return Err(if TIMEOUT == Err(msg) { SolverError::Timeout } else { SolverError::Unwinnable });
 				},
 				Ok((actions, winrate)) => {
+					let line = full_line.then(|| self.extend_line(hypo_game.clone(), remaining_ids, actions[0], state.our_player_index, &deadline));
 					log::set_max_level(level);
 					info!("{}", format!("endgame winnable! found actions {}", actions.iter().map(|action| action.fmt(&hypo_game)).join(",")).purple());
-					return Ok((actions[0], winrate));
+
+					let Some((line, line_str)) = line else {
+						return Ok((vec![actions[0]], winrate));
+					};
+					info!("{}", format!("line: {}", line_str.join(", ")).purple());
+					return Ok((line, winrate));
 				}
 			}
 		}
@@ -129,7 +182,7 @@ impl EndgameSolver {
 
 			remaining.iter().filter_map(|(id, RemainingEntry { missing, .. })| {
 				let order = unknown_own[ids.len()];
-				let thought = &game.me().thoughts[*order];
+				let thought = &game.us().thoughts[*order];
 
 				// Check if this id cannot be assigned to this order
 				let impossible = state.deck[*order].id().is_some_and(|i| i != *id) ||
@@ -140,7 +193,7 @@ impl EndgameSolver {
 					else {
 						!thought.possibilities().is_empty() && !thought.possibilities().iter().any(|i| state.is_basic_trash(i)) &&
 						// We cannot assign a trash id if it is linked and all other orders are already trash
-						(!linked_orders.contains(order) || game.me().links.iter().all(|l| {
+						(!linked_orders.contains(order) || game.us().links.iter().all(|l| {
 							match l {
 								Link::Promised { orders, .. } | Link::Unpromised { orders, .. } => {
 									!orders.contains(order) || orders.iter().all(|o| o == order || (0..ids.len()).any(|i| o == unknown_own[i] && state.is_basic_trash(ids[i])))
@@ -169,7 +222,7 @@ impl EndgameSolver {
 		for _ in 0..unknown_own.len() {
 			if Instant::now() > deadline {
 				log::set_max_level(level);
-				return Err("timed out".to_string());
+				return Err(SolverError::Timeout);
 			}
 			all_arrangements = all_arrangements.iter().flat_map(expand_arr).collect();
 		}
@@ -204,6 +257,15 @@ impl EndgameSolver {
 
 		info!("arrangements {}", arrangements.len());
 
+		let mut likeliest_game = hypo_game.clone();
+		let likeliest_remaining = arrangements.first().map(|Arrangement { ids, remaining, .. }| {
+			for (order, id) in unknown_own.iter().zip(ids) {
+				likeliest_game.state.deck[**order].base = Some(*id);
+				likeliest_game.deck_ids[**order] = Some(*id);
+			}
+			remaining.clone()
+		}).unwrap_or_default();
+
 		let mut best_performs: HashMap<PerformAction, (Frac, usize)> = HashMap::new();
 
 		let mut eval = |e_game: &Game, GameArr { prob, remaining, .. }| {
@@ -237,7 +299,7 @@ impl EndgameSolver {
 			for Arrangement { ids, prob, remaining } in arrangements {
 				if Instant::now() > deadline {
 					log::set_max_level(level);
-					return Err("timed out".to_string());
+					return Err(SolverError::Timeout);
 				}
 
 				let mut hypo = hypo_game.clone();
@@ -252,16 +314,179 @@ impl EndgameSolver {
 			}
 		};
 
-		log::set_max_level(level);
-
 		if best_performs.is_empty() {
-			Err("couldn't find any winning actions".to_owned())
+			log::set_max_level(level);
+
+			// Searches inside each arrangement give up silently at the deadline, so a late failure proves nothing
+			Err(if Instant::now() > deadline {
+				SolverError::Timeout
+			} else if self.monte_carlo {
+				SolverError::NoWinFound
+			} else {
+				SolverError::Unwinnable
+			})
 		}
 		else {
 			let (best_action, (winrate, _)) = best_performs.into_iter().max_by_key(|(_, (winrate, index))| *winrate * 1000 - Frac::new(*index as u64, 1_u64)).unwrap();
+			let line = full_line.then(|| self.extend_line(likeliest_game, likeliest_remaining, best_action, state.our_player_index, &deadline));
+			log::set_max_level(level);
 			info!("endgame winnable! {} (winrate {})", best_action.fmt(game), winrate);
-			Ok((best_action, winrate))
+
+			let Some((line, line_str)) = line else {
+				return Ok((vec![best_action], winrate));
+			};
+			info!("{}", format!("line: {}", line_str.join(", ")).purple());
+			Ok((line, winrate))
+		}
+	}
+
+	/**
+	 * Returns whether the game can still be won, given the identity of every card in the deck (indexed by order).
+	 * Since all future draws are known, no arrangements need to be considered and every action is searched, so the result is exact.
+	 * Returns an error if the search doesn't finish within the time limit.
+	 */
+	pub fn solve_game_known_deck(&mut self, game: &Game, deck: &[Identity], time_limit: Duration) -> Result<bool, SolverError> {
+		let deadline = Instant::now() + time_limit;
+
+		let level = log::max_level();
+		log::set_max_level(log::LevelFilter::Off);
+
+		let mut hypo_game = game.clone();
+		hypo_game.deck_ids = deck.iter().map(|&id| Some(id)).collect();
+
+		for order in hypo_game.state.hands.concat() {
+			hypo_game.state.deck[order].base = Some(deck[order]);
+		}
+
+		let winnable = self.known_winnable(&hypo_game, game.state.current_player_index, deck, &deadline);
+		log::set_max_level(level);
+		winnable
+	}
+
+	fn known_winnable(&mut self, game: &Game, player_turn: usize, deck: &[Identity], deadline: &Instant) -> Result<bool, SolverError> {
+		let Game { state, .. } = game;
+
+		if state.score() == state.max_score() {
+			return Ok(true);
+		}
+
+		if state.ended() {
+			return Ok(false);
+		}
+
+		if Instant::now() >= *deadline {
+			return Err(SolverError::Timeout);
+		}
+
+		// Everything is known, so only the state and whose turn it is matter
+		let mut hasher = AHasher::default();
+		hasher.write_u64(state.hash());
+		hasher.write_usize(player_turn);
+		let hash = hasher.finish();
+
+		if let Some(&winnable) = self.known_cache.get(&hash) {
+			return Ok(winnable);
 		}
+
+		if EndgameSolver::trivially_winnable(game, player_turn).is_ok() {
+			self.known_cache.insert(hash, true);
+			return Ok(true);
+		}
+
+		if EndgameSolver::unwinnable_state(state, player_turn) {
+			self.known_cache.insert(hash, false);
+			return Ok(false);
+		}
+
+		let hand = &state.hands[player_turn];
+		let mut actions = hand.iter().map(|&target| PerformAction::Play { target }).collect::<Vec<_>>();
+
+		if state.can_discard() {
+			actions.extend(hand.iter().map(|&target| PerformAction::Discard { target }));
+		}
+
+		// Every clue only stalls for a turn, so one is enough
+		if state.can_clue() && let Some(target) = (0..state.num_players).find(|&i| i != player_turn && !state.hands[i].is_empty()) {
+			actions.push(PerformAction::Rank { target, value: deck[state.hands[target][0]].rank });
+		}
+
+		let next_player_index = state.next_player_index(player_turn);
+		let mut winnable = false;
+
+		for perform in actions {
+			let new_game = game.simulate_action(&util::perform_to_action(state, &perform, player_turn, Some(deck)), None);
+
+			if new_game.state.max_score() == state.max_score() && self.known_winnable(&new_game, next_player_index, deck, deadline)? {
+				winnable = true;
+				break;
+			}
+		}
+
+		self.known_cache.insert(hash, winnable);
+		Ok(winnable)
+	}
+
+	/**
+	 * Extends the given first action into a full line of play, by repeatedly simulating the best action for each player.
+	 * Draws are assumed to be the most common remaining identity that keeps the game winnable.
+	 * If the solver times out or can no longer find a win, the line found so far is returned.
+	 * Also returns a description of each action in the line.
+	 */
+	fn extend_line(&mut self, mut game: Game, mut remaining: RemainingMap, first: PerformAction, mut player_turn: usize, deadline: &Instant) -> (Vec<PerformAction>, Vec<String>) {
+		let mut line = vec![first];
+		let mut line_str = Vec::new();
+		let mut perform = first;
+
+		loop {
+			let next_player_index = game.state.next_player_index(player_turn);
+			let action = util::perform_to_action(&game.state, &perform, player_turn, None);
+
+			let drawn = if perform.is_clue() || game.state.cards_left == 0 { None } else {
+				remaining.iter()
+					.sorted_by_key(|(id, RemainingEntry { missing, .. })| (std::cmp::Reverse(*missing), id.suit_index, id.rank))
+					.map(|(id, _)| *id)
+					.find(|id| {
+						let new_game = game.simulate_action(&action, Some(*id));
+						new_game.state.max_score() == game.state.max_score() &&
+							self.winnable(&new_game, next_player_index, &remove_remaining(&remaining, *id), 0, deadline).is_ok()
+					})
+			};
+
+			if !perform.is_clue() && game.state.cards_left > 0 {
+				match drawn {
+					Some(id) => remaining = remove_remaining(&remaining, id),
+					None => break
+				}
+			}
+
+			line_str.push(perform.fmt_obj(&game, player_turn));
+			game = game.simulate_action(&action, drawn);
+			player_turn = next_player_index;
+
+			if game.state.ended() {
+				break;
+			}
+
+			perform = match self.winnable(&game, player_turn, &remaining, 0, deadline) {
+				Err(_) => break,
+				Ok((performs, _)) => performs[0]
+			};
+
+			// The clueless solver only returns a placeholder clue, so substitute any real clue
+			if let PerformAction::Rank { value: 0, .. } = perform {
+				match game.convention.find_all_clues(&game, player_turn).first() {
+					Some(clue) => perform = *clue,
+					None => break
+				}
+			}
+			line.push(perform);
+		}
+
+		// The last action wasn't simulated
+		if line_str.len() < line.len() {
+			line_str.push(perform.fmt_obj(&game, player_turn));
+		}
+		(line, line_str)
 	}
 
 	fn winnable(&mut self, game: &Game, player_turn: usize, remaining: &RemainingMap, depth: usize, deadline: &Instant) -> WinnableResult {
@@ -285,7 +510,7 @@ impl EndgameSolver {
 		let mut viable_clueless = true;
 
 		for suit_index in 0..state.variant.suits.len() {
-			for rank in (state.play_stacks[suit_index] + 1)..=state.max_ranks[suit_index] {
+			for rank in (state.play_stacks[suit_index]..state.max_ranks[suit_index]).filter_map(|height| state.next_rank_at(suit_index, height)) {
 				if !state.hands.concat().iter().any(|&o| common.thoughts[o].is(&Identity { suit_index, rank })) {
 					viable_clueless = false;
 					break;
@@ -369,7 +594,7 @@ impl EndgameSolver {
 			};
 		}
 
-		let playables = game.players[player_turn].obvious_playables(&game.frame(), player_turn);
+		let playables = game.frame().playable_orders(&game.players[player_turn], player_turn);
 		for order in playables {
 			if Instant::now() > *deadline {
 				return Vec::new();
@@ -396,9 +621,19 @@ impl EndgameSolver {
 			}
 
 			let default_clue = PerformAction::Rank { target: 0, value: 0 };
-			let too_many_clues = game.state.action_list.concat().iter().rev()
+			let consecutive_clues = game.state.action_list.concat().iter().rev()
 				.take_while(|action| !matches!(action, Action::Play(_) | Action::Discard(_)))
-				.filter(|action| matches!(action, Action::Clue(_))).count() > game.state.num_players;
+				.filter(|action| matches!(action, Action::Clue(_))).count();
+
+			// A round of clues normally gets nowhere, but if no one must play yet and there is pace left,
+			// stalling for as long as the clue tokens allow can still delay a forced discard
+			let must_play = (0..state.num_players).any(|i| !game.frame().playable_orders(&game.players[i], i).is_empty());
+			let max_clues = if !must_play && state.pace() > 0 {
+				state.num_players + state.clue_tokens.floor().to_usize().unwrap_or(0)
+			} else {
+				state.num_players
+			};
+			let too_many_clues = consecutive_clues > max_clues;
 			let clue_winnable = state.can_clue() && !too_many_clues && match solver.winnable_if(state, player_turn, &default_clue, remaining, deadline) {
 				SimpleResult::Unwinnable => false,
 				SimpleResult::AlwaysWinnable => true,
@@ -421,6 +656,17 @@ impl EndgameSolver {
 						break;
 					}
 				}
+
+				// Stalling changes who draws the next card, so consider a clue that only retouches clued cards
+				if state.cards_left > 0 && !actions.iter().any(|(p, _)| p.is_clue()) {
+					let stall = (0..state.num_players).filter(|&i| i != player_turn).flat_map(|i| state.all_valid_clues(i)).find(|clue|
+						state.clue_touched(&state.hands[clue.target], &clue.to_base()).iter().all(|&o| state.deck[o].clued));
+
+					if let Some(clue) = stall && let Some(r) = try_action(solver, util::clue_to_perform(&clue)) {
+						info!("adding stall clue to pass the next draw");
+						actions.push(r);
+					}
+				}
 			}
 		};
 
@@ -429,7 +675,7 @@ impl EndgameSolver {
 				return;
 			}
 
-			if state.pace() > 0 {
+			if state.pace() > 0 && state.can_discard() {
 				for perform in game.convention.find_all_discards(game, player_turn) {
 					match try_action(solver, perform) {
 						None => continue,
@@ -608,34 +854,29 @@ pub struct RemainingEntry {
 
 fn find_remaining_ids(game: &Game) -> (RemainingMap, Vec<(usize, Option<Identity>)>) {
 	let Game { state, .. } = game;
-	let mut seen_ids = HashMap::new();
+	let thoughts = &game.us().thoughts;
+	// The number of cards we know for sure, plus our own cards inferred as each identity
+	let mut seen_ids: HashMap<Identity, usize> = all_ids(&state.variant).map(|id| (id, state.count_in_hands(id, thoughts))).collect();
 	let mut own_ids: Vec<(usize, Option<Identity>)> = Vec::new();
 	let mut infer_ids: HashMap<Identity, Vec<usize>> = HashMap::new();
 
-	for i in 0..state.num_players {
-		for &order in &state.hands[i] {
-			// Identify all the cards we know for sure
-			if let Some(id) = game.me().thoughts[order].id() {
-				seen_ids.entry(id).and_modify(|e| *e += 1).or_insert(1);
-
-				if i == state.our_player_index {
-					own_ids.push((order, Some(id)));
-				}
-			}
-			else if i == state.our_player_index {
-				match game.me().thoughts[order].identity(&IdOptions { infer: true, ..Default::default() }) {
-					Some(id) => {
-						infer_ids.entry(id).and_modify(|e| e.push(order)).or_insert(vec![order]);
-					}
-					None => own_ids.push((order, None))
+	for &order in state.our_hand() {
+		if let Some(id) = thoughts[order].id() {
+			own_ids.push((order, Some(id)));
+		}
+		else {
+			match thoughts[order].identity(&IdOptions { infer: true, ..Default::default() }) {
+				Some(id) => {
+					infer_ids.entry(id).and_modify(|e| e.push(order)).or_insert(vec![order]);
 				}
+				None => own_ids.push((order, None))
 			}
 		}
 	}
 
 	// Check that the inferred ids don't add up to too many
 	for (id, orders) in infer_ids {
-		let seen = seen_ids.get(&id).unwrap_or(&0);
+		let seen = seen_ids[&id];
 		let too_many = seen + orders.len() + state.base_count(id) > state.card_count(id);
 
 		if !too_many {
@@ -651,7 +892,7 @@ fn find_remaining_ids(game: &Game) -> (RemainingMap, Vec<(usize, Option<Identity
 
 	for id in all_ids(&state.variant) {
 		let total = state.card_count(id);
-		let missing = total - state.base_count(id) - seen_ids.get(&id).unwrap_or(&0);
+		let missing = total - state.base_count(id) - seen_ids[&id];
 
 		if missing > 0 {
 			remaining_ids.insert(id, RemainingEntry { missing, all: missing == total });
@@ -125,7 +125,7 @@ impl EndgameSolver {
 
 				for i in 0..endgame_turns {
 					let player_index = (player_turn + i) % state.num_players;
-					let playables = game.players[player_index].obvious_playables(&game.frame(), player_index);
+					let playables = game.frame().playable_orders(&game.players[player_index], player_index);
 
 					if playables.is_empty() {
 						continue;
@@ -137,7 +137,7 @@ impl EndgameSolver {
 							if i == 0 {
 								action = PerformAction::Play { target: playables[0] };
 							}
-							play_stacks[id.suit_index] = id.rank;
+							play_stacks[id.suit_index] += 1;
 						}
 					}
 				}
@@ -347,15 +347,15 @@ impl EndgameSolver {
 					None => new_state.strikes += 1,
 					Some(id) => {
 						if state.is_playable(*id) {
-							new_state.play_stacks[id.suit_index] = id.rank;
+							new_state.play_to_stack(*id);
 
-							if id.rank == 5 {
+							if state.regains_clue(id.rank) {
 								new_state.regain_clue();
 							}
 						}
 						else {
 							new_state.strikes += 1;
-							new_state.discard_stacks[id.suit_index][id.rank - 1].push(*target);
+							new_state.discard_to_stack(*target, *id);
 						}
 					}
 				}
@@ -363,15 +363,15 @@ impl EndgameSolver {
 			}
 			PerformAction::Discard { target, .. } => {
 				if let Some(id) = state.deck[*target].id() {
-					new_state.discard_stacks[id.suit_index][id.rank - 1].push(*target);
+					new_state.discard_to_stack(*target, id);
 				}
 
 				new_state.regain_clue();
 				remove_and_draw_new(&mut new_state, player_index, *target);
 			}
 			PerformAction::Colour { .. } | PerformAction::Rank {.. } => {
-				new_state.clue_tokens -= 1;
-				new_state.endgame_turns = new_state.endgame_turns.map(|turns| turns - 1);
+				new_state.spend_clue();
+				new_state.endgame_turns = new_state.endgame_turns.map(|turns| turns.saturating_sub(1));
 			}
 			_ => {}
 		}
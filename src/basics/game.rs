@@ -1,20 +1,23 @@
 use ahash::AHasher;
 use colored::Colorize;
+use fraction::Fraction;
 use itertools::Itertools;
-use log::{info, LevelFilter};
-use serde_json::json;
+use log::{info, warn, LevelFilter};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt::{self, Display, Formatter};
 use std::hash::Hasher;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use crate::basics::{self, on_draw};
-use crate::basics::action::{Action, ClueAction, DiscardAction,DrawAction, InterpAction, PerformAction, PlayAction, TurnAction};
-use crate::basics::card::{CardStatus, ConvData, Identifiable, Identity};
+use crate::basics::action::{Action, ClueAction, DiscardAction,DrawAction, EndCondition, GameOverAction, InterpAction, PerformAction, PlayAction, StatusAction, StrikeAction, TurnAction};
+use crate::basics::card::{CardStatus, ConvData, Identity};
 use crate::basics::identity_set::IdentitySet;
 use crate::basics::player::{Link, Player};
 use crate::basics::util::FastMap;
 use crate::reactor::{ClueInterp, ReactorInterp};
 use super::state::State;
-use super::variant::all_ids;
+use super::variant::{all_ids, Variant};
 use self::frame::Frame;
 
 pub mod frame;
@@ -26,9 +29,25 @@ pub enum Interp {
 
 #[derive(Debug, Clone, Default)]
 pub struct Note {
-	pub turn: usize,
-	pub last: String,
-	pub full: String,
+	/** The note written on each turn the card's information changed, as (turn, note) pairs in order. */
+	pub entries: Vec<(usize, String)>,
+}
+
+impl Note {
+	/** Returns the turn the latest note was written on. */
+	pub fn turn(&self) -> usize {
+		self.entries.last().map_or(0, |(turn, _)| *turn)
+	}
+
+	/** Returns the latest note. */
+	pub fn last(&self) -> &str {
+		self.entries.last().map_or("", |(_, note)| note)
+	}
+
+	/** Returns the note's full history, as written on the card. */
+	pub fn full(&self) -> String {
+		self.entries.iter().map(|(turn, note)| format!("t{turn}: {note}")).join(" | ")
+	}
 }
 
 #[derive(Debug, Clone, Default)]
@@ -36,8 +55,28 @@ pub struct SimOpts {
 	pub free: bool,
 	pub log: bool,
 	pub no_recurse: bool,
+	/** Whether to build notes in the simulated game. Simulations only need the resulting state, so this is off by default. */
+	pub notes: bool,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedGame {
+	variant: String,
+	our_player_index: usize,
+	player_names: Vec<String>,
+	action_list: Vec<Vec<Action>>,
+}
+
+/** A simulated clue's interpretation and value. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedClue {
+	pub interp: Option<Interp>,
+	pub value: f32,
+}
+
+pub type ClueCache = FastMap<CachedClue>;
+
 #[derive(Clone)]
 pub struct Game {
 	pub table_id: u32,
@@ -55,10 +94,55 @@ pub struct Game {
 	pub queued_cmds: Vec<(String, String)>,
 	pub next_interp: Option<ClueInterp>,
 	pub no_recurse: bool,
+	/** Whether to skip building notes, as in hypothetical games. */
+	pub no_notes: bool,
+	pub verbose_notes: bool,
+	/** Whether to terminate the game once the endgame solver proves it lost and any action risks a strikeout. Only set at tables of bots. */
+	pub auto_terminate: bool,
+	/** Interpretations and values of simulated clues, keyed by [`Game::clue_key`]. Shared with hypothetical games. */
+	pub clue_cache: Arc<Mutex<ClueCache>>,
+	/** How many nested rewinds are allowed before giving up. */
+	pub max_rewind_depth: usize,
+	/** The convention level to play at. Techniques above this level are neither given nor expected. */
+	pub level: u8,
+	/** Why the game ended, once it is over. */
+	pub end_condition: Option<EndCondition>,
+	/** Whether to never discard a card that might be critical while a stall clue is available, for cautious teammates. */
+	pub safe_mode: bool,
 	rewind_depth: usize
 }
 
+/** A summary of how a game went, for reporting at the end of the game. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameResult {
+	pub score: usize,
+	pub max_score: usize,
+	pub strikes: u8,
+	pub turns: usize,
+	/** Why the game ended early, if it didn't end normally. */
+	pub loss_reason: Option<EndCondition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RewindError {
+	InvalidTurn(usize),
+	AlreadyRewound,
+	TooDeep,
+}
+
+impl Display for RewindError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			RewindError::InvalidTurn(turn) => write!(f, "attempted to rewind to invalid turn {turn}"),
+			RewindError::AlreadyRewound => write!(f, "action was already rewinded"),
+			RewindError::TooDeep => write!(f, "rewind depth went too deep"),
+		}
+	}
+}
+
 const HAND_SIZE: [usize; 7] = [0, 0, 5, 5, 4, 4, 3];
+/** The most possible worlds [`Game::possible_worlds_count`] will count before giving up. */
+pub const MAX_WORLDS: u64 = 1_000_000_000;
 
 impl Game {
 	pub fn new(table_id: u32, state: State, in_progress: bool, convention: Arc<dyn Convention + Send + Sync>) -> Self {
@@ -87,6 +171,14 @@ impl Game {
 			queued_cmds: Vec::new(),
 			next_interp: None,
 			no_recurse: false,
+			no_notes: false,
+			verbose_notes: false,
+			auto_terminate: false,
+			clue_cache: Arc::default(),
+			max_rewind_depth: 3,
+			level: u8::MAX,
+			end_condition: None,
+			safe_mode: false,
 			rewind_depth: 0
 		}
 	}
@@ -98,7 +190,14 @@ impl Game {
 		if keep_deck {
 			new_game.deck_ids = self.deck_ids.clone();
 		}
+		// Keep the original base, so that blanking a blank game (e.g. in a nested rewind) still starts from it
+		new_game.base = Arc::clone(&self.base);
 		new_game.meta = meta.clone();
+		new_game.verbose_notes = self.verbose_notes;
+		new_game.auto_terminate = self.auto_terminate;
+		new_game.max_rewind_depth = self.max_rewind_depth;
+		new_game.level = self.level;
+		new_game.safe_mode = self.safe_mode;
 		new_game.players = players.clone();
 		new_game.common = common.clone();
 		new_game
@@ -128,19 +227,104 @@ impl Game {
 		hasher.finish()
 	}
 
-	pub fn frame(&self) -> Frame {
+	/** Returns the key for a clue in this game's clue cache. */
+	pub fn clue_key(&self, action: &ClueAction) -> u64 {
+		let mut hasher = AHasher::default();
+
+		hasher.write_u64(self.hash());
+		hasher.write_u64(Action::Clue(action.clone()).hash());
+		hasher.write_u8(self.no_recurse as u8);
+		// The level changes how clues are interpreted, and can be changed mid-game
+		hasher.write_u8(self.level);
+
+		hasher.finish()
+	}
+
+	/** Serializes the game's setup and actions, so that it can be rebuilt with [`Game::from_json`]. */
+	pub fn to_json(&self) -> Value {
+		json!({
+			"variant": self.state.variant.name,
+			"ourPlayerIndex": self.state.our_player_index,
+			"playerNames": self.state.player_names,
+			"actionList": *self.state.action_list,
+		})
+	}
+
+	/** Rebuilds a game serialized with [`Game::to_json`] by replaying all of its actions. */
+	pub fn from_json(json: &Value, variant: Arc<Variant>, convention: Arc<dyn Convention + Send + Sync>) -> Result<Self, String> {
+		let SavedGame { variant: variant_name, our_player_index, player_names, action_list } = SavedGame::deserialize(json).map_err(|err| err.to_string())?;
+
+		if variant_name != variant.name {
+			return Err(format!("saved game has variant {variant_name}, but was given {}", variant.name));
+		}
+
+		let state = State::new(player_names, our_player_index, variant);
+		let mut game = Game::new(0, state, false, convention);
+
+		let level = log::max_level();
+		log::set_max_level(LevelFilter::Off);
+
+		game.catchup = true;
+		for action in action_list.iter().flatten() {
+			game.handle_action(action);
+		}
+		game.catchup = false;
+
+		log::set_max_level(level);
+		Ok(game)
+	}
+
+	pub fn frame(&self) -> Frame<'_> {
 		Frame::new(&self.state, &self.meta)
 	}
 
-	pub fn me(&self) -> &Player {
+	/**
+	 * Returns the player whose turn it is.
+	 * This is only us on our own turn, so use [`Game::us`] for our own view of the game.
+	 */
+	pub fn current_player(&self) -> &Player {
 		&self.players[self.state.current_player_index]
 	}
 
-	pub fn me_mut(&mut self) -> &mut Player {
-		&mut self.players[self.state.current_player_index]
+	/** Returns our own view of the game, regardless of whose turn it is. */
+	pub fn us(&self) -> &Player {
+		&self.players[self.state.our_player_index]
+	}
+
+	/** Returns whether it is currently our turn. */
+	pub fn is_our_turn(&self) -> bool {
+		self.state.current_player_index == self.state.our_player_index
+	}
+
+	/** Summarises the current score and, if the game is over, why it ended early. */
+	pub fn result(&self) -> GameResult {
+		GameResult {
+			score: self.state.score(),
+			max_score: self.state.max_score(),
+			strikes: self.state.strikes,
+			turns: self.state.turn_count,
+			loss_reason: self.end_condition.filter(|cond| *cond != EndCondition::Normal),
+		}
+	}
+
+	/**
+	 * Returns the number of ways our unresolved cards could be assigned identities, from our own possibilities (capped at [`MAX_WORLDS`]).
+	 * Cards are counted independently, so this is a rough measure of how constrained our hand is.
+	 */
+	pub fn possible_worlds_count(&self) -> u64 {
+		let player = self.us();
+
+		self.state.our_hand().iter().map(|&o| player.thoughts[o].possible.len() as u64)
+			.filter(|&len| len > 1)
+			.fold(1, |acc, len| acc.saturating_mul(len).min(MAX_WORLDS))
 	}
 
 	pub fn handle_action(&mut self, action: &Action) {
+		// Hypothetical games are always in catchup, so only real actions invalidate the cache
+		if !self.catchup {
+			self.clue_cache.lock().unwrap().clear();
+		}
+
 		let prev = &self.clone();
 		let action_list = Arc::make_mut(&mut self.state.action_list);
 		if action_list.len() <= self.state.turn_count {
@@ -171,15 +355,21 @@ impl Game {
 					self.state.turn_count += 1;
 				}
 			},
-			Action::GameOver(_) => {
+			Action::GameOver(GameOverAction { end_condition, .. }) => {
 				self.in_progress = false;
-				info!("Game over!");
+
+				let end_condition = EndCondition::from(*end_condition);
+				self.end_condition = Some(end_condition);
+
+				let GameResult { score, max_score, .. } = self.result();
+				info!("Game over! Ended because {}, with a score of {score}/{max_score}.", end_condition.reason());
 			}
 			Action::Turn(turn) => {
 				let TurnAction { num, current_player_index } = turn;
 
 				if *current_player_index >= 0 {
 					self.state.current_player_index = *current_player_index as usize;
+					self.queue_playables(*current_player_index as usize);
 				}
 				self.state.turn_count = num + 1;
 
@@ -189,7 +379,46 @@ impl Game {
 			Action::Interp(InterpAction { interp }) => {
 				self.next_interp = Some(interp.clone());
 			}
-			_ => (),
+			Action::Strike(StrikeAction { num, order, .. }) => {
+				// The strike is authoritative, even if we missed (or already counted) the failed discard
+				self.state.strikes = *num as u8;
+
+				if let Some(id) = self.deck_ids[*order] {
+					let thought = &mut self.common.thoughts[*order];
+					thought.possible = IdentitySet::single(id);
+					thought.inferred = IdentitySet::single(id);
+				}
+			}
+			Action::Status(StatusAction { clues, score, .. }) => {
+				// The server's counts are authoritative, so correct any drift in our own
+				let clue_tokens = Fraction::from(*clues) * self.state.clue_unit();
+				if self.state.clue_tokens != clue_tokens {
+					warn!("clue tokens out of sync! expected {clue_tokens}, found {}", self.state.clue_tokens);
+					self.state.clue_tokens = clue_tokens;
+				}
+
+				if self.state.score() != *score {
+					warn!("score out of sync! expected {score}, found {}", self.state.score());
+				}
+			}
+		}
+	}
+
+	/** Queues good touch playables at the start of a turn, in every hand other than the current player's. */
+	fn queue_playables(&mut self, current_player_index: usize) {
+		let Game { common, state, meta, .. } = self;
+
+		for (i, hand) in state.hands.iter().enumerate() {
+			if i == current_player_index {
+				continue;
+			}
+
+			for &order in hand {
+				let thought = &common.thoughts[order];
+				if state.deck[order].clued && !thought.possibilities().is_empty() && thought.possibilities().iter().all(|i| state.is_playable(i)) {
+					meta[order].queued = true;
+				}
+			}
 		}
 	}
 
@@ -206,6 +435,7 @@ impl Game {
 	pub fn simulate_clean(&self) -> Self {
 		let mut hypo_game = self.clone();
 		hypo_game.catchup = true;
+		hypo_game.no_notes = true;
 		hypo_game
 	}
 
@@ -224,6 +454,7 @@ impl Game {
 		if options.no_recurse {
 			hypo_game.no_recurse = true;
 		}
+		hypo_game.no_notes = !options.notes;
 
 		let copy = if !options.free { self } else { &hypo_game.clone() };
 		hypo_game.handle_clue(copy, action);
@@ -274,19 +505,19 @@ impl Game {
 		hypo_game
 	}
 
-	pub fn rewind(&self, turn: usize, rewind_action: Action) -> Result<Self, String> {
+	pub fn rewind(&self, turn: usize, rewind_action: Action) -> Result<Self, RewindError> {
 		if turn < 1 || turn > self.state.action_list.len() + 1 {
-			return Err(format!("attempted to rewind to invalid turn {turn}"));
+			return Err(RewindError::InvalidTurn(turn));
 		}
 
 		info!("{}", format!("Rewinding to insert {rewind_action:?} on turn {turn}!").blue());
 
 		if self.state.action_list[turn].contains(&rewind_action) {
-			return Err("action was already rewinded".to_owned());
+			return Err(RewindError::AlreadyRewound);
 		}
 
-		if self.rewind_depth > 2 {
-			return Err("rewind depth went too deep".to_owned());
+		if self.rewind_depth >= self.max_rewind_depth {
+			return Err(RewindError::TooDeep);
 		}
 
 		info!("{}", "------- STARTING REWIND -------".green());
@@ -316,7 +547,15 @@ impl Game {
 		info!("{}", "------- REWIND COMPLETE -------".green());
 		new_game.catchup = self.catchup;
 
-		new_game.notes = self.notes.clone();
+		// Keep the note history from before the rewind, followed by the history from the new timeline
+		let orders = self.notes.keys().chain(new_game.notes.keys()).copied().unique().collect::<Vec<_>>();
+		new_game.notes = orders.into_iter().filter_map(|order| {
+			let old_entries = self.notes.get(&order).into_iter().flat_map(|note| &note.entries).filter(|(t, _)| *t < turn);
+			let new_entries = new_game.notes.get(&order).into_iter().flat_map(|note| &note.entries).filter(|(t, _)| *t >= turn);
+
+			let entries = old_entries.chain(new_entries).cloned().collect::<Vec<_>>();
+			(!entries.is_empty()).then_some((order, Note { entries }))
+		}).collect();
 
 		for card in &mut new_game.state.deck {
 			if card.base.is_none() && let Some(id) = self.deck_ids[card.order] {
@@ -363,7 +602,7 @@ impl Game {
 
 		new_game.catchup = self.catchup;
 
-		if !new_game.catchup && new_game.state.current_player_index == new_game.state.our_player_index {
+		if !new_game.catchup && new_game.is_our_turn() {
 			let perform = new_game.take_action();
 			info!("{}", format!("Suggested action: {}", perform.fmt(&new_game)).blue());
 		}
@@ -371,19 +610,83 @@ impl Game {
 		new_game
 	}
 
+	/** Returns the game after replaying the first `flat_index` actions of the flattened action list, for stepping through a replay action by action. */
+	pub fn navigate_to_action(&self, flat_index: usize) -> Self {
+		info!("{}", format!("------- NAVIGATING (action {flat_index}) -------").green());
+
+		let mut new_game = self.blank(false);
+		let actions = &self.state.action_list;
+
+		// Cards already drawn in the base state won't be drawn again
+		new_game.deck_ids = self.deck_ids[..new_game.state.deck.len()].to_vec();
+
+		let level = log::max_level();
+		log::set_max_level(LevelFilter::Off);
+
+		for action in actions.concat().iter().take(flat_index) {
+			match action {
+				Action::Interp(_) => continue,
+				Action::Draw(DrawAction { order, player_index, .. })
+					if new_game.state.hands[*player_index].contains(order) => continue,
+				_ => new_game.handle_action(action),
+			}
+		}
+
+		log::set_max_level(level);
+
+		new_game.catchup = self.catchup;
+		new_game.state.action_list = actions.clone();
+		new_game
+	}
+
+	/** Returns the game replayed from the given seat, hiding that seat's draws. Used when spectating while shadowing a player. */
+	pub fn shadow(&self, player_index: usize) -> Self {
+		info!("{}", format!("------- SHADOWING {} -------", self.state.player_names[player_index]).green());
+
+		let state = State::new(self.state.player_names.clone(), player_index, Arc::clone(&self.state.variant));
+		let mut new_game = Game::new(self.table_id, state, self.in_progress, Arc::clone(&self.convention));
+		new_game.verbose_notes = self.verbose_notes;
+		new_game.auto_terminate = self.auto_terminate;
+		new_game.max_rewind_depth = self.max_rewind_depth;
+		new_game.level = self.level;
+		new_game.safe_mode = self.safe_mode;
+		new_game.catchup = true;
+
+		let level = log::max_level();
+		log::set_max_level(LevelFilter::Off);
+
+		for action in self.state.action_list.concat() {
+			match action {
+				Action::Interp(_) => continue,
+				Action::Draw(draw) if draw.player_index == player_index =>
+					new_game.handle_action(&Action::Draw(DrawAction { suit_index: -1, rank: -1, ..draw })),
+				_ => new_game.handle_action(&action),
+			}
+		}
+
+		log::set_max_level(level);
+		new_game.catchup = self.catchup;
+		new_game
+	}
+
 	pub fn update_notes(&mut self) {
-		let Game { common, state, meta, notes, .. } = self;
+		if self.no_notes {
+			return;
+		}
+
+		let Game { common, state, meta, notes, verbose_notes, .. } = self;
 
 		for order in state.hands.concat() {
 			let frame = Frame::new(state, meta);
 			let card = &state.deck[order];
 			let meta = &meta[order];
 
-			if !card.clued && meta.status == CardStatus::None {
+			// Unclued cards only get a note if they have a status or are part of a waiting connection
+			if !card.clued && meta.status == CardStatus::None && frame.waiting_note(common, order).is_none() {
 				continue;
 			}
 
-			let mut note: String = frame.get_note(common, order);
+			let mut note: String = frame.get_note(common, order, *verbose_notes);
 			if note.is_empty() {
 				continue;
 			}
@@ -404,25 +707,18 @@ impl Game {
 
 			let prev_note = notes.get(&(order as u64));
 			let write_note = match prev_note {
-				Some(prev_note) => note != prev_note.last && state.turn_count > prev_note.turn,
+				Some(prev_note) => note != prev_note.last() && state.turn_count > prev_note.turn(),
 				None => true
 			};
 
 			if write_note {
-				let prev_note = notes.remove(&(order as u64));
-				let mut full = prev_note.map(|n| format!("{} | ", n.full)).unwrap_or_else(|| "".to_owned());
-				full.push_str(&format!("t{}: {note}", state.turn_count));
-				let new_note = Note {
-					last: note,
-					turn: state.turn_count,
-					full: full.to_string()
-				};
-				notes.insert(order as u64, new_note);
+				let new_note = notes.entry(order as u64).or_default();
+				new_note.entries.push((state.turn_count, note));
 
 				if !self.catchup && self.in_progress {
 					self.queued_cmds.push((
 						"note".to_string(),
-						json!({ "tableID": self.table_id, "order": order, "note": full }).to_string()
+						json!({ "tableID": self.table_id, "order": order, "note": new_note.full() }).to_string()
 					));
 				}
 			}
@@ -439,4 +735,88 @@ pub trait Convention {
 
 	fn find_all_clues(&self, game: &Game, player_index: usize) -> Vec<PerformAction>;
 	fn find_all_discards(&self, game: &Game, player_index: usize) -> Vec<PerformAction>;
+	fn find_all_fixes(&self, game: &Game, player_index: usize) -> Vec<PerformAction>;
+
+	/** Returns a short explanation of why the action would be taken, suitable for sending in chat. */
+	fn explain_action(&self, game: &Game, perform: &PerformAction) -> String;
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::basics::clue::{BaseClue, ClueKind};
+	use crate::basics::test_util::{no_variant, pre_clue, setup, take_turn};
+	use crate::reactor::Reactor;
+
+	fn empty_game() -> Game {
+		let state = State::new(vec!["Alice".to_owned(), "Bob".to_owned()], 0, Arc::new(no_variant()));
+		Game::new(0, state, false, Arc::new(Reactor))
+	}
+
+	#[test]
+	fn it_round_trips_a_saved_game() {
+		let mut game = setup(&[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["r1", "y3", "g4", "b2", "p3"],
+			&["y1", "g2", "b4", "r3", "p4"],
+		]);
+		let [alice, bob] = [0, 1].map(|i| game.state.hands[i].clone());
+
+		// A clue, a play and a discard of one of our own cards, so that the saved actions include hidden draws
+		take_turn(&mut game, Action::clue(0, 1, BaseClue { kind: ClueKind::RANK, value: 1 }, vec![bob[0]]), None);
+		take_turn(&mut game, Action::play(1, bob[0], 0, 1), Some("g1"));
+		take_turn(&mut game, Action::clue(2, 1, BaseClue { kind: ClueKind::RANK, value: 3 }, vec![bob[1], bob[4]]), None);
+		take_turn(&mut game, Action::discard(0, alice[4], 1, 4, false), Some("xx"));
+
+		let json = game.to_json();
+		let loaded = Game::from_json(&json, game.state.variant.clone(), Arc::new(Reactor)).unwrap();
+
+		assert_eq!(loaded.hash(), game.hash());
+		assert_eq!(loaded.to_json(), json);
+	}
+
+	#[test]
+	fn it_summarises_a_finished_game() {
+		let mut game = empty_game();
+		game.state.play_stacks = vec![5, 5, 5, 5, 4];
+		game.state.strikes = 1;
+		game.state.turn_count = 40;
+		game.handle_action(&Action::game_over(1, 1));
+
+		assert_eq!(game.result(), GameResult { score: 24, max_score: 25, strikes: 1, turns: 40, loss_reason: None });
+	}
+
+	#[test]
+	fn it_summarises_a_struck_out_game() {
+		let mut game = empty_game();
+		game.state.play_stacks = vec![2, 3, 0, 1, 0];
+		game.state.max_ranks = vec![5, 3, 5, 5, 5];
+		game.state.strikes = 3;
+		game.state.turn_count = 12;
+		game.handle_action(&Action::game_over(2, 1));
+
+		// The max score is lowered by the yellow stack only reaching 3
+		assert_eq!(game.result(), GameResult { score: 6, max_score: 23, strikes: 3, turns: 12, loss_reason: Some(EndCondition::Strikeout) });
+	}
+
+	#[test]
+	fn it_counts_possible_worlds() {
+		let mut game = setup(&[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["r2", "y2", "g3", "p3", "y3"],
+			&["r3", "g4", "p2", "y4", "r4"],
+		]);
+		let [colour, rank] = [ClueKind::COLOUR, ClueKind::RANK].map(|kind| move |value| BaseClue { kind, value });
+
+		// Alice has r1, a 5, a blue card, g2 and a 4.
+		pre_clue(&mut game, 0, 1, &[colour(0), rank(1)]);
+		pre_clue(&mut game, 0, 2, &[rank(5)]);
+		pre_clue(&mut game, 0, 3, &[colour(3)]);
+		pre_clue(&mut game, 0, 4, &[colour(2), rank(2)]);
+		pre_clue(&mut game, 0, 5, &[rank(4)]);
+		basics::elim(&mut game, true);
+
+		// The two fully known cards don't add any worlds.
+		assert_eq!(game.possible_worlds_count(), 5 * 5 * 5);
+	}
 }
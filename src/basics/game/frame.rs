@@ -1,4 +1,6 @@
-use crate::basics::{card::{CardStatus, ConvData}, player::Player, state::State};
+use itertools::Itertools;
+
+use crate::basics::{card::{CardStatus, ConvData}, player::{Player, WaitingConnection}, state::State};
 
 pub struct Frame<'a> {
 	pub state: &'a State,
@@ -24,7 +26,71 @@ impl<'a> Frame<'a> {
 		!state.deck[order].clued && (status == CardStatus::CalledToPlay)
 	}
 
-	pub fn get_note(&self, common: &Player, order: usize) -> String {
+	/** Returns the orders in the player's hand that the given perspective knows are playable. */
+	pub fn playable_orders(&self, perspective: &Player, player_index: usize) -> Vec<usize> {
+		self.state.hands[player_index].iter().filter(|&&order|
+			perspective.known_playable(self, order) ||
+				perspective.thoughts[order].info_lock.is_some_and(|ids| ids.iter().all(|id| self.state.is_playable(id)))
+		).copied().collect()
+	}
+
+	/** Returns the orders in the player's hand that the given perspective knows are trash. */
+	pub fn trash_orders(&self, perspective: &Player, player_index: usize) -> Vec<usize> {
+		self.state.hands[player_index].iter().filter(|&&order| perspective.order_trash(self, order)).copied().collect()
+	}
+
+	/** Returns the player's chop: a zero clue chop if there is one, otherwise their newest unclued card with no status. */
+	pub fn chop(&self, player_index: usize) -> Option<usize> {
+		let Frame { state, meta } = self;
+
+		if let Some(&zcs) = state.hands[player_index].iter().find(|&&o| meta[o].status == CardStatus::ZeroClueChop) {
+			return Some(zcs);
+		}
+		state.hands[player_index].iter().find(|&&o| !state.deck[o].clued && meta[o].status == CardStatus::None).copied()
+	}
+
+	/** Returns whether the card is on its holder's chop. */
+	pub fn is_chop(&self, order: usize) -> bool {
+		self.state.hands.iter().position(|hand| hand.contains(&order)).is_some_and(|player_index| self.chop(player_index) == Some(order))
+	}
+
+	/** Returns the note for the given card. If verbose, appends the turns on which the card's information changed. */
+	pub fn get_note(&self, common: &Player, order: usize, verbose: bool) -> String {
+		let mut note = self.base_note(common, order);
+		if let Some(waiting_note) = self.waiting_note(common, order) {
+			note = if note.is_empty() { waiting_note } else { format!("{note}, {waiting_note}") };
+		}
+		let reasoning = &self.meta[order].reasoning;
+
+		if !verbose || reasoning.is_empty() {
+			return note;
+		}
+
+		let trail = format!("({})", reasoning.iter().map(|turn| format!("t{turn}")).join(","));
+		if note.is_empty() { trail } else { format!("{note} {trail}") }
+	}
+
+	/**
+	 * Returns the note for a card in the current waiting connection, if any:
+	 * the reacter's card that is expected to react, or the receiver's card that it targets.
+	 */
+	pub fn waiting_note(&self, common: &Player, order: usize) -> Option<String> {
+		let wc = common.waiting.as_ref()?;
+		let Frame { state, meta } = self;
+		let reacting = |o: usize| matches!(meta[o].status, CardStatus::CalledToPlay | CardStatus::CalledToDiscard) && meta[o].reasoning.last() == Some(&wc.turn);
+
+		if let Some(index) = state.hands[wc.reacter].iter().position(|&o| o == order) {
+			return reacting(order).then(|| format!("react: slot {}", WaitingConnection::calc_slot(state.hand_size(), wc.focus_slot, index + 1)));
+		}
+
+		let target_slot = wc.receiver_hand.iter().position(|&o| o == order)? + 1;
+		let react_slot = WaitingConnection::calc_slot(state.hand_size(), wc.focus_slot, target_slot);
+		let react_order = *state.hands[wc.reacter].get(react_slot - 1)?;
+
+		reacting(react_order).then(|| format!("target of {} slot {react_slot}", state.player_names[wc.reacter]))
+	}
+
+	fn base_note(&self, common: &Player, order: usize) -> String {
 		if self.meta[order].trash {
 			return "kt".to_string();
 		}
@@ -42,16 +108,18 @@ impl<'a> Frame<'a> {
 		};
 
 		match self.meta[order].status {
-			CardStatus::CalledToPlay => {
-				format!("[f]{}", if note.is_empty() { String::new() } else { format!(" [{note}]") })
-			}
-			CardStatus::ChopMoved => {
-				format!("[cm]{}", if note.is_empty() { String::new() } else { format!(" [{note}]") })
-			}
+			CardStatus::CalledToPlay => tagged_note("f", &note),
+			CardStatus::ChopMoved => tagged_note("cm", &note),
 			CardStatus::CalledToDiscard => {
 				"dc".to_string()
 			}
-			_ => note
+			CardStatus::ZeroClueChop => tagged_note("zcs", &note),
+			CardStatus::None => note
 		}
 	}
 }
+
+/** Prefixes a note with a status tag, e.g. "[f] [r1,y1]". */
+fn tagged_note(tag: &str, note: &str) -> String {
+	if note.is_empty() { format!("[{tag}]") } else { format!("[{tag}] [{note}]") }
+}
@@ -97,7 +97,7 @@ impl FromIterator<Identity> for IdentitySet {
 		let mut val = 0;
 
 		for id in iter {
-			val += 1 << id.to_ord();
+			val |= 1 << id.to_ord();
 		}
 
 		IdentitySet(val)
@@ -132,6 +132,25 @@ impl ExactSizeIterator for IdentitySetIter {
 #[cfg(test)]
 mod test {
 	use super::*;
+	use rand::{Rng, SeedableRng};
+	use rand_chacha::ChaCha8Rng;
+	use std::collections::HashSet;
+
+	fn all_ids() -> impl Iterator<Item = Identity> {
+		(0..30).map(Identity::from_ord)
+	}
+
+	fn random_ids(rng: &mut ChaCha8Rng) -> HashSet<Identity> {
+		all_ids().filter(|_| rng.random_bool(0.5)).collect()
+	}
+
+	fn to_set(ids: &HashSet<Identity>) -> IdentitySet {
+		IdentitySet::from_iter(ids.iter().copied())
+	}
+
+	fn to_hash_set(ids: IdentitySet) -> HashSet<Identity> {
+		ids.iter().collect()
+	}
 
 	#[test]
 	fn it_inserts() {
@@ -176,4 +195,33 @@ mod test {
 		assert_eq!(ids.len(), 25);
 		assert_eq!(new_ids.len(), 11);
 	}
+
+	#[test]
+	fn it_froms_duplicates() {
+		let id = Identity { suit_index: 1, rank: 3 };
+		let ids = IdentitySet::from_iter(vec![id, id]);
+
+		assert_eq!(ids, IdentitySet::single(id));
+	}
+
+	#[test]
+	fn it_matches_set_algebra() {
+		let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+		for _ in 0..1000 {
+			let (a, b) = (random_ids(&mut rng), random_ids(&mut rng));
+			let (set_a, set_b) = (to_set(&a), to_set(&b));
+
+			assert_eq!(to_hash_set(set_a.intersect(&set_b)), a.intersection(&b).copied().collect());
+			assert_eq!(to_hash_set(set_a.union(&set_b)), a.union(&b).copied().collect());
+			assert_eq!(to_hash_set(set_a.difference(&set_b)), a.difference(&b).copied().collect());
+			assert_eq!(to_hash_set(set_a.filter(|i| b.contains(&i))), a.intersection(&b).copied().collect());
+
+			let mut retained = set_a;
+			retained.retain(|i| !b.contains(&i));
+			assert_eq!(to_hash_set(retained), a.difference(&b).copied().collect());
+			assert_eq!(set_a.len(), a.len());
+		}
+	}
+
 }
@@ -35,6 +35,14 @@ pub struct WaitingConnection {
 	pub turn: usize
 }
 
+impl WaitingConnection {
+	/** Returns the slot that pairs with the given slot, such that the two slots sum to the focus slot (modulo the hand size). */
+	pub fn calc_slot(hand_size: usize, focus_slot: usize, slot: usize) -> usize {
+		let other = (focus_slot + hand_size - slot) % hand_size;
+		if other == 0 { hand_size } else { other }
+	}
+}
+
 #[derive(Debug, Clone)]
 pub struct Player {
 	pub player_index: usize,
@@ -48,6 +56,8 @@ pub struct Player {
 
 	pub unknown_plays: AHashSet<usize>,
 	pub hypo_plays: AHashSet<usize>,
+	/** The orders of the hypo plays, in the order they were found to be playable. */
+	pub hypo_play_order: Vec<usize>,
 
 	pub waiting: Option<WaitingConnection>,
 
@@ -67,6 +77,7 @@ impl Player {
 			links: Vec::new(),
 			unknown_plays: AHashSet::new(),
 			hypo_plays: AHashSet::new(),
+			hypo_play_order: Vec::new(),
 			waiting: None,
 
 			certain_map: Vec::new(),
@@ -82,7 +93,11 @@ impl Player {
 		self.thoughts[order].possible.iter().sorted_by_key(|&i| i.suit_index * 10 + i.rank).map(|id| state.log_id(id)).join(",")
 	}
 
-	pub fn refer(&self, frame: &Frame, hand: &[usize], order: usize, left: bool) -> usize {
+	/**
+	 * Returns the first untouched card in the given direction from the order, wrapping around the hand.
+	 * Returns None if every other card in the hand is touched.
+	 */
+	pub fn refer(&self, frame: &Frame, hand: &[usize], order: usize, left: bool) -> Option<usize> {
 		let offset: i32 = if left { -1 } else { 1 };
 		let index = hand.iter().position(|&o| o == order).unwrap();
 
@@ -92,7 +107,7 @@ impl Player {
 			target_index = (target_index as i32 + offset + hand.len() as i32) as usize % hand.len();
 		}
 
-		hand[target_index]
+		(target_index != index).then_some(hand[target_index])
 	}
 
 	/** Returns whether the identity has already been sieved in anyone's hand, excluding the given order. */
@@ -100,7 +115,7 @@ impl Player {
 		let Frame { state, meta } = frame;
 		for player_index in 0..state.num_players {
 			let loaded = self.thinks_loaded(frame, player_index);
-			let chop = state.hands[player_index].iter().find(|&&o| !state.deck[o].clued && meta[o].status == CardStatus::None);
+			let chop = frame.chop(player_index);
 
 			for o in state.hands[player_index].iter() {
 				if *o != order && self.thoughts[*o].matches(&id, &MatchOptions { infer: true, ..Default::default() }) {
@@ -109,7 +124,7 @@ impl Player {
 							return true;
 						}
 					}
-					else if chop.is_none_or(|c| o != c) {
+					else if chop.is_none_or(|c| *o != c) {
 						return true;
 					}
 				}
@@ -186,42 +201,39 @@ impl Player {
 	}
 
 	pub fn thinks_locked(&self, frame: &Frame, player_index: usize) -> bool {
+		let finessed = self.thinks_finessed(frame, player_index);
+
 		!self.thinks_loaded(frame, player_index) && frame.state.hands[player_index].iter().all(|&order|
 			frame.state.deck[order].clued ||
-			frame.meta[order].status == CardStatus::CalledToPlay ||
+			finessed.contains(&order) ||
 			frame.meta[order].cm()
 		)
 	}
 
-	pub fn obvious_playables(&self, frame: &Frame, player_index: usize) -> Vec<usize> {
-		let Frame { state, meta } = frame;
-
-		state.hands[player_index].iter().filter_map(|&order| {
-			if meta[order].status == CardStatus::CalledToPlay && self.thoughts[order].possible.iter().any(|id| frame.state.is_playable(id)) {
-				return Some(order);
-			}
+	/** Returns the unclued cards in the player's hand that are called to play. */
+	pub fn thinks_finessed(&self, frame: &Frame, player_index: usize) -> Vec<usize> {
+		frame.state.hands[player_index].iter().filter(|&&order| frame.is_blind_playing(order)).copied().collect()
+	}
 
-			if self.thoughts[order].possible.iter().all(|id| state.is_playable(id)) {
-				return Some(order);
-			}
+	/** Returns whether the card is known to be playable, either from its possibilities or from being called to play. */
+	pub fn known_playable(&self, frame: &Frame, order: usize) -> bool {
+		let Frame { state, meta } = frame;
+		let thought = &self.thoughts[order];
 
-			self.thoughts[order].info_lock.is_some_and(|ids| ids.iter().all(|id| state.is_playable(id))).then_some(order)
-		}).collect()
+		thought.possible.iter().all(|id| state.is_playable(id)) ||
+			(meta[order].status == CardStatus::CalledToPlay && thought.possible.iter().any(|id| state.is_playable(id)))
 	}
 
 	pub fn thinks_playables(&self, frame: &Frame, player_index: usize) -> Vec<usize> {
 		let Frame { state, meta } = frame;
 
 		state.hands[player_index].iter().filter_map(|&order| {
-			let thought = &self.thoughts[order];
-
-			let known_playable = thought.possible.iter().all(|id| state.is_playable(id)) ||
-				(meta[order].status == CardStatus::CalledToPlay && thought.possible.iter().any(|id| frame.state.is_playable(id)));
-
-			if known_playable {
+			if self.known_playable(frame, order) {
 				return Some(order);
 			}
 
+			let thought = &self.thoughts[order];
+
 			if meta[order].trash {
 				return None;
 			}
@@ -241,10 +253,6 @@ impl Player {
 		}).collect()
 	}
 
-	pub fn thinks_trash(&self, frame: &Frame, player_index: usize) -> Vec<usize> {
-		frame.state.hands[player_index].iter().filter(|&order| self.order_trash(frame, *order)).copied().collect()
-	}
-
 	pub fn discardable(&self, frame: &Frame, player_index: usize) -> Vec<usize> {
 		frame.state.hands[player_index].iter().filter(|&&order|
 			self.order_trash(frame, order) || self.thoughts[order].possibilities().iter().all(|id| self.is_sieved(frame, id, order))
@@ -252,16 +260,21 @@ impl Player {
 	}
 
 	pub fn safe_actions(&self, frame: &Frame, player_index: usize) -> Vec<usize> {
-		self.obvious_playables(frame, player_index).iter().chain(self.thinks_trash(frame, player_index).iter()).copied().collect::<Vec<_>>()
+		frame.playable_orders(self, player_index).into_iter().chain(frame.trash_orders(self, player_index)).collect::<Vec<_>>()
 	}
 
 	pub fn thinks_loaded(&self, frame: &Frame, player_index: usize) -> bool {
-		!self.obvious_playables(frame, player_index).is_empty() || !self.thinks_trash(frame, player_index).is_empty()
+		!frame.playable_orders(self, player_index).is_empty() || !frame.trash_orders(self, player_index).is_empty()
 	}
 
 	/** Returns how far the identity is from playable (through cards known by this player). 0 means that it is playable.*/
-	pub fn playable_away(&self, id: Identity) -> i32 {
-		id.rank as i32 - (self.hypo_stacks[id.suit_index] + 1) as i32
+	pub fn playable_away(&self, state: &State, id: Identity) -> i32 {
+		state.playable_away_on(id, &self.hypo_stacks)
+	}
+
+	/** Returns how far the closest-to-playable inferred identity of the card is from playable. */
+	pub fn min_playable_away(&self, state: &State, order: usize) -> i32 {
+		self.thoughts[order].inferred.iter().map(|i| state.playable_away(i)).min().unwrap_or(i32::MAX)
 	}
 
 	pub fn save2(&self, state: &State, id: Identity) -> bool {
@@ -269,11 +282,26 @@ impl Player {
 
 		rank == 2 &&
 		state.play_stacks[suit_index] < 2 &&
-		visible_find(state, self, id, MatchOptions { infer: true, ..Default::default() }, |_, _| true).len() == 1
+		self.find_visible(state, id, MatchOptions { infer: true, ..Default::default() }).len() == 1
+	}
+
+	/** Returns the orders of all cards in hands that this player thinks match the identity. Cards in their own hand are matched symmetrically. */
+	pub fn find_visible(&self, state: &State, id: Identity, options: MatchOptions) -> Vec<usize> {
+		visible_find(state, self, id, options, |_, _| true)
 	}
 
-	pub fn locked_discard(&self, state: &State, player_index: usize) -> usize {
-		let crit_percents = state.hands[player_index].iter().map(|&o| {
+	pub fn locked_discard(&self, frame: &Frame, player_index: usize) -> usize {
+		let Frame { state, meta } = frame;
+
+		// Identities that a teammate's called card is waiting on to become playable
+		let connecting = |id: Identity| !state.is_basic_trash(id) && state.hands.iter().enumerate().any(|(i, hand)|
+			i != player_index && hand.iter().any(|&o| meta[o].status == CardStatus::CalledToPlay &&
+				self.thoughts[o].possibilities().iter().any(|p| state.next_id(id) == Some(p))));
+
+		let connectors = state.hands[player_index].iter().filter(|&&o| self.thoughts[o].possibilities().iter().all(connecting)).collect::<Vec<_>>();
+		let excluded = if connectors.len() == state.hands[player_index].len() { Vec::new() } else { connectors };
+
+		let crit_percents = state.hands[player_index].iter().filter(|o| !excluded.contains(o)).map(|&o| {
 			let poss = self.thoughts[o].possibilities();
 			let percent = poss.iter().filter(|&p| state.is_critical(p)).count() / poss.len();
 			(o, percent)
@@ -289,10 +317,22 @@ impl Player {
 		}).unwrap().0
 	}
 
+	/**
+	 * Returns the card in the player's hand most likely to have another copy of its identity remaining,
+	 * weighting each possibility by how many copies of it are unknown.
+	 */
+	pub fn sacrifice_discard(&self, state: &State, player_index: usize) -> usize {
+		state.hands[player_index].iter().map(|&o| {
+			let (safe, total) = self.thoughts[o].possibilities().iter().fold((0, 0), |(safe, total), p| {
+				let unknown = self.unknown_ids(state, p);
+				(if unknown > 1 { safe + unknown } else { safe }, total + unknown)
+			});
+			(o, if total == 0 { 0.0 } else { safe as f32 / total as f32 })
+		}).max_by(|(_, a), (_, b)| a.total_cmp(b)).unwrap().0
+	}
+
 	pub fn unknown_ids(&self, state: &State, id: Identity) -> usize {
-		let visible_count: usize = state.hands.iter().map(|hand|
-			hand.iter().filter(|&&o| self.thoughts[o].is(&id)).count()).sum();
-		state.card_count(id) - state.base_count(id) - visible_count
+		state.card_count(id) - state.base_count(id) - state.count_in_hands(id, &self.thoughts)
 	}
 
 	pub fn linked_orders(&self, state: &State) -> AHashSet<usize> {
@@ -319,15 +359,16 @@ impl Player {
 		let mut hypo_stacks = state.play_stacks.clone();
 		let mut unknown_plays: AHashSet<usize> = AHashSet::new();
 		let mut played: AHashSet<usize> = AHashSet::new();
+		let mut play_order = Vec::new();
 		let mut unplayable: AHashSet<usize> = AHashSet::new();
 
 		let mut found_playable = true;
 		let mut good_touch_elim = IdentitySet::EMPTY;
 		let linked_orders = self.linked_orders(state);
 
-		fn delayed_playable(good_touch_elim: IdentitySet, hypo_stacks: &[usize], ids: impl Iterator<Item = Identity>) -> bool {
+		fn delayed_playable(state: &State, good_touch_elim: IdentitySet, hypo_stacks: &[usize], ids: impl Iterator<Item = Identity>) -> bool {
 			let mut remaining = ids.filter(|id| !good_touch_elim.contains(*id)).peekable();
-			remaining.peek().is_some() && remaining.all(|id| hypo_stacks[id.suit_index] + 1 == id.rank)
+			remaining.peek().is_some() && remaining.all(|id| state.next_rank_at(id.suit_index, hypo_stacks[id.suit_index]) == Some(id.rank))
 		}
 
 		while found_playable {
@@ -348,9 +389,9 @@ impl Player {
 					}
 
 					let playable = state.has_consistent_inferences(thought) && (
-						delayed_playable(good_touch_elim, &hypo_stacks, thought.possible.iter()) ||
-						delayed_playable(good_touch_elim, &hypo_stacks, thought.inferred.iter()) ||
-						(frame.is_blind_playing(order) && actual_id.is_some_and(|i| delayed_playable(good_touch_elim, &hypo_stacks, std::iter::once(i)))));
+						delayed_playable(state, good_touch_elim, &hypo_stacks, thought.possible.iter()) ||
+						delayed_playable(state, good_touch_elim, &hypo_stacks, thought.inferred.iter()) ||
+						(frame.is_blind_playing(order) && actual_id.is_some_and(|i| delayed_playable(state, good_touch_elim, &hypo_stacks, std::iter::once(i)))));
 
 					if !playable {
 						continue;
@@ -360,31 +401,33 @@ impl Player {
 						None => {
 							unknown_plays.insert(order);
 							played.insert(order);
+							play_order.push(order);
 							found_playable = true;
 
 							for link in &self.links {
 								if let Link::Promised { id, .. } = link {
-									if id.rank != hypo_stacks[id.suit_index] + 1 {
+									if state.next_rank_at(id.suit_index, hypo_stacks[id.suit_index]) != Some(id.rank) {
 										warn!("tried to add linked {} ({}) onto hypo stacks, but they were at {hypo_stacks:?} {:?}", state.log_id(*id), order, played);
 										unplayable.insert(order);
 									}
 									else {
-										hypo_stacks[id.suit_index] = id.rank;
+										hypo_stacks[id.suit_index] += 1;
 										good_touch_elim = good_touch_elim.with(*id);
 									}
 								}
 							}
 						},
 						Some(id) => {
-							if id.rank != hypo_stacks[id.suit_index] + 1 {
+							if state.next_rank_at(id.suit_index, hypo_stacks[id.suit_index]) != Some(id.rank) {
 								warn!("tried to add {} ({}) onto hypo stacks, but they were at {hypo_stacks:?} {:?}", state.log_id(id), order, played);
 								unplayable.insert(order);
 							}
 							else {
 								found_playable = true;
-								hypo_stacks[id.suit_index] = id.rank;
+								hypo_stacks[id.suit_index] += 1;
 								good_touch_elim = good_touch_elim.with(id);
 								played.insert(order);
+								play_order.push(order);
 							}
 						}
 					}
@@ -394,5 +437,35 @@ impl Player {
 		self.hypo_stacks = hypo_stacks;
 		self.unknown_plays = unknown_plays;
 		self.hypo_plays = played;
+		self.hypo_play_order = play_order;
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::basics::card::MatchOptions;
+	use crate::basics::clue::{BaseClue, ClueKind};
+	use crate::basics::identity_set::IdentitySet;
+	use crate::basics::test_util::{pre_clue, setup};
+
+	#[test]
+	fn it_finds_visible_cards_by_inference() {
+		let mut game = setup(&[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["b1", "y3", "g4", "p2", "y4"],
+			&["p3", "g3", "r4", "b1", "y2"],
+		]);
+
+		// Alice's slot 1 is clued red and inferred as r1, which nobody else holds
+		pre_clue(&mut game, 0, 1, &[BaseClue { kind: ClueKind::COLOUR, value: 0 }]);
+		let alice_slot1 = game.state.hands[0][0];
+		let [r1, b1] = ["r1", "b1"].map(|short| game.state.expand_short(short));
+		game.common.thoughts[alice_slot1].inferred = IdentitySet::single(r1);
+
+		assert_eq!(game.common.find_visible(&game.state, r1, MatchOptions { infer: true, ..MatchOptions::default() }), [alice_slot1]);
+		assert!(game.common.find_visible(&game.state, r1, MatchOptions::default()).is_empty());
+
+		// Alice can see both of the b1s
+		assert_eq!(game.us().find_visible(&game.state, b1, MatchOptions::default()), [game.state.hands[1][0], game.state.hands[2][3]]);
 	}
 }
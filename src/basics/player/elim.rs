@@ -40,8 +40,7 @@ impl Player {
 				}
 
 				// Card can be further eliminated
-				if thought.possible.len() == 1 {
-					let recursive_id = thought.possible.iter().next().unwrap();
+				if let Some(recursive_id) = thought.known_id() {
 					let certains = &mut self.certain_map[Identity::to_ord(recursive_id)];
 					match certains.iter_mut().find(|e| e.order == order) {
 						Some(entry) => entry.unknown_to = None,
@@ -324,6 +323,20 @@ impl Player {
 						continue;
 					}
 
+					// The promised id was played or discarded elsewhere, so the promise can no longer hold
+					if state.is_basic_trash(id) {
+						info!("promised id {} is now trash, dissolving link among cards {:?}", state.log_id(id), orders);
+						for &o in &orders {
+							let thought = &mut self.thoughts[o];
+							thought.inferred.retain(|i| i != id);
+
+							if thought.inferred.is_empty() {
+								thought.reset_inferences();
+							}
+						}
+						continue;
+					}
+
 					if !self.thoughts[target].possible.iter().any(|i| id.suit_index == i.suit_index) {
 						continue;
 					}
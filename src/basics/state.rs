@@ -1,9 +1,10 @@
+use std::cmp::{max, min};
 use std::hash::Hasher;
 use std::sync::{Arc, LazyLock};
 
 use crate::basics::identity_set::IdentitySet;
 use crate::basics::variant::{all_ids, card_touched, Variant, PINKISH, RAINBOWISH};
-use super::action::Action;
+use super::action::{Action, DiscardAction};
 use super::clue::{BaseClue, Clue, ClueKind};
 use super::card::{Card, Identifiable, Identity, Thought};
 
@@ -11,6 +12,15 @@ use ahash::AHasher;
 use fraction::{ConstOne, Fraction};
 use regex::Regex;
 
+/** The direction a suit's stack is played in. Only "Up or Down" variants have stacks that can go down (START cards are not modelled, so the bot doesn't join these tables). */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackDirection {
+	Up,
+	Down,
+	/** Nothing has been played yet, so the stack can start from either a 1 or a 5. */
+	Undecided,
+}
+
 #[derive(Debug, Clone)]
 pub struct State {
 	pub turn_count: usize,
@@ -26,19 +36,25 @@ pub struct State {
 	/** The order of the next card to draw. */
 	pub card_order: usize,
 	pub cards_left: usize,
+	/** The number of cards played on each stack (the top rank, when playing up). */
 	pub play_stacks: Vec<usize>,
-	pub discard_stacks: Vec<Vec<Vec<usize>>>,
+	pub stack_direction: Vec<StackDirection>,
+	discard_stacks: Vec<Vec<Vec<usize>>>,
+	/** The maximum number of cards that can still be played on each stack. */
 	pub max_ranks: Vec<usize>,
 	pub action_list: Arc<Vec<Vec<Action>>>,
 	pub current_player_index: usize,
 	pub endgame_turns: Option<usize>,
 	card_count: Vec<usize>,
+	/** The number of copies of each identity (by [`Identity::to_ord`]) that haven't been discarded. */
+	remaining: Vec<usize>,
 }
 
 impl State {
 	pub fn new(player_names: Vec<String>, our_player_index: usize, variant: Arc<Variant>) -> Self {
 		let num_players = player_names.len();
 		let num_suits = variant.suits.len();
+		let direction = if variant.up_or_down.is_some_and(|u| u) { StackDirection::Undecided } else { StackDirection::Up };
 
 		let mut card_count = Vec::new();
 		let mut cards_left = 0;
@@ -65,8 +81,10 @@ impl State {
 			our_player_index,
 			card_order: 0,
 			cards_left,
+			remaining: card_count.clone(),
 			card_count,
 			play_stacks: vec![0; num_suits],
+			stack_direction: vec![direction; num_suits],
 			discard_stacks: vec![vec![Vec::new(); 5]; num_suits],
 			max_ranks: vec![5; num_suits],
 			action_list: Arc::new(Vec::new()),
@@ -93,6 +111,15 @@ impl State {
 		hasher.write_u64(self.clue_tokens.numer().copied().unwrap());
 		hasher.write_u64(self.clue_tokens.denom().copied().unwrap());
 
+		// Stacks can differ between states with the same cards in hand, e.g. in endgame searches
+		for &stack in &self.play_stacks {
+			hasher.write_usize(stack);
+		}
+		for &max_rank in &self.max_ranks {
+			hasher.write_usize(max_rank);
+		}
+		hasher.write_u8(self.strikes);
+
 		match self.endgame_turns {
 			Some(turns) => {
 				hasher.write_u8(1);
@@ -151,12 +178,103 @@ impl State {
 
 	/** Returns whether the identity is trash (played already or can never be played).  */
 	pub fn is_basic_trash(&self, id: Identity) -> bool {
-		id.rank <= self.play_stacks[id.suit_index] || id.rank > self.max_ranks[id.suit_index]
+		let Identity { suit_index, rank } = id;
+		let reachable = |height: usize, max_height: usize| height > self.play_stacks[suit_index] && height <= max_height;
+
+		match self.stack_direction[suit_index] {
+			// Only an undecided stack needs its max height in each direction, otherwise the cached max rank is enough
+			StackDirection::Undecided =>
+				!reachable(rank, self.max_height(suit_index, StackDirection::Up)) && !reachable(6 - rank, self.max_height(suit_index, StackDirection::Down)),
+			_ => !reachable(self.stack_height(id), self.max_ranks[suit_index])
+		}
+	}
+
+	/**
+	 * Returns the rank that would be played next on a stack of the given height in the suit, following the stack's direction,
+	 * or None if the stack can't be played on any further. Undecided stacks are assumed to go up.
+	 */
+	pub fn next_rank_at(&self, suit_index: usize, height: usize) -> Option<usize> {
+		(height < self.max_ranks[suit_index]).then(|| match self.stack_direction[suit_index] {
+			StackDirection::Down => 5 - height,
+			_ => height + 1,
+		})
+	}
+
+	/** Returns the next rank to be played on the suit's stack, or None if the suit is finished. */
+	pub fn next_playable_rank(&self, suit_index: usize) -> Option<usize> {
+		self.next_rank_at(suit_index, self.play_stacks[suit_index])
+	}
+
+	/** Returns whether the identity would be playable on the given (e.g. projected) stack heights. */
+	pub fn is_playable_on(&self, id: Identity, stacks: &[usize]) -> bool {
+		let Identity { suit_index, rank } = id;
+
+		match self.stack_direction[suit_index] {
+			StackDirection::Undecided if stacks[suit_index] == 0 => rank == 1 || rank == 5,
+			_ => self.next_rank_at(suit_index, stacks[suit_index]) == Some(rank),
+		}
 	}
 
 	/** Returns how far the identity is from playable. 0 means that it is playable.*/
 	pub fn playable_away(&self, id: Identity) -> i32 {
-		id.rank as i32 - (self.play_stacks[id.suit_index] + 1) as i32
+		self.playable_away_on(id, &self.play_stacks)
+	}
+
+	/** Returns how far the identity is from playable on the given (e.g. projected) stack heights. */
+	pub fn playable_away_on(&self, id: Identity, stacks: &[usize]) -> i32 {
+		let Identity { suit_index, rank } = id;
+
+		match self.stack_direction[suit_index] {
+			StackDirection::Undecided if stacks[suit_index] == 0 => min(rank - 1, 5 - rank) as i32,
+			_ => self.stack_height(id) as i32 - (stacks[suit_index] + 1) as i32,
+		}
+	}
+
+	/** Returns the identity played on the stack right after the given one, following the stack's direction, or None if it would finish the suit. */
+	pub fn next_id(&self, id: Identity) -> Option<Identity> {
+		self.next_rank_at(id.suit_index, self.stack_height(id)).map(|rank| Identity { suit_index: id.suit_index, rank })
+	}
+
+	/** Returns the identity played on the stack right before the given one, following the stack's direction, or None if it starts the suit. */
+	pub fn prev_id(&self, id: Identity) -> Option<Identity> {
+		let height = self.stack_height(id);
+		(height > 1).then(|| Identity { suit_index: id.suit_index, rank: if self.stack_direction[id.suit_index] == StackDirection::Down { id.rank + 1 } else { id.rank - 1 } })
+	}
+
+	/** Returns the height the identity's stack would have once it is played, following the stack's direction. */
+	pub fn stack_height(&self, id: Identity) -> usize {
+		match self.stack_direction[id.suit_index] {
+			StackDirection::Down => 6 - id.rank,
+			_ => id.rank,
+		}
+	}
+
+	/** Returns the maximum number of cards that can be played on the suit's stack in the given direction, given the discards. */
+	fn max_height(&self, suit_index: usize, direction: StackDirection) -> usize {
+		let all_discarded = |rank: usize| self.remaining(Identity { suit_index, rank }) == 0;
+
+		match direction {
+			StackDirection::Up => (1..=5).find(|&rank| all_discarded(rank)).map(|rank| rank - 1).unwrap_or(5),
+			StackDirection::Down => (1..=5).rev().find(|&rank| all_discarded(rank)).map(|rank| 5 - rank).unwrap_or(5),
+			StackDirection::Undecided => max(self.max_height(suit_index, StackDirection::Up), self.max_height(suit_index, StackDirection::Down)),
+		}
+	}
+
+	/** Updates the maximum number of playable cards on the suit's stack, after a discard or a change of direction. */
+	pub fn update_max_rank(&mut self, suit_index: usize) {
+		self.max_ranks[suit_index] = min(self.max_ranks[suit_index], self.max_height(suit_index, self.stack_direction[suit_index]));
+	}
+
+	/** Plays the identity onto its stack, deciding the stack's direction if needed. */
+	pub fn play_to_stack(&mut self, id: Identity) {
+		let Identity { suit_index, rank } = id;
+
+		if self.stack_direction[suit_index] == StackDirection::Undecided {
+			self.stack_direction[suit_index] = if rank == 5 { StackDirection::Down } else { StackDirection::Up };
+			self.update_max_rank(suit_index);
+		}
+
+		self.play_stacks[suit_index] = self.stack_height(id);
 	}
 
 	pub fn is_playable(&self, id: Identity) -> bool {
@@ -164,7 +282,37 @@ impl State {
 	}
 
 	pub fn is_critical(&self, id: Identity) -> bool {
-		!self.is_basic_trash(id) && self.discard_stacks[id.suit_index][id.rank - 1].len() == (self.card_count(id) - 1)
+		!self.is_basic_trash(id) && self.remaining(id) == 1
+	}
+
+	/** Returns the number of copies of the identity that haven't been discarded. */
+	#[inline]
+	pub fn remaining(&self, id: Identity) -> usize {
+		self.remaining[id.to_ord()]
+	}
+
+	/** Returns the orders of the discarded cards with the given identity. */
+	pub fn discards(&self, id: Identity) -> &[usize] {
+		&self.discard_stacks[id.suit_index][id.rank - 1]
+	}
+
+	/** Adds the card to the discard pile, updating the remaining copies and the suit's max rank if every copy is now gone. */
+	pub fn discard_to_stack(&mut self, order: usize, id: Identity) {
+		self.discard_stacks[id.suit_index][id.rank - 1].push(order);
+		self.remaining[id.to_ord()] = self.remaining[id.to_ord()].saturating_sub(1);
+
+		if self.remaining(id) == 0 {
+			self.update_max_rank(id.suit_index);
+		}
+	}
+
+	/** Returns the player index and identity of the card discarded on the previous turn, if any. */
+	pub fn last_discard(&self) -> Option<(usize, Identity)> {
+		self.action_list.get(self.turn_count.checked_sub(1)?)?.iter().find_map(|action| match action {
+			Action::Discard(DiscardAction { player_index, suit_index, rank, .. }) if *suit_index != -1 && *rank != -1 =>
+				Some((*player_index, Identity { suit_index: *suit_index as usize, rank: *rank as usize })),
+			_ => None
+		})
 	}
 
 	pub fn our_hand(&self) -> &Vec<usize> {
@@ -175,9 +323,19 @@ impl State {
 		&mut self.hands[self.our_player_index]
 	}
 
+	/** Returns the number of cards in all hands that are visibly the given identity. */
+	pub fn visible_count(&self, id: Identity) -> usize {
+		self.count_in_hands(id, &self.deck)
+	}
+
+	/** Returns the number of cards in all hands that are the given identity, as seen through the given cards (e.g. a player's thoughts). */
+	pub fn count_in_hands(&self, id: Identity, cards: &[impl Identifiable]) -> usize {
+		self.hands.iter().map(|hand| hand.iter().filter(|&&o| cards[o].is(&id)).count()).sum()
+	}
+
 	/** Returns the number of cards matching an identity on the play+discard stacks.  */
 	pub fn base_count(&self, id: Identity) -> usize {
-		(if self.play_stacks[id.suit_index] >= id.rank { 1 } else { 0 }) +
+		(if self.play_stacks[id.suit_index] >= self.stack_height(id) { 1 } else { 0 }) +
 		self.discard_stacks[id.suit_index][id.rank - 1].len()
 	}
 
@@ -197,6 +355,22 @@ impl State {
 		clues
 	}
 
+	/**
+	 * Returns the clues that can legally be given to the target right now.
+	 * A clue token must be available, and the variant may restrict which ranks can be clued (e.g. "Number Mute") or have no clue colours at all.
+	 */
+	pub fn legal_clues(&self, target: usize) -> Vec<Clue> {
+		if !self.can_clue() {
+			return Vec::new();
+		}
+
+		let colourable = !self.variant.colourable_suits.as_ref().unwrap().is_empty();
+		self.all_valid_clues(target).into_iter().filter(|clue| match clue.kind {
+			ClueKind::RANK => self.variant.clue_ranks.as_ref().is_none_or(|ranks| ranks.contains(&clue.value)),
+			ClueKind::COLOUR => colourable,
+		}).collect()
+	}
+
 	pub fn clue_touched(&self, orders: &[usize], clue: &BaseClue) -> Vec<usize> {
 		orders.iter().filter_map(|&order| {
 			card_touched(&self.deck[order], &self.variant, clue).then_some(order)
@@ -204,7 +378,7 @@ impl State {
 	}
 
 	pub fn has_consistent_inferences(&self, thought: &Thought) -> bool {
-		if thought.possible.len() == 1 {
+		if thought.known_id().is_some() {
 			return true;
 		}
 
@@ -214,18 +388,37 @@ impl State {
 		}
 	}
 
+	/** Spends a clue token. Giving a clue always costs a whole token, even in Clue Starved where discards only regain half. */
+	pub fn spend_clue(&mut self) {
+		self.clue_tokens -= 1;
+	}
+
+	/** Returns the clue tokens regained by a discard. In Clue Starved, this is half a token, which is also the unit the server counts clue tokens in. */
+	pub fn clue_unit(&self) -> Fraction {
+		if self.variant.clue_starved.is_some_and(|c| c) { Fraction::new(1u64, 2u64) } else { Fraction::ONE }
+	}
+
 	pub fn regain_clue(&mut self) {
-		self.clue_tokens += if self.variant.clue_starved.is_some_and(|c| c) { Fraction::new(1u64, 2u64) } else { Fraction::ONE };
+		self.clue_tokens += self.clue_unit();
 
 		if self.clue_tokens > Fraction::from(8) {
 			self.clue_tokens = Fraction::from(8);
 		}
 	}
 
+	/** Returns whether playing a card of the given rank regains a clue token. */
+	pub fn regains_clue(&self, rank: usize) -> bool {
+		rank == 5 && !self.variant.no_five_regain.is_some_and(|c| c)
+	}
+
 	pub fn can_clue(&self) -> bool {
 		self.clue_tokens >= Fraction::ONE
 	}
 
+	pub fn can_discard(&self) -> bool {
+		self.clue_tokens < Fraction::from(8)
+	}
+
 	pub fn includes_variant(&self, regex: &LazyLock<Regex>) -> bool {
 		self.variant.suits.iter().any(|suit| regex.is_match(suit)) ||
 		(regex.as_str() == RAINBOWISH.as_str() && self.variant.rainbow_s.is_some_and(|s| s)) ||
@@ -245,8 +438,14 @@ impl State {
 	}
 
 	pub fn expand_short(&self, short: &str) -> Identity {
-		let suit_index = self.variant.short_forms.as_ref().unwrap().iter().position(|form| form == &short[0..1]).unwrap_or_else(|| panic!("Colour {short} doesn't exist in selected variant"));
-		Identity { suit_index, rank: short[1..2].parse().unwrap_or_else(|_| panic!("Rank {short} doesn't exist in selected variant")) }
+		let suit_index = self.variant.short_forms.as_ref().unwrap().iter().position(|form| short.get(0..1) == Some(form)).unwrap_or_else(|| panic!("Colour {short} doesn't exist in selected variant"));
+		let rank = short.get(1..2).and_then(|r| r.parse().ok()).filter(|r| (1..=5).contains(r)).unwrap_or_else(|| panic!("Rank {short} doesn't exist in selected variant"));
+		Identity { suit_index, rank }
+	}
+
+	/** Returns the set of identities with the given short forms (e.g. ["r1", "b3"]). Panics on any short form that doesn't exist in the variant. */
+	pub fn expand_shorts(&self, shorts: &[&str]) -> IdentitySet {
+		IdentitySet::from_iter(shorts.iter().map(|short| self.expand_short(short)))
 	}
 
 	pub fn log_id(&self, id: Identity) -> String {
@@ -264,3 +463,98 @@ impl State {
 		self.log_oid(&iden.id())
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::basics::test_util::{no_variant, setup};
+
+	fn empty_state() -> State {
+		State::new(vec!["Alice".to_owned(), "Bob".to_owned()], 0, Arc::new(no_variant()))
+	}
+
+	#[test]
+	fn it_counts_visible_identities() {
+		let mut game = setup(&[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["r1", "g2", "r2", "r1", "g5"],
+			&["g1", "b5", "r1", "b1", "g2"],
+		]);
+		let b1 = game.state.expand_short("b1");
+		game.state.discard_to_stack(99, b1);
+		let state = &game.state;
+
+		let count = |short| state.visible_count(state.expand_short(short));
+
+		assert_eq!(count("r1"), 3);
+		assert_eq!(count("g2"), 2);
+		assert_eq!(count("b5"), 1);
+		assert_eq!(count("p1"), 0);
+
+		// The discarded b1 is not visible in a hand.
+		assert_eq!(count("b1"), 1);
+
+		// Through common knowledge, none of the unclued cards are known.
+		let r1 = state.expand_short("r1");
+		assert_eq!(state.count_in_hands(r1, &game.common.thoughts), 0);
+		assert_eq!(state.count_in_hands(r1, &game.us().thoughts), 3);
+	}
+
+	#[test]
+	fn it_hashes_stacks_and_strikes() {
+		let state = empty_state();
+
+		let mut played = state.clone();
+		played.play_stacks[0] = 1;
+		assert_ne!(played.hash(), state.hash());
+
+		let mut capped = state.clone();
+		capped.max_ranks[0] = 4;
+		assert_ne!(capped.hash(), state.hash());
+
+		let mut struck = state.clone();
+		struck.strikes = 1;
+		assert_ne!(struck.hash(), state.hash());
+	}
+
+	#[test]
+	fn it_tracks_remaining_copies() {
+		let mut state = empty_state();
+		let [r1, r4, r5] = ["r1", "r4", "r5"].map(|short| state.expand_short(short));
+
+		state.discard_to_stack(10, r4);
+		assert_eq!(state.remaining(r4), 1);
+		assert!(state.is_critical(r4));
+
+		// Playing doesn't use up a copy, but the rest become trash.
+		state.play_to_stack(r1);
+		assert_eq!(state.remaining(r1), 3);
+		assert!(!state.is_critical(r1) && state.is_basic_trash(r1));
+
+		// Discarding the last r4 means no more red cards can be played.
+		state.discard_to_stack(11, r4);
+		assert_eq!(state.remaining(r4), 0);
+		assert_eq!(state.discards(r4), [10, 11]);
+		assert_eq!(state.max_ranks[0], 3);
+		assert!(state.is_basic_trash(r5) && !state.is_critical(r5));
+		assert_eq!(state.remaining(r5), 1);
+	}
+
+	#[test]
+	fn it_finds_the_next_playable_rank() {
+		let mut state = empty_state();
+		state.play_stacks = vec![2, 0, 1, 5, 3];
+
+		assert_eq!((0..5).map(|suit_index| state.next_playable_rank(suit_index)).collect::<Vec<_>>(), vec![Some(3), Some(1), Some(2), None, Some(4)]);
+
+		state.play_to_stack(state.expand_short("r3"));
+		assert_eq!(state.next_playable_rank(0), Some(4));
+		assert!(state.is_playable(state.expand_short("r4")));
+
+		// Nothing more can be played once every g2 is gone.
+		let g2 = state.expand_short("g2");
+		state.discard_to_stack(10, g2);
+		state.discard_to_stack(11, g2);
+		assert_eq!(state.next_playable_rank(2), None);
+	}
+}
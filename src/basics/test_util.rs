@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use crate::basics::action::{Action, ClueAction, DiscardAction, PlayAction};
+use crate::basics::card::Identifiable;
+use crate::basics::clue::{BaseClue, CardClue};
+use crate::basics::game::Game;
+use crate::basics::identity_set::IdentitySet;
+use crate::basics::state::State;
+use crate::basics::variant::{all_ids, id_touched, Variant, VariantOpts};
+use crate::reactor::Reactor;
+
+const NAMES: [&str; 5] = ["Alice", "Bob", "Cathy", "Donald", "Emily"];
+
+pub fn no_variant() -> Variant {
+	Variant::new(0, "No Variant", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts::default())
+}
+
+/** Sets up a game of No Variant from Alice's perspective, dealing each hand from slot 1 ("xx" for a card we can't see). */
+pub fn setup(hands: &[&[&str]]) -> Game {
+	let player_names = NAMES[..hands.len()].iter().map(|&name| name.to_string()).collect();
+	let state = State::new(player_names, 0, Arc::new(no_variant()));
+	let mut game = Game::new(0, state, false, Arc::new(Reactor));
+	game.catchup = true;
+
+	for (player_index, hand) in hands.iter().enumerate() {
+		for &short in hand.iter().rev() {
+			draw(&mut game, player_index, short);
+		}
+	}
+	game
+}
+
+fn draw(game: &mut Game, player_index: usize, short: &str) {
+	let order = game.state.card_order;
+	let action = if short == "xx" { Action::draw(player_index, order, -1, -1) } else {
+		let id = game.state.expand_short(short);
+		Action::draw(player_index, order, id.suit_index as i32, id.rank as i32)
+	};
+	game.handle_action(&action);
+}
+
+/** Takes a turn with the action, drawing the given card afterward if it was a play or discard. */
+pub fn take_turn(game: &mut Game, action: Action, drawn: Option<&str>) {
+	let player_index = match &action {
+		Action::Clue(ClueAction { giver, .. }) => *giver,
+		Action::Play(PlayAction { player_index, .. }) | Action::Discard(DiscardAction { player_index, .. }) => *player_index,
+		_ => panic!("Can't take a turn with {action:?}")
+	};
+
+	game.handle_action(&action);
+
+	if let Some(short) = drawn {
+		draw(game, player_index, short);
+	}
+
+	let num = game.state.turn_count;
+	game.handle_action(&Action::turn(num, game.state.next_player_index(player_index) as i32));
+}
+
+/** Touches the card in the slot with the given clues as though they were given before the game, so that common knowledge agrees with them. */
+pub fn pre_clue(game: &mut Game, player_index: usize, slot: usize, clues: &[BaseClue]) {
+	let Game { state, common, .. } = game;
+	let order = state.hands[player_index][slot - 1];
+
+	if let Some(id) = state.deck[order].id() {
+		assert!(clues.iter().all(|clue| id_touched(id, &state.variant, clue)), "Clues don't all touch order {order}!");
+	}
+
+	let possibilities = IdentitySet::from_iter(all_ids(&state.variant).filter(|&i| clues.iter().all(|clue| id_touched(i, &state.variant, clue))));
+	common.thoughts[order].possible = possibilities;
+	common.thoughts[order].inferred = possibilities;
+
+	let giver = if player_index == 0 { 1 } else { 0 };
+	state.deck[order].clued = true;
+	state.deck[order].clues = clues.iter().map(|&BaseClue { kind, value }| CardClue { kind, value, giver, turn: 0 }).collect();
+}
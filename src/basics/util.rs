@@ -3,7 +3,7 @@ use std::hash::BuildHasherDefault;
 
 use nohash_hasher::NoHashHasher;
 
-use super::action::{Action, PerformAction};
+use super::action::{Action, ClueAction, PerformAction};
 use crate::basics::action::GameOverAction;
 use crate::basics::clue::{BaseClue, Clue, ClueKind};
 use crate::basics::variant::card_touched;
@@ -96,3 +96,51 @@ pub fn players_upto(num_players: usize, mut start: usize, end: usize) -> Vec<usi
 	}
 	indices
 }
+
+/**
+ * Parses a clue written as "Alice clues red to Bob".
+ * Clues to us must also list the touched slots, as in "Bob clues 2 to Alice (slots 2,4)".
+ */
+pub fn parse_clue(state: &State, raw: &str) -> Result<ClueAction, String> {
+	let parts = raw.split_whitespace().collect::<Vec<&str>>();
+
+	if parts.len() < 5 || parts[1] != "clues" || parts[3] != "to" {
+		return Err(format!("Couldn't parse clue '{raw}', expected '<giver> clues <value> to <target>'"));
+	}
+
+	let find_player = |name: &str| state.player_names.iter().position(|n| n == name)
+		.ok_or_else(|| format!("Couldn't parse player {name}, not in list of players {:?}", state.player_names));
+
+	let giver = find_player(parts[0])?;
+	let target = find_player(parts[4])?;
+
+	let clue = match parts[2].parse::<usize>() {
+		Ok(rank) if (1..=5).contains(&rank) => BaseClue { kind: ClueKind::RANK, value: rank },
+		_ => {
+			let value = state.variant.colourable_suits.as_ref().unwrap().iter().position(|suit| suit.to_lowercase() == parts[2].to_lowercase())
+				.ok_or_else(|| format!("Couldn't parse colour {}", parts[2]))?;
+			BaseClue { kind: ClueKind::COLOUR, value }
+		}
+	};
+
+	let list = if target != state.our_player_index {
+		state.clue_touched(&state.hands[target], &clue)
+	}
+	else {
+		let slots = parts.get(6).filter(|_| parts[5].contains("slot"))
+			.ok_or_else(|| format!("Not enough arguments provided (clue to us) in '{raw}', needs '(slots x,y)'"))?;
+
+		slots.trim_end_matches(|c: char| !c.is_numeric()).split(',').map(|slot| {
+			slot.parse::<usize>().ok()
+				.and_then(|slot| slot.checked_sub(1))
+				.and_then(|index| state.our_hand().get(index).copied())
+				.ok_or_else(|| format!("Failed to parse slot '{slot}' in '{raw}'"))
+		}).collect::<Result<Vec<usize>, String>>()?
+	};
+
+	if list.is_empty() {
+		return Err(format!("No cards touched by clue {:?} to {}", clue, state.player_names[target]));
+	}
+
+	Ok(ClueAction { giver, target, list, clue })
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize};
 use regex::Regex;
+use std::collections::HashMap;
 use std::sync::LazyLock;
 
 use super::card::{Identity, Identifiable};
@@ -16,6 +17,12 @@ pub struct Variant {
 	pub critical_rank: Option<usize>,
 	#[serde(rename="clueStarved")]
 	pub clue_starved: Option<bool>,
+	/** Whether each suit's stack can be played either upward from 1 or downward from 5. */
+	#[serde(rename="upOrDown")]
+	pub up_or_down: Option<bool>,
+	/** Whether playing a 5 does not regain a clue token, as in "Throw It in a Hole". Set from the variant's name. */
+	#[serde(skip)]
+	pub no_five_regain: Option<bool>,
 	#[serde(rename="specialRank")]
 	pub special_rank: Option<usize>,
 	#[serde(rename="specialRankAllClueColors")]
@@ -28,56 +35,59 @@ pub struct Variant {
 	pub brown_s: Option<bool>,
 	#[serde(rename="specialRankDeceptive")]
 	pub deceptive_s: Option<bool>,
+	/** The ranks that can be clued, if the variant restricts them (e.g. none in "Number Mute"). */
+	#[serde(rename="clueRanks")]
+	pub clue_ranks: Option<Vec<usize>>,
 
 	pub short_forms: Option<Vec<String>>,
 	pub colourable_suits: Option<Vec<String>>,
+	/** Maps the index of each suit touched by other colours (e.g. "Orange D") to the colour clue values that touch it. */
+	pub dual_colours: Option<HashMap<usize, Vec<usize>>>,
 }
 
 #[derive(Default)]
 pub struct VariantOpts {
 	pub critical_rank: Option<usize>,
 	pub clue_starved: Option<bool>,
+	pub up_or_down: Option<bool>,
+	pub no_five_regain: Option<bool>,
 	pub special_rank: Option<usize>,
 	pub rainbow_s: Option<bool>,
 	pub white_s: Option<bool>,
 	pub pink_s: Option<bool>,
 	pub brown_s: Option<bool>,
 	pub deceptive_s: Option<bool>,
+	pub clue_ranks: Option<Vec<usize>>,
+	/** The clue colours that touch each suit not touched by its own colour (e.g. "Orange D" by red and yellow), keyed by suit. */
+	pub clue_colours: HashMap<String, Vec<String>>,
 }
 
 impl Variant {
 	pub fn new(id: u32, name: &str, suit_strs: &[&str], short_strs: &[&str], opts: VariantOpts) -> Self {
-		let VariantOpts { critical_rank, clue_starved, special_rank, rainbow_s, white_s, pink_s, brown_s, deceptive_s } = opts;
+		let VariantOpts { critical_rank, clue_starved, up_or_down, no_five_regain, special_rank, rainbow_s, white_s, pink_s, brown_s, deceptive_s, clue_ranks, clue_colours } = opts;
 
-		let mut suits = Vec::new();
-		let mut short_forms = Vec::new();
-		let mut colourable_suits = Vec::new();
-
-		for i in 0..suit_strs.len() {
-			let suit = suit_strs[i].to_string();
-			let colourable = !NO_COLOUR.is_match(&suit);
-			suits.push(suit.clone());
-			short_forms.push(short_strs[i].to_string());
-
-			if colourable {
-				colourable_suits.push(suit);
-			}
-		}
+		let suits = suit_strs.iter().map(|suit| suit.to_string()).collect::<Vec<_>>();
+		let short_forms = short_strs.iter().map(|short| short.to_string()).collect();
+		let (colourable_suits, dual_colours) = suit_clue_colours(&suits, |suit| clue_colours.get(suit).cloned());
 
 		Self {
 			id,
 			name: name.to_string(),
 			suits,
 			colourable_suits: Some(colourable_suits),
+			dual_colours: Some(dual_colours),
 			short_forms: Some(short_forms),
 			critical_rank,
 			clue_starved,
+			up_or_down,
+			no_five_regain,
 			special_rank,
 			rainbow_s,
 			white_s,
 			pink_s,
 			brown_s,
-			deceptive_s
+			deceptive_s,
+			clue_ranks
 		}
 	}
 }
@@ -85,9 +95,20 @@ impl Variant {
 #[derive(Debug, Deserialize, Clone)]
 pub struct Suit {
 	pub name: String,
+	/** How variants refer to the suit, if different from its name (e.g. "Orange D"). */
+	pub id: Option<String>,
 	pub abbreviation: Option<String>,
+	#[serde(rename="clueColors")]
+	pub clue_colours: Option<Vec<String>>,
+}
+
+impl Suit {
+	fn id(&self) -> &str {
+		self.id.as_deref().unwrap_or(&self.name)
+	}
 }
 
+#[derive(Default)]
 pub struct VariantManager {
 	variants: Vec<Variant>,
 	colours: Vec<Suit>,
@@ -98,12 +119,16 @@ impl VariantManager {
 		let variants_raw = reqwest::get(VARIANTS_URL).await.expect("Failed to fetch variants.")
 			.text().await.expect("Failed to parse variants response.");
 
-		let variants = serde_json::from_str(&variants_raw).expect("Failed to parse variants response as JSON.");
-
 		let colours_raw = reqwest::get(COLOURS_URL).await.expect("Failed to fetch colours.")
 			.text().await.expect("Failed to parse colours response.");
 
-		let colours: Vec<Suit> = serde_json::from_str(&colours_raw).expect("Failed to parse colours response as JSON.");
+		Self::from_json(&variants_raw, &colours_raw)
+	}
+
+	/** Creates a variant manager from the contents of hanab.live's variants.json and suits.json. */
+	pub fn from_json(variants_raw: &str, colours_raw: &str) -> Self {
+		let variants = serde_json::from_str(variants_raw).expect("Failed to parse variants response as JSON.");
+		let colours: Vec<Suit> = serde_json::from_str(colours_raw).expect("Failed to parse colours response as JSON.");
 
 		Self { variants, colours }
 	}
@@ -122,7 +147,7 @@ impl VariantManager {
 				"Pink" => "i".to_string(),
 				"Brown" => "n".to_string(),
 				_ => {
-					if let Some(colour) = self.colours.iter().find(|colour| &colour.name == suit) {
+					if let Some(colour) = self.colours.iter().find(|colour| colour.id() == suit) {
 						let abbreviation = colour.abbreviation.clone().unwrap_or(suit[0..1].to_lowercase().to_string());
 						if !short_forms.contains(&abbreviation) {
 							abbreviation.clone()
@@ -138,10 +163,13 @@ impl VariantManager {
 			short_forms.push(short);
 		}
 
-		let colourable_suits = var.suits.iter().filter(|suit| !NO_COLOUR.is_match(suit)).map(|suit| suit.to_string()).collect();
+		let (colourable_suits, dual_colours) = suit_clue_colours(&var.suits, |suit|
+			self.colours.iter().find(|colour| colour.id() == suit).and_then(|colour| colour.clue_colours.clone()));
 
 		var.short_forms = Some(short_forms);
+		var.dual_colours = Some(dual_colours);
 		var.colourable_suits = Some(colourable_suits);
+		var.no_five_regain = Some(NO_FIVE_REGAIN.is_match(&var.name));
 		var
 	}
 }
@@ -156,6 +184,36 @@ pub static BROWNISH: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"Brown|Muddy
 pub static DARK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"Black|Dark|Gray|Cocoa").unwrap());
 pub static PRISM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"Prism").unwrap());
 pub static NO_COLOUR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"White|Gray|Light|Null|Rainbow|Omni|Prism").unwrap());
+pub static NO_FIVE_REGAIN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"Throw It in a Hole").unwrap());
+pub static UP_OR_DOWN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"Up or Down").unwrap());
+
+/**
+ * Returns the clue colours in the variant, and the colour clue values that touch each suit not touched by its own colour.
+ * A suit's clue colours are listed in its suit data (e.g. "Orange D" lists red and yellow), and are otherwise its own colour.
+ */
+fn suit_clue_colours(suits: &[String], listed_colours: impl Fn(&str) -> Option<Vec<String>>) -> (Vec<String>, HashMap<usize, Vec<usize>>) {
+	let suit_colours = suits.iter().map(|suit| {
+		if NO_COLOUR.is_match(suit) {
+			Vec::new()
+		} else {
+			listed_colours(suit).unwrap_or_else(|| vec![suit.clone()])
+		}
+	}).collect::<Vec<_>>();
+
+	let mut colourable_suits: Vec<String> = Vec::new();
+	for colour in suit_colours.iter().flatten() {
+		if !colourable_suits.contains(colour) {
+			colourable_suits.push(colour.clone());
+		}
+	}
+
+	let dual_colours = suits.iter().zip(&suit_colours).enumerate()
+		.filter(|(_, (suit, colours))| !colours.is_empty() && colours.as_slice() != [(*suit).clone()])
+		.map(|(suit_index, (_, colours))| (suit_index, colours.iter().filter_map(|c| colourable_suits.iter().position(|s| s == c)).collect()))
+		.collect();
+
+	(colourable_suits, dual_colours)
+}
 
 pub fn all_ids(variant: &Variant) -> impl Iterator<Item = Identity> {
 	(0..variant.suits.len()).flat_map(move |suit_index|
@@ -178,6 +236,7 @@ pub fn id_touched(id: Identity, variant: &Variant, clue: &BaseClue) -> bool {
 			return false;
 		}
 
+		// Also covers Muddy/Cocoa Rainbow, which are brownish and so never touched by rank
 		if RAINBOWISH.is_match(suit) {
 			return true;
 		}
@@ -195,6 +254,10 @@ pub fn id_touched(id: Identity, variant: &Variant, clue: &BaseClue) -> bool {
 			return ((rank - 1) % variant.colourable_suits.as_ref().unwrap().len()) == *value;
 		}
 
+		if let Some(colours) = variant.dual_colours.as_ref().and_then(|dual| dual.get(&suit_index)) {
+			return colours.contains(value);
+		}
+
 		variant.suits[suit_index] == variant.colourable_suits.as_ref().unwrap()[*value]
 	}
 	else {
@@ -210,7 +273,9 @@ pub fn id_touched(id: Identity, variant: &Variant, clue: &BaseClue) -> bool {
 				return false;
 			}
 			else if variant.deceptive_s.is_some_and(|c| c) {
-				return (suit_index % 4) + (if variant.special_rank.unwrap() == 1 { 2 } else { 1 }) == *value;
+				// Each suit's special rank is touched by one of the other ranks, cycling through them by suit
+				let deceptive_ranks = (1..=5).filter(|&r| r != rank).collect::<Vec<_>>();
+				return deceptive_ranks[suit_index % deceptive_ranks.len()] == *value;
 			}
 		}
 
@@ -231,7 +296,8 @@ pub fn card_touched(card: &impl Identifiable, variant: &Variant, clue: &BaseClue
 
 pub fn card_count(variant: &Variant, identity: Identity) -> usize {
 	let Identity { suit_index, rank } = identity;
-	if DARK.is_match(&variant.suits[suit_index]) || variant.critical_rank.is_some_and(|r| r == rank) {
+	// Up or Down decks have a single copy of each 1 (alongside a START card)
+	if DARK.is_match(&variant.suits[suit_index]) || variant.critical_rank.is_some_and(|r| r == rank) || (rank == 1 && variant.up_or_down.is_some_and(|u| u)) {
 		1
 	}
 	else {
@@ -1,14 +1,22 @@
 use rust_bot::{logger, websocket, console, console::DebugCommand};
+use rust_bot::basics::variant::VariantManager;
+use rust_bot::seed::deck_from_seed;
+use rust_bot::self_play::{simulate_game, PLAYER_NAMES};
 use std::{collections::HashMap, env, error::Error};
 use futures::future::pending;
 use tokio::sync::mpsc;
 
 struct Args {
-	index: usize
+	index: usize,
+	selfplay: Option<String>,
+	variant: String,
+	players: usize,
+	log_file: Option<String>,
+	log_json: bool
 }
 
 impl Args {
-	fn parse(args: &[String]) -> Self {
+	fn parse(args: &[String]) -> Result<Self, String> {
 		let mut hash_map = HashMap::new();
 
 		for arg in args {
@@ -23,21 +31,41 @@ impl Args {
 			hash_map.insert(key.to_string(), value.to_string());
 		}
 
+		let selfplay = hash_map.get("selfplay").cloned();
+		let variant = hash_map.get("variant").cloned().unwrap_or("No Variant".to_owned());
+		let players = hash_map.get("players").map_or(3, |players| players.parse().unwrap_or_else(|_| panic!("Invalid player count {players}")));
+
+		if !(2..=PLAYER_NAMES.len()).contains(&players) {
+			return Err(format!("Invalid player count {players}, must be between 2 and {}", PLAYER_NAMES.len()));
+		}
+		let log_file = hash_map.get("log_file").cloned();
+		let log_json = hash_map.get("log_json").is_some_and(|json| json == "true");
+
 		if hash_map.contains_key("index") {
 			let index = hash_map["index"].parse().unwrap();
-			Self { index }
+			Ok(Self { index, selfplay, variant, players, log_file, log_json })
 		}
 		else {
-			Self { index: 1 }
+			Ok(Self { index: 1, selfplay, variant, players, log_file, log_json })
 		}
 	}
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-	let _ = logger::init();
 	let args = env::args().collect::<Vec<String>>();
-	let Args { index } = Args::parse(&args[1..]);
+	let Args { index, selfplay, variant, players, log_file, log_json } = Args::parse(&args[1..])?;
+	let _ = logger::init_with(log_file.as_deref(), log_json);
+
+	if let Some(seed) = selfplay {
+		log::set_max_level(log::LevelFilter::Error);
+
+		let variant = VariantManager::new().await.get_variant(&variant);
+		let summary = simulate_game(&deck_from_seed(&variant, &seed), &variant, players);
+
+		println!("Seed {seed}: Score: {}, Result: {:?}", summary.score, summary.result);
+		return Ok(());
+	}
 
 	dotenvy::dotenv()?;
 
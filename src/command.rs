@@ -2,12 +2,13 @@ use log::{error, info, warn};
 use serde::{Deserialize};
 use serde_json::json;
 use tokio::{spawn, sync::mpsc, time::sleep};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::{collections::{HashMap, VecDeque}, time::Duration};
 
+use crate::logger;
 use crate::reactor::Reactor;
 use crate::websocket::{send_chat, send_cmd, send_pm};
-use crate::basics::{action::Action, game::Game, state::State, variant::VariantManager};
+use crate::basics::{action::{Action, PerformAction}, clue::{BaseClue, ClueKind}, clue_result::{bad_touch_result, elim_result, playables_result, BadTouchResult, ElimResult, PlayablesResult}, game::{Convention, Game, SimOpts, MAX_WORLDS}, player::Link, state::State, util, variant::{VariantManager, UP_OR_DOWN}};
 use crate::console::{DebugCommand, NavArg};
 
 #[derive(Deserialize)]
@@ -74,11 +75,18 @@ struct InitMessage {
 	our_player_index: usize,
 	replay: bool,
 	seed: String,
+	#[serde(default)]
+	spectating: bool,
 	options: TableOptions,
 }
 
 struct Settings {
 	convention: String,
+	verbose_notes: bool,
+	auto_terminate: bool,
+	explain: bool,
+	level: u8,
+	safe_mode: bool,
 }
 
 #[derive(Clone, Deserialize)]
@@ -101,37 +109,110 @@ struct TableIDMsg {
 
 const CONVENTIONS: [&str; 1] = ["Reactor 1.0"];
 
+/** Returns the convention with the given name, if it exists. */
+pub fn convention_by_name(name: &str) -> Option<Arc<dyn Convention + Send + Sync>> {
+	match name {
+		"Reactor 1.0" => Some(Arc::new(Reactor)),
+		_ => None
+	}
+}
+
+/** Returns the target and clue of the action, if it is a clue. */
+fn perform_clue(perform: &PerformAction) -> Option<(usize, BaseClue)> {
+	match *perform {
+		PerformAction::Colour { target, value } => Some((target, BaseClue { kind: ClueKind::COLOUR, value })),
+		PerformAction::Rank { target, value } => Some((target, BaseClue { kind: ClueKind::RANK, value })),
+		_ => None
+	}
+}
+
+/** The parts of a table's latest state that a delayed action is re-checked against. */
+struct LiveTable {
+	turn_count: usize,
+	hands: Vec<Vec<usize>>,
+}
+
+impl LiveTable {
+	fn new(state: &State) -> Self {
+		Self { turn_count: state.turn_count, hands: state.hands.clone() }
+	}
+}
+
+/** What the bot knows about a table it has joined. Removed when the bot leaves the table. */
+#[derive(Default)]
+struct Seat {
+	game_started: bool,
+	spectating: bool,
+	shadowing: Option<String>,
+}
+
 pub struct BotClient {
 	settings: Settings,
 	info: Option<SelfData>,
+	/** The table that debug commands apply to. */
 	table_id: Option<u32>,
-	pub game: Option<Game>,
-	game_started: bool,
-	last_sender: Option<String>,
+	pub games: HashMap<u32, Game>,
+	/** The latest turn and hands at each table, shared with actions waiting to be sent. */
+	live_tables: Arc<Mutex<HashMap<u32, LiveTable>>>,
+	/** How long to wait before sending an action. */
+	pub action_delay: Duration,
+	seats: HashMap<u32, Seat>,
 	tables: HashMap<u32, Table>,
 	ws: mpsc::UnboundedSender<String>,
 	variant_manager: VariantManager,
+	/** The usernames of all bot accounts. Games are only auto-terminated at tables where every player is one of them. */
+	pub bot_names: Vec<String>,
+}
+
+/** Returns whether every player is one of the bot accounts. */
+fn bot_only(bot_names: &[String], player_names: &[String]) -> bool {
+	player_names.iter().all(|name| bot_names.contains(name))
 }
 
 impl BotClient {
 	pub fn new(ws: mpsc::UnboundedSender<String>, variant_manager: VariantManager) -> Self {
 		Self {
-			settings: Settings { convention: CONVENTIONS[0].to_owned() },
+			settings: Settings { convention: CONVENTIONS[0].to_owned(), verbose_notes: false, auto_terminate: false, explain: false, level: u8::MAX, safe_mode: false },
 			info: None,
 			table_id: None,
-			game: None,
-			game_started: false,
-			last_sender: None,
+			games: HashMap::new(),
+			live_tables: Arc::new(Mutex::new(HashMap::new())),
+			action_delay: Duration::from_secs(2),
+			seats: HashMap::new(),
 			tables: HashMap::new(),
 			ws,
-			variant_manager
+			variant_manager,
+			bot_names: Vec::new(),
+		}
+	}
+
+	/** Returns the game at the current table, if any. */
+	pub fn game(&self) -> Option<&Game> {
+		self.table_id.and_then(|table_id| self.games.get(&table_id))
+	}
+
+	/** Adds the game at its table (replacing any existing game there), and makes that the current table. */
+	pub fn add_game(&mut self, game: Game) {
+		self.table_id = Some(game.table_id);
+		self.live_tables.lock().unwrap().insert(game.table_id, LiveTable::new(&game.state));
+		self.games.insert(game.table_id, game);
+	}
+
+	/** Forgets everything about the given table. */
+	fn remove_table(&mut self, table_id: u32) {
+		self.games.remove(&table_id);
+		self.live_tables.lock().unwrap().remove(&table_id);
+		self.seats.remove(&table_id);
+
+		if self.table_id == Some(table_id) {
+			self.table_id = None;
 		}
 	}
 
 	pub fn handle_debug_command(&mut self, command: DebugCommand) {
 		match command {
 			DebugCommand::Hand(player_name, from) => {
-				if let Some(game) = &self.game {
+				if let Some(game) = self.game() {
 					let state = &game.state;
 					if let Some(hand) = state.player_names.iter().position(|name| *name == player_name).map(|i| &state.hands[i]) {
 						let player = match &from {
@@ -182,7 +263,7 @@ impl BotClient {
 				}
 			}
 			DebugCommand::Navigate(nav_arg) => {
-				if let Some(game) = &mut self.game {
+				if let Some(game) = self.game() {
 					if game.in_progress {
 						warn!("Cannot navigate while game is in progress.");
 					}
@@ -190,6 +271,16 @@ impl BotClient {
 						let Game { state, .. } = game;
 
 						let turn = match nav_arg {
+							NavArg::Action(index) => {
+								if index > state.action_list.concat().len() {
+									error!("Action {index} does not exist.");
+								}
+								else {
+									let new_game = game.navigate_to_action(index);
+									self.add_game(new_game);
+								}
+								return;
+							}
 							NavArg::Turn(turn) => turn,
 							NavArg::NextRound => state.turn_count + state.num_players,
 							NavArg::Next => state.turn_count + 1,
@@ -201,22 +292,167 @@ impl BotClient {
 							error!("Turn {turn} does not exist.");
 						}
 						else {
-							self.game = Some(game.navigate(turn));
+							let new_game = game.navigate(turn);
+							self.add_game(new_game);
 						}
 					}
 				} else {
 					println!("No active game.");
 				}
 			}
+			DebugCommand::Links(from) => {
+				if let Some(game) = self.game() {
+					let state = &game.state;
+					let player = match &from {
+						None => &game.common,
+						Some(from_name) => match state.player_names.iter().position(|name| name == from_name) {
+							None => {
+								println!("Player {from_name} not found.");
+								return;
+							}
+							Some(index) => &game.players[index]
+						}
+					};
+
+					println!("links for {}", from.unwrap_or_else(|| "common".to_owned()));
+					println!("====================");
+
+					let linked_orders = player.linked_orders(state);
+
+					for link in &player.links {
+						let (kind, orders, ids) = match link {
+							Link::Promised { orders, id, target } => (format!("promised (target {target})"), orders, vec![*id]),
+							Link::Unpromised { orders, ids } => ("unpromised".to_owned(), orders, ids.clone())
+						};
+						let active = orders.iter().all(|o| linked_orders.contains(o));
+
+						println!("{kind}: orders {orders:?} ids [{}]{}", ids.iter().map(|id| state.log_id(*id)).collect::<Vec<_>>().join(","), if active { " active" } else { "" });
+					}
+
+					if player.links.is_empty() {
+						println!("no links");
+					}
+					println!("====================");
+				} else {
+					println!("No active game.");
+				}
+			}
+			DebugCommand::Eval(raw_clue) => {
+				if let Some(game) = self.game() {
+					match util::parse_clue(&game.state, &raw_clue) {
+						Err(err) => println!("{err}"),
+						Ok(clue) => {
+							let hypo_game = game.simulate_clue(&clue, SimOpts { log: true, ..SimOpts::default() });
+							let result = Reactor::get_result(game, &hypo_game, &clue);
+
+							let state = &game.state;
+							let fmt_orders = |orders: &[usize]| orders.iter().map(|&o| format!("{} {o}", state.log_iden(&state.deck[o]))).collect::<Vec<_>>().join(", ");
+							let BadTouchResult { bad_touch, trash, .. } = bad_touch_result(game, &hypo_game, clue.giver, clue.target);
+							let ElimResult { new_touched, fill, elim } = elim_result(game, &hypo_game, &hypo_game.state.hands[clue.target], &clue.list);
+							let PlayablesResult { playables, .. } = playables_result(game, &hypo_game);
+
+							let value = Reactor::eval_action(game, &Action::Clue(clue));
+
+							println!("{raw_clue}: result {result}, value {value} ({:?})", hypo_game.last_move);
+							println!("====================");
+							println!("new touched: [{}]", fmt_orders(&new_touched));
+							println!("bad touch: [{}]", fmt_orders(&bad_touch));
+							println!("trash: [{}]", fmt_orders(&trash));
+							println!("playables: [{}]", fmt_orders(&playables));
+							println!("fill: [{}]", fmt_orders(&fill));
+							println!("elim: [{}]", fmt_orders(&elim));
+							println!("====================");
+						}
+					}
+				} else {
+					println!("No active game.");
+				}
+			}
+			DebugCommand::Values => {
+				if let Some(game) = self.game() {
+					let current_player_index = game.state.current_player_index;
+
+					// Evaluate from the perspective of whoever's turn it is
+					let shadowed;
+					let game = if game.is_our_turn() { game } else {
+						shadowed = game.shadow(current_player_index);
+						&shadowed
+					};
+
+					println!("values for {}", game.state.player_names[current_player_index]);
+					println!("====================");
+
+					for (perform, value) in Reactor::action_values(game) {
+						println!("{}: {value}", perform.fmt(game));
+					}
+				} else {
+					println!("No active game.");
+				}
+			}
+			DebugCommand::Worlds => {
+				if let Some(game) = self.game() {
+					let count = game.possible_worlds_count();
+					println!("possible worlds: {count}{}", if count == MAX_WORLDS { "+" } else { "" });
+				} else {
+					println!("No active game.");
+				}
+			}
+			DebugCommand::Save(path) => {
+				if let Some(game) = self.game() {
+					match std::fs::write(&path, game.to_json().to_string()) {
+						Ok(_) => println!("Saved game to {path}."),
+						Err(err) => println!("Failed to save game to {path}: {err}"),
+					}
+				} else {
+					println!("No active game.");
+				}
+			}
+			DebugCommand::Load(path) => {
+				let json = match std::fs::read_to_string(&path).map_err(|err| err.to_string())
+					.and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).map_err(|err| err.to_string())) {
+					Ok(json) => json,
+					Err(err) => {
+						println!("Failed to read game from {path}: {err}");
+						return;
+					}
+				};
+
+				let Some(variant_name) = json["variant"].as_str() else {
+					println!("Saved game in {path} has no variant.");
+					return;
+				};
+				let variant = self.variant_manager.get_variant(variant_name);
+
+				let convention = convention_by_name(&self.settings.convention).unwrap_or_else(|| panic!("Unknown convention {}!", self.settings.convention));
+
+				match Game::from_json(&json, Arc::new(variant), convention) {
+					Ok(game) => {
+						println!("Loaded game from {path}.");
+						self.add_game(game);
+					}
+					Err(err) => println!("Failed to load game from {path}: {err}"),
+				}
+			}
 		}
 	}
 
-	fn assign_settings(&mut self, data: &ChatMessage, in_pm: bool) {
-		let reply: Box<dyn Fn(&str)> = match in_pm {
-			true => Box::new(|msg: &str| send_pm(&self.ws, &data.who, msg)),
-			false => Box::new(|msg: &str| send_chat(&self.ws, &self.table_id.unwrap().to_string(), msg)),
+	fn assign_settings(&mut self, data: &ChatMessage, table_id: Option<u32>) {
+		let room = table_id.map(|table_id| table_id.to_string());
+		let reply: Box<dyn Fn(&str)> = match &room {
+			None => Box::new(|msg: &str| send_pm(&self.ws, &data.who, msg)),
+			Some(room) => Box::new(|msg: &str| send_chat(&self.ws, room, msg)),
 		};
 
+		let name = data.msg.trim_start_matches("/setall").trim();
+
+		if !name.is_empty() {
+			if convention_by_name(name).is_none() {
+				reply(&format!("Unknown convention {name}. Available conventions: {}.", CONVENTIONS.join(", ")));
+				return;
+			}
+			self.settings.convention = name.to_owned();
+		}
+
 		reply(&format!("Currently playing with {} conventions.", self.settings.convention));
 	}
 
@@ -243,11 +479,11 @@ impl BotClient {
 
 					let GameActionListMessage { table_id, mut list } = serde_json::from_str::<GameActionListMessage>(args).unwrap();
 
-					self.game.as_mut().unwrap().catchup = true;
+					self.games.get_mut(&table_id).unwrap().catchup = true;
 					for _ in 0..list.len() - 1 {
 						self.handle_action(GameActionMessage { table_id, action: list.pop_front().unwrap() })
 					}
-					self.game.as_mut().unwrap().catchup = false;
+					self.games.get_mut(&table_id).unwrap().catchup = false;
 					self.handle_action(GameActionMessage { table_id, action: list.pop_front().unwrap() });
 
 					send_cmd(&self.ws, "loaded", &json!({ "tableID": table_id }).to_string());
@@ -255,12 +491,14 @@ impl BotClient {
 				"joined" => {
 					let TableIDMsg { table_id } = serde_json::from_str::<TableIDMsg>(args).unwrap();
 					self.table_id = Some(table_id);
-					self.game_started = false;
+					self.seats.insert(table_id, Seat::default());
 				},
 				"init" => self.handle_init(serde_json::from_str::<InitMessage>(args).unwrap()),
 				"left" => {
-					self.table_id = None;
-					self.game_started = false;
+					let table_id = serde_json::from_str::<TableIDMsg>(args).map(|json| json.table_id).ok().or(self.table_id);
+					if let Some(table_id) = table_id {
+						self.remove_table(table_id);
+					}
 				},
 				"table" => {
 					let table = serde_json::from_str::<Table>(args).unwrap();
@@ -292,23 +530,74 @@ impl BotClient {
 		}
 	}
 
-	pub fn leave_room(&mut self) {
-		let cmd = if self.game_started { "tableUnattend" } else { "tableLeave" };
-		send_cmd(&self.ws, cmd, &json!({ "tableID": self.table_id }).to_string());
+	pub fn leave_room(&mut self, table_id: u32) {
+		let game_started = self.seats.get(&table_id).is_some_and(|seat| seat.game_started);
+		let cmd = if game_started { "tableUnattend" } else { "tableLeave" };
+		send_cmd(&self.ws, cmd, &json!({ "tableID": table_id }).to_string());
 
-		self.table_id = None;
-		self.game = None;
-		self.game_started = false;
+		self.remove_table(table_id);
 	}
 
 	fn handle_init(&mut self, data: InitMessage) {
-		let InitMessage { table_id, player_names, our_player_index, options, .. } = data;
+		let InitMessage { table_id, player_names, our_player_index, spectating, options, .. } = data;
 		let variant = self.variant_manager.get_variant(&options.variant_name);
+
+		let seat = self.seats.entry(table_id).or_default();
+		seat.game_started = true;
+		seat.spectating = spectating;
+
+		// When spectating, reason from the seat being shadowed (either requested with /shadow, or as shown on the table)
+		let shadowed_index = spectating.then(|| {
+			let username = &self.info.as_ref()?.username;
+			match &self.seats[&table_id].shadowing {
+				Some(name) => player_names.iter().position(|n| n == name),
+				None => self.tables.get(&table_id)?.spectators.iter()
+					.find(|spectator| &spectator.name == username && spectator.shadowing_player_index >= 0)
+					.map(|spectator| spectator.shadowing_player_index as usize)
+			}
+		}).flatten();
+		let our_player_index = shadowed_index.unwrap_or(our_player_index);
+
 		let state = State::new(player_names, our_player_index, Arc::new(variant.clone()));
 
-		self.table_id = Some(table_id);
-		self.game = Some(Game::new(table_id, state, true, Arc::new(Reactor)));
-		send_cmd(&self.ws, "getGameInfo2", &json!({ "tableID": self.table_id }).to_string());
+		let convention = convention_by_name(&self.settings.convention).unwrap_or_else(|| panic!("Unknown convention {}!", self.settings.convention));
+		let mut game = Game::new(table_id, state, true, convention);
+		game.verbose_notes = self.settings.verbose_notes;
+		game.auto_terminate = self.settings.auto_terminate && bot_only(&self.bot_names, &game.state.player_names);
+		game.level = self.settings.level;
+		game.safe_mode = self.settings.safe_mode;
+
+		self.add_game(game);
+		send_cmd(&self.ws, "getGameInfo2", &json!({ "tableID": table_id }).to_string());
+	}
+
+	fn shadow(&mut self, table_id: u32, name: Option<&str>) {
+		let room = table_id.to_string();
+
+		let Some(seat) = self.seats.get_mut(&table_id).filter(|seat| seat.spectating) else {
+			send_chat(&self.ws, &room, "Can only shadow a player while spectating.");
+			return;
+		};
+
+		let Some(name) = name else {
+			send_chat(&self.ws, &room, "Usage: /shadow <name>");
+			return;
+		};
+
+		let Some(game) = self.games.get(&table_id) else {
+			seat.shadowing = Some(name.to_owned());
+			return;
+		};
+
+		match game.state.player_names.iter().position(|n| n == name) {
+			Some(player_index) => {
+				let shadowed = game.shadow(player_index);
+				seat.shadowing = Some(name.to_owned());
+				self.add_game(shadowed);
+				send_chat(&self.ws, &room, &format!("Shadowing {name}."));
+			}
+			None => send_chat(&self.ws, &room, &format!("Player {name} not found."))
+		}
 	}
 
 	fn handle_chat(&mut self, data: ChatMessage) {
@@ -316,11 +605,76 @@ impl BotClient {
 		let within_room = recipient.is_empty() && room.starts_with("table");
 
 		if within_room {
+			// Commands sent in a table's chat apply to that table
+			let Ok(table_id) = room.trim_start_matches("table").parse::<u32>() else {
+				return;
+			};
+			let room = table_id.to_string();
+
+			if self.games.contains_key(&table_id) {
+				self.table_id = Some(table_id);
+			}
+
 			if msg.starts_with("/setall") {
-				self.assign_settings(&data, false);
+				self.assign_settings(&data, Some(table_id));
 			}
 			else if msg.starts_with("/leaveall") {
-				self.leave_room();
+				self.leave_room(table_id);
+			}
+			else if msg.starts_with("/notesverbose") {
+				self.settings.verbose_notes = !self.settings.verbose_notes;
+				for game in self.games.values_mut() {
+					game.verbose_notes = self.settings.verbose_notes;
+				}
+				let status = if self.settings.verbose_notes { "enabled" } else { "disabled" };
+				send_chat(&self.ws, &room, &format!("Verbose notes {status}."));
+			}
+			else if msg.starts_with("/autoterminate") {
+				self.settings.auto_terminate = !self.settings.auto_terminate;
+				for game in self.games.values_mut() {
+					game.auto_terminate = self.settings.auto_terminate && bot_only(&self.bot_names, &game.state.player_names);
+				}
+				let status = if self.settings.auto_terminate { "enabled" } else { "disabled" };
+				send_chat(&self.ws, &room, &format!("Auto-terminate {status}."));
+			}
+			else if msg.starts_with("/explain") {
+				match msg.split_whitespace().nth(1) {
+					Some("on") => self.settings.explain = true,
+					Some("off") => self.settings.explain = false,
+					_ => {
+						send_chat(&self.ws, &room, "Usage: /explain on|off");
+						return;
+					}
+				}
+				let status = if self.settings.explain { "enabled" } else { "disabled" };
+				send_chat(&self.ws, &room, &format!("Explanations {status}."));
+			}
+			else if msg.starts_with("/safemode") {
+				self.settings.safe_mode = !self.settings.safe_mode;
+				for game in self.games.values_mut() {
+					game.safe_mode = self.settings.safe_mode;
+				}
+				let status = if self.settings.safe_mode { "enabled" } else { "disabled" };
+				send_chat(&self.ws, &room, &format!("Safe mode {status}."));
+			}
+			else if msg.starts_with("/level") {
+				match msg.split_whitespace().nth(1).map(|level| level.parse::<u8>()) {
+					Some(Ok(level)) => {
+						self.settings.level = level;
+						for game in self.games.values_mut() {
+							game.level = level;
+						}
+					}
+					Some(Err(_)) => {
+						send_chat(&self.ws, &room, "Usage: /level <level>");
+						return;
+					}
+					None => ()
+				}
+				send_chat(&self.ws, &room, &format!("Playing at level {}.", self.settings.level));
+			}
+			else if msg.starts_with("/shadow") {
+				self.shadow(table_id, msg.split_whitespace().nth(1));
 			}
 			return;
 		}
@@ -336,6 +690,9 @@ impl BotClient {
 				.max_by_key(|table| table.id);
 
 			match table {
+				// START cards are not modelled, so Up or Down decks can't be tracked
+				Some(table) if UP_OR_DOWN.is_match(&table.variant) =>
+					send_pm(&self.ws, who, "Could not join, as Up or Down variants are not supported."),
 				Some(table) => {
 					if table.password_protected {
 						let password = msg.split_whitespace().nth(1);
@@ -355,12 +712,8 @@ impl BotClient {
 		}
 
 		if msg.starts_with("/rejoin") {
-			if self.game.is_some() {
-				send_pm(&self.ws, who, "Could not rejoin, as the bot is already in a game.");
-			}
-
 			let table = &self.tables.values().filter(|table|
-					table.players.contains(&self.info.as_ref().unwrap().username))
+					table.players.contains(&self.info.as_ref().unwrap().username) && !self.games.contains_key(&table.id))
 				.max_by_key(|table| table.id);
 
 			match table {
@@ -376,9 +729,11 @@ impl BotClient {
 	}
 
 	pub fn handle_action(&mut self, data: GameActionMessage) {
-		let GameActionMessage { action, .. } = data;
-		if let Some(game) = &mut self.game {
+		let GameActionMessage { table_id, action } = data;
+		if let Some(game) = self.games.get_mut(&table_id) {
+			logger::set_context(Some(table_id), Some(game.state.turn_count));
 			game.handle_action(&action);
+			self.live_tables.lock().unwrap().insert(table_id, LiveTable::new(&game.state));
 
 			for (cmd, arg) in &game.queued_cmds {
 				send_cmd(&self.ws, cmd, arg);
@@ -387,7 +742,7 @@ impl BotClient {
 			game.queued_cmds.clear();
 
 			let Game { state, table_id, .. } = &game;
-			let perform = !game.catchup && state.current_player_index == state.our_player_index &&
+			let perform = !game.catchup && game.is_our_turn() &&
 				!state.ended() &&
 				match action {
 					Action::Turn { .. } => true,
@@ -397,18 +752,43 @@ impl BotClient {
 
 			if perform {
 				let suggested_action = game.take_action();
-				info!("Suggested action: {}", suggested_action.fmt(game));
+				let action_str = suggested_action.fmt(game);
+				info!("Suggested action: {action_str}");
 
 				if game.in_progress {
 					let ws = self.ws.clone();
+					let live_tables = Arc::clone(&self.live_tables);
+					let (delay, turn_count) = (self.action_delay, state.turn_count);
+
+					// Cards keep their identities, so a clue still touches the cards it touched now if they are still in the target's hand
+					let touched = perform_clue(&suggested_action).map(|(target, clue)| (target, state.clue_touched(&state.hands[target], &clue)));
 					let arg = suggested_action.json(*table_id).to_string();
 
+					let explanation = self.settings.explain.then(|| game.convention.explain_action(game, &suggested_action));
+					let table_id = *table_id;
+
 					spawn(async move {
-						sleep(Duration::from_secs(2)).await;
+						sleep(delay).await;
+
+						// The game may have moved on (or been left) while waiting
+						let valid = live_tables.lock().unwrap().get(&table_id).is_some_and(|live|
+							live.turn_count == turn_count && touched.as_ref().is_none_or(|(target, orders)| orders.iter().any(|o| live.hands[*target].contains(o))));
+
+						if !valid {
+							warn!("Suggested action {action_str} is no longer valid, not sending it!");
+							return;
+						}
+
 						send_cmd(&ws, "action", &arg);
+
+						if let Some(explanation) = explanation {
+							send_chat(&ws, &table_id.to_string(), &explanation);
+						}
 					});
 				}
 			}
+
+			logger::set_context(None, None);
 		}
 	}
 }
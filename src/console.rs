@@ -6,33 +6,46 @@ use tokio::sync::mpsc;
 pub enum DebugCommand {
 	Hand(String, Option<String>),
 	Navigate(NavArg),
+	Links(Option<String>),
+	Eval(String),
+	Values,
+	Worlds,
+	Save(String),
+	Load(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NavArg {
-	PrevRound, Prev, Next, NextRound, Turn(usize)
+	PrevRound, Prev, Next, NextRound, Turn(usize), Action(usize)
 }
 
 impl DebugCommand {
 	pub fn parse(input: &str) -> Option<Self> {
 		let parts: Vec<&str> = input.split_whitespace().collect();
 
-		if parts.len() < 2 {
+		if parts.is_empty() {
 			return None;
 		}
 
 		match parts[0].to_lowercase().as_str() {
-			"hand" | "h" => Some(DebugCommand::Hand(parts[1].to_string(), parts.get(2).map(|s| s.to_owned().to_owned()))),
+			"hand" | "h" => Some(DebugCommand::Hand(parts.get(1)?.to_string(), parts.get(2).map(|s| s.to_owned().to_owned()))),
 			"navigate" | "nav" => {
-				let arg = match parts[1] {
+				let arg = match *parts.get(1)? {
 					"++" => NavArg::NextRound,
 					"+" => NavArg::Next,
 					"--" => NavArg::PrevRound,
 					"-" => NavArg::Prev,
+					"action" | "a" => NavArg::Action(parts.get(2)?.parse().unwrap()),
 					x => NavArg::Turn(x.parse().unwrap()),
 				};
 				Some(DebugCommand::Navigate(arg))
 			},
+			"links" | "l" => Some(DebugCommand::Links(parts.get(1).map(|s| s.to_string()))),
+			"eval" | "e" => (parts.len() > 1).then(|| DebugCommand::Eval(parts[1..].join(" "))),
+			"values" | "v" => Some(DebugCommand::Values),
+			"worlds" | "w" => Some(DebugCommand::Worlds),
+			"save" => Some(DebugCommand::Save(parts.get(1)?.to_string())),
+			"load" => Some(DebugCommand::Load(parts.get(1)?.to_string())),
 			_ => None,
 		}
 	}
@@ -1,7 +1,17 @@
+/*!
+ * Convention-independent helpers for recognising fix clues and simple connections.
+ * These only rely on the common knowledge of a [`Game`], so any convention can build on them.
+ */
+
 use crate::basics::action::{Action, ClueAction};
 use crate::basics::card::{IdOptions, Identifiable, Identity, MatchOptions};
 use crate::basics::game::{Game};
 
+/**
+ * Compares the games before and after a clue, returning the orders it fixed as (clued resets, duplicate reveals).
+ * A clued reset is a touched card whose inferences were emptied by the clue.
+ * A duplicate reveal is a previously-clued card shown to be the same identity as another previously-clued card touched by the clue.
+ */
 pub fn check_fix(prev: &Game, game: &Game, action: &ClueAction) -> (Vec<usize>, Vec<usize>) {
 	let ClueAction { list, .. } = action;
 	let Game { common, state, .. } = game;
@@ -28,6 +38,11 @@ pub fn check_fix(prev: &Game, game: &Game, action: &ClueAction) -> (Vec<usize>,
 	(clued_resets, duplicate_reveals)
 }
 
+/**
+ * Simulates each player from the start player up to the target player playing their obvious playables.
+ * Returns [99] as soon as the identity becomes playable, otherwise the target's own obvious playables once their turn is reached.
+ * An empty list means nothing connects.
+ */
 pub fn connectable_simple(game: &Game, start: usize, target: usize, id: Option<Identity>) -> Vec<usize> {
 	let Game { state, players, .. } = game;
 
@@ -36,7 +51,7 @@ pub fn connectable_simple(game: &Game, start: usize, target: usize, id: Option<I
 	}
 
 	if start == target {
-		return players[target].obvious_playables(&game.frame(), target);
+		return game.frame().playable_orders(&players[target], target);
 	}
 
 	if game.state.ended() {
@@ -44,7 +59,7 @@ pub fn connectable_simple(game: &Game, start: usize, target: usize, id: Option<I
 	}
 
 	let next_player_index = state.next_player_index(start);
-	let playables = players[start].obvious_playables(&game.frame(), start);
+	let playables = game.frame().playable_orders(&players[start], start);
 
 	for order in playables {
 		let play_id = players[start].thoughts[order].identity(&IdOptions { infer: true, ..Default::default() });
@@ -63,3 +78,83 @@ pub fn connectable_simple(game: &Game, start: usize, target: usize, id: Option<I
 	}
 	connectable_simple(game, next_player_index, target, id)
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::basics;
+	use crate::basics::clue::{BaseClue, ClueKind};
+	use crate::basics::game::SimOpts;
+	use crate::basics::identity_set::IdentitySet;
+	use crate::basics::test_util::{pre_clue, setup};
+
+	const RED: BaseClue = BaseClue { kind: ClueKind::COLOUR, value: 0 };
+	const BLUE: BaseClue = BaseClue { kind: ClueKind::COLOUR, value: 3 };
+
+	fn fix_game() -> Game {
+		let mut game = setup(&[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["g4", "b3", "r4", "r4", "p4"],
+			&["g1", "b1", "y3", "y1", "g5"],
+		]);
+		game.state.play_stacks = vec![0, 0, 0, 1, 0];
+		for player in game.players.iter_mut().chain(std::iter::once(&mut game.common)) {
+			player.hypo_stacks = game.state.play_stacks.clone();
+		}
+
+		// Bob's blue card is thought to be b2, but it is actually b3
+		pre_clue(&mut game, 1, 2, &[BLUE]);
+		let order = game.state.hands[1][1];
+		game.common.thoughts[order].inferred = IdentitySet::single(game.state.expand_short("b2"));
+
+		for slot in [3, 4] {
+			pre_clue(&mut game, 1, slot, &[RED]);
+		}
+		basics::elim(&mut game, true);
+		game
+	}
+
+	fn check_clue_fix(game: &Game, clue: BaseClue) -> (Vec<usize>, Vec<usize>) {
+		let list = game.state.clue_touched(&game.state.hands[1], &clue);
+		let action = ClueAction { giver: 0, target: 1, list, clue };
+		check_fix(game, &game.simulate_clue(&action, SimOpts::default()), &action)
+	}
+
+	#[test]
+	fn it_checks_clued_reset_fixes() {
+		let game = fix_game();
+		let bob = &game.state.hands[1];
+
+		// 3 contradicts Bob's b2 inference, resetting it.
+		assert_eq!(check_clue_fix(&game, BaseClue { kind: ClueKind::RANK, value: 3 }), (vec![bob[1]], vec![]));
+		assert!(check_clue_fix(&game, BaseClue { kind: ClueKind::RANK, value: 4 }).0.is_empty());
+	}
+
+	#[test]
+	fn it_checks_duplicate_reveal_fixes() {
+		let game = fix_game();
+		let bob = &game.state.hands[1];
+
+		// 4 reveals that both of Bob's red cards are r4.
+		assert_eq!(check_clue_fix(&game, BaseClue { kind: ClueKind::RANK, value: 4 }), (vec![], vec![bob[2], bob[3]]));
+	}
+
+	#[test]
+	fn it_finds_simple_connections() {
+		let hands: [&[&str]; 3] = [
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["g4", "r1", "b3", "y4", "p4"],
+			&["r2", "b1", "y3", "y1", "g5"],
+		];
+
+		// Nothing connects to Cathy's r2 yet.
+		let mut game = setup(&hands);
+		let r2 = game.state.expand_short("r2");
+		assert!(connectable_simple(&game, 1, 2, Some(r2)).is_empty());
+
+		// Once Bob knows his r1, he will play into it.
+		pre_clue(&mut game, 1, 2, &[RED, BaseClue { kind: ClueKind::RANK, value: 1 }]);
+		basics::elim(&mut game, true);
+		assert_eq!(connectable_simple(&game, 1, 2, Some(r2)), vec![99]);
+	}
+}
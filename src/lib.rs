@@ -5,3 +5,6 @@ pub mod reactor;
 pub mod fix;
 pub mod console;
 pub mod logger;
+pub mod replay;
+pub mod seed;
+pub mod self_play;
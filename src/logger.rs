@@ -1,13 +1,114 @@
 use colored::Colorize;
+use log::kv::{Key, Value as KvValue, VisitSource};
 use log::{SetLoggerError, LevelFilter, Record, Level, Metadata};
+use serde_json::{json, Map, Value};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 static LOGGER: SimpleLogger = SimpleLogger;
 
+/** The files that logs are also written to. */
+static LOG_FILES: Mutex<Option<LogFiles>> = Mutex::new(None);
+
+thread_local! {
+    /** The table and turn that log lines on this thread belong to. */
+    static CONTEXT: Cell<(Option<u32>, Option<usize>)> = const { Cell::new((None, None)) };
+}
+
+struct LogFiles {
+    path: String,
+    json: bool,
+    /** The open files, keyed by table (None for lines that don't belong to a table). */
+    files: HashMap<Option<u32>, File>,
+}
+
+impl LogFiles {
+    fn file(&mut self, table_id: Option<u32>) -> Option<&mut File> {
+        if !self.files.contains_key(&table_id) {
+            let path = table_id.map_or_else(|| PathBuf::from(&self.path), |table_id| table_path(&self.path, table_id));
+            match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => { self.files.insert(table_id, file); },
+                Err(err) => eprintln!("Failed to open log file {}: {err}", path.display()),
+            }
+        }
+        self.files.get_mut(&table_id)
+    }
+}
+
 pub fn init() -> Result<(), SetLoggerError> {
     log::set_logger(&LOGGER)
         .map(|()| log::set_max_level(LevelFilter::Info))
 }
 
+/**
+ * Initializes the logger, additionally appending every log line to the file at the given path.
+ * Lines logged while handling a table go to a separate file per table, with the table id before the extension (e.g. "bot.1234.log").
+ * If json is set, each line in the files is a JSON object with the level, message, turn and any structured fields (e.g. interp, action, value).
+ * The files are set even if a logger was already initialized.
+ */
+pub fn init_with(path: Option<&str>, json: bool) -> Result<(), SetLoggerError> {
+    if let Some(path) = path {
+        let mut log_files = LogFiles { path: path.to_owned(), json, files: HashMap::new() };
+        if log_files.file(None).is_some() {
+            *LOG_FILES.lock().unwrap() = Some(log_files);
+        }
+    }
+    init()
+}
+
+/** Returns the path of the log file for the table, given the path of the main log file. */
+pub fn table_path(path: &str, table_id: u32) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+
+    match path.extension() {
+        Some(extension) => path.with_file_name(format!("{stem}.{table_id}.{}", extension.to_string_lossy())),
+        None => path.with_file_name(format!("{stem}.{table_id}")),
+    }
+}
+
+/** Sets the table and turn that later log lines on this thread belong to. */
+pub fn set_context(table_id: Option<u32>, turn: Option<usize>) {
+    CONTEXT.set((table_id, turn));
+}
+
+/** Formats a log line as a single line of JSON, with the turn and structured fields if present. */
+pub fn json_line(level: Level, message: &str, turn: Option<usize>, fields: Map<String, Value>) -> String {
+    let mut line = json!({ "level": level.as_str(), "message": message });
+
+    if let Some(turn) = turn {
+        line["turn"] = json!(turn);
+    }
+
+    line.as_object_mut().unwrap().extend(fields);
+    line.to_string()
+}
+
+/** Collects the structured fields of a log record as JSON values. */
+struct JsonFields(Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: KvValue<'kvs>) -> Result<(), log::kv::Error> {
+        // Only values recorded as numbers become JSON numbers, so that strings like "05" are kept as written
+        let number = if let Some(int) = value.to_i64() {
+            Some(serde_json::Number::from(int))
+        } else if let Some(int) = value.to_u64() {
+            Some(serde_json::Number::from(int))
+        } else if value.to_f64().is_some() {
+            // Floats are parsed from their display form, so that e.g. an f32 of 0.1 isn't widened to 0.10000000149
+            value.to_string().parse::<f64>().ok().and_then(serde_json::Number::from_f64)
+        } else {
+            None
+        };
+        self.0.insert(key.to_string(), number.map_or_else(|| Value::String(value.to_string()), Value::Number));
+        Ok(())
+    }
+}
+
 struct SimpleLogger;
 
 impl log::Log for SimpleLogger {
@@ -24,8 +125,52 @@ impl log::Log for SimpleLogger {
 	            Level::Debug => println!("{}", format!("{}", record.args()).purple()),
 	            Level::Trace => println!("{}", record.args()),
 	        }
+
+	        if let Some(log_files) = LOG_FILES.lock().unwrap().as_mut() {
+	            // Messages may already be coloured, which isn't wanted in a file
+	            let message = strip_colours(&record.args().to_string());
+	            let (table_id, turn) = CONTEXT.get();
+
+	            let line = if log_files.json {
+	                let mut fields = JsonFields(Map::new());
+	                let _ = record.key_values().visit(&mut fields);
+	                json_line(record.level(), &message, turn, fields.0)
+	            } else {
+	                message
+	            };
+
+	            if let Some(file) = log_files.file(table_id) {
+	                let _ = writeln!(file, "{line}");
+	            }
+	        }
         }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Some(log_files) = LOG_FILES.lock().unwrap().as_mut() {
+            for file in log_files.files.values_mut() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/** Removes ANSI colour codes from a message. */
+fn strip_colours(message: &str) -> String {
+    let mut stripped = String::with_capacity(message.len());
+    let mut chars = message.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip until the end of the escape sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
 }
@@ -1,27 +1,56 @@
-use core::f32;
 use colored::Colorize;
 use fraction::Fraction;
 use log::{info, warn};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::basics;
 use crate::basics::card::{CardStatus, IdOptions, Identifiable, Identity};
-use crate::basics::endgame::{EndgameSolver};
+use crate::basics::endgame::{EndgameSolver, SolverError};
 use crate::basics::game::SimOpts;
 use crate::basics::game::{Convention, frame::Frame, Game, Interp};
 use crate::basics::action::{Action, ClueAction, DiscardAction, PerformAction, PlayAction, TurnAction};
+use crate::basics::clue::ClueKind;
 use crate::basics::util;
 use crate::fix::check_fix;
 
+mod explain;
 mod interpret_clue;
 mod interpret_reaction;
 mod state_eval;
 
 pub struct Reactor;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+/** The minimum convention level for reactive finesses (reacting to a card that is one away from playable). */
+pub const FINESSE_LEVEL: u8 = 2;
+/** The minimum convention level for sacrificing a potentially critical card, or targeting one with a reactive clue. */
+pub const SACRIFICE_LEVEL: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ClueInterp {
-	Illegal, Mistake, Reactive, RefPlay, RefDiscard, Lock, Reveal, Fix, Reclue, Stall
+	Illegal, Mistake, Reactive, RefPlay, RefDiscard, Lock, Reveal, Fix, Reclue, Stall, FiveStall
+}
+
+/** How safe a clue is to give, for tools that want to flag bad clues. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClueSafety {
+	Safe,
+	/** The clue newly touches the given orders, which are trash or duplicated. */
+	BadTouch(Vec<usize>),
+	CausesLock,
+	/** The clue is illegal or would be interpreted as a mistake. */
+	Illegal,
+}
+
+/** What a clue would mean if we gave it, for tools that want to show a clue's effect. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CluePreview {
+	/** The orders that the clue would touch for the first time. */
+	pub newly_touched: Vec<usize>,
+	/** The focus of the clue as a stable clue, which is the leftmost newly touched card. */
+	pub focus: Option<usize>,
+	/** The card referenced for play or discard, if the clue would be a referential clue. */
+	pub ref_target: Option<usize>,
+	pub interp: ClueInterp,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -46,12 +75,177 @@ impl Reactor {
 		}
 	}
 
-	pub fn chop(game: &Game, player_index: usize) -> Option<&usize> {
-		let Game { state, meta, .. } = game;
-		if let Some(zcs) = state.hands[player_index].iter().find(|&&o| meta[o].status == CardStatus::ZeroClueChop) {
-			return Some(zcs);
+	pub fn chop(game: &Game, player_index: usize) -> Option<usize> {
+		game.frame().chop(player_index)
+	}
+
+	/** Returns whether safe mode forbids us from discarding the card, since it might be critical and we could stall with a clue instead. */
+	fn unsafe_discard(game: &Game, order: usize) -> bool {
+		let Game { state, .. } = game;
+		game.safe_mode && state.can_clue() && game.us().thoughts[order].possibilities().iter().any(|i| state.is_critical(i))
+	}
+
+	/**
+	 * Returns whether we are one strike from losing with no safe action left:
+	 * no card we know is playable, no clue to give, and no card we know isn't critical to discard.
+	 */
+	fn no_safe_action(game: &Game) -> bool {
+		let Game { state, .. } = game;
+		let me = game.us();
+
+		state.strikes == 2 && !state.can_clue() && state.our_hand().iter().all(|&o| {
+			let possible = me.thoughts[o].possible;
+			!possible.iter().all(|i| state.is_playable(i)) && possible.iter().any(|i| state.is_critical(i))
+		})
+	}
+
+	/** Returns the actions we would choose between on our turn, paired with the action each would produce. */
+	fn candidate_actions(game: &Game) -> Vec<(PerformAction, Action)> {
+		let Game { common, state, meta, .. } = game;
+		let frame = game.frame();
+		let me = game.us();
+
+		let mut playable_orders = me.thinks_playables(&frame, state.our_player_index);
+
+		// A called card that can no longer be playable would only bomb
+		playable_orders.retain(|&o| meta[o].status != CardStatus::CalledToPlay || me.thoughts[o].possible.iter().any(|i| state.is_playable(i)));
+
+		// Good touch only queues a card once another player has had a turn to fix it
+		playable_orders.retain(|&o| meta[o].queued || meta[o].status != CardStatus::None || me.thoughts[o].possible.iter().all(|i| state.is_playable(i)));
+		let mut discard_orders = me.discardable(&frame, state.our_player_index);
+		discard_orders.retain(|&o| !Reactor::unsafe_discard(game, o));
+
+		// Retain only signalled playables if there is at least 1 such
+		if playable_orders.iter().any(|&o| me.order_kp(&frame, o)) {
+			playable_orders.retain(|&o| me.order_kp(&frame, o));
+		}
+
+		info!("playables {playable_orders:?}");
+		info!("discardable {discard_orders:?}");
+
+		let all_clues = if !state.can_clue() || common.waiting.as_ref().is_some_and(|w| w.receiver == state.our_player_index) { Vec::new() } else {
+			(1..state.num_players).flat_map(|offset| {
+				let target = (state.our_player_index + offset) % state.num_players;
+				state.all_valid_clues(target)
+			}).map(|clue| {
+				let perform = util::clue_to_perform(&clue);
+				let action = util::perform_to_action(state, &perform, state.our_player_index, None);
+				(perform, action)
+			}).collect()
+		};
+		let num_clues = all_clues.len();
+
+		let fix_clues = if all_clues.is_empty() { Vec::new() } else {
+			game.convention.find_all_fixes(game, state.our_player_index).into_iter().map(|perform| {
+				let action = util::perform_to_action(state, &perform, state.our_player_index, None);
+				(perform, action)
+			}).collect::<Vec<_>>()
+		};
+
+		if !fix_clues.is_empty() {
+			info!("fixing broken cards");
+			return fix_clues;
+		}
+
+		let all_plays = playable_orders.iter().map(|&order| {
+			(PerformAction::Play { target: order },
+			match me.thoughts[order].identity(&IdOptions { infer: true, ..Default::default() }) {
+				Some(Identity { suit_index, rank }) => {
+					Action::play(state.our_player_index, order, suit_index as i32, rank as i32)
+				}
+				None => {
+					Action::play(state.our_player_index, order, -1, -1)
+				}
+			})
+		}).collect::<Vec<_>>();
+		let num_plays = all_plays.len();
+
+		let cant_discard = state.clue_tokens == Fraction::from(8) ||
+			(state.pace() == 0 && (num_clues > 0 || num_plays > 0)) ||
+			(num_plays > 0 && game.common.waiting.as_ref().is_some_and(|w| w.reacter == state.next_player_index(state.our_player_index)));	// If we have a play and there's a potential inversion
+		info!("can discard: {}", !cant_discard);
+
+		let all_discards = if cant_discard { Vec::new() } else {
+			discard_orders.iter().map(|&order| {
+				(PerformAction::Discard { target: order },
+				match me.thoughts[order].id() {
+					Some(Identity { suit_index, rank }) => {
+						Action::discard(state.our_player_index, order, suit_index as i32, rank as i32, false)
+					}
+					None => {
+						Action::discard(state.our_player_index, order, -1, -1, false)
+					}
+				})
+			}).collect::<Vec<_>>()
+		};
+		let num_discards = all_discards.len();
+
+		let mut all_actions = all_clues.into_iter().chain(all_plays).chain(all_discards).collect::<Vec<_>>();
+
+		// Double discard avoidance: our chop might be the last copy of the card that was just discarded
+		let dda = |chop: usize| state.can_clue() && state.last_discard().is_some_and(|(_, id)|
+			state.is_critical(id) && me.thoughts[chop].possible.contains(id));
+
+		if !cant_discard && (!state.can_clue() || num_plays == 0) && num_discards == 0 && !me.thinks_locked(&frame, state.our_player_index) &&
+			let Some(chop) = Reactor::chop(game, state.our_player_index) && !dda(chop) && !Reactor::unsafe_discard(game, chop) {
+			all_actions.push((
+				PerformAction::Discard { target: chop },
+				Action::discard(state.our_player_index, chop, -1, -1, false)
+			));
 		}
-		state.hands[player_index].iter().find(|&&o| !state.deck[o].clued && meta[o].status == CardStatus::None)
+
+		all_actions
+	}
+
+	/** Returns the most valuable clue we could give, excluding clues that would be illegal or a mistake. */
+	fn best_stall_clue(game: &Game) -> Option<PerformAction> {
+		let Game { state, .. } = game;
+
+		(1..state.num_players).flat_map(|offset| state.all_valid_clues((state.our_player_index + offset) % state.num_players)).filter_map(|clue| {
+			let base_clue = clue.to_base();
+			let list = state.clue_touched(&state.hands[clue.target], &base_clue);
+			let action = ClueAction { giver: state.our_player_index, target: clue.target, list, clue: base_clue };
+
+			let value = Reactor::clue_value(game, &action);
+			let mistake = Reactor::cached_clue(game, &action).is_some_and(|cached| Reactor::illegal_clue(&cached.interp));
+			(!mistake).then(|| (util::clue_to_perform(&clue), value))
+		}).max_by(|(_, a), (_, b)| a.total_cmp(b)).map(|(perform, _)| perform)
+	}
+
+	/** Returns whether the next player is about to discard a critical card, so a clue can't wait. */
+	fn urgent_clue(game: &Game) -> bool {
+		let Game { common, state, .. } = game;
+		let next_player_index = state.next_player_index(state.our_player_index);
+
+		state.can_clue() && common.thinks_playables(&game.frame(), next_player_index).is_empty() &&
+			Reactor::chop(game, next_player_index).is_some_and(|chop| state.deck[chop].id().is_some_and(|id| state.is_critical(id)))
+	}
+
+	/** Returns the clues the giver could give that reset or reveal any of the given orders. */
+	fn fix_clues(game: &Game, giver: usize, orders: &[usize]) -> Vec<PerformAction> {
+		let Game { state, .. } = game;
+
+		(0..state.num_players).filter(|&target| target != giver).flat_map(|target| state.all_valid_clues(target)).filter(|clue| {
+			let base_clue = clue.to_base();
+			let list = state.clue_touched(&state.hands[clue.target], &base_clue);
+			let action = ClueAction { giver, target: clue.target, list, clue: base_clue };
+
+			let hypo = game.simulate_clue(&action, SimOpts::default());
+			let (clued_resets, duplicate_reveals) = check_fix(game, &hypo, &action);
+			clued_resets.iter().chain(&duplicate_reveals).any(|o| orders.contains(o))
+		}).map(|clue| util::clue_to_perform(&clue)).collect()
+	}
+
+	/** Returns the value of each of our candidate actions, from best to worst. */
+	pub fn action_values(game: &Game) -> Vec<(PerformAction, f32)> {
+		let level = log::max_level();
+		log::set_max_level(log::LevelFilter::Off);
+
+		let mut values = Reactor::candidate_actions(game).into_iter().map(|(perform, action)| (perform, Reactor::eval_action(game, &action))).collect::<Vec<_>>();
+		values.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+		log::set_max_level(level);
+		values
 	}
 }
 
@@ -65,6 +259,8 @@ impl Convention for Reactor {
 			game.common.waiting = None;
 		}
 
+		let revealed_trash = Reactor::revealed_trash(prev, game, action);
+
 		// Force interpretation if rewinded
 		let interp = if let Some(interp) = &game.next_interp {
 			info!("forcing rewinded interp {interp:?}");
@@ -76,6 +272,14 @@ impl Convention for Reactor {
 				Reactor::interpret_stable(prev, game, action, false)
 			}
 		}
+		else if !revealed_trash.is_empty() {
+			info!("revealed queued cards {revealed_trash:?} as trash, fix!");
+			for order in revealed_trash {
+				game.meta[order].clear();
+				game.meta[order].trash = true;
+			}
+			Some(ClueInterp::Fix)
+		}
 		else if prev.common.thinks_locked(&prev.frame(), *giver) || game.state.in_endgame() || prev.state.clue_tokens == Fraction::from(8) {
 			Reactor::interpret_stable(prev, game, action, true)
 		}
@@ -86,8 +290,8 @@ impl Convention for Reactor {
 				let player_index = (giver + i) % game.state.num_players;
 
 				// The clue may reveal a new playable, or the clue may fix a bad-touched card that looked playable previously
-				let old_playables = prev.common.obvious_playables(&prev.frame(), player_index);
-				let new_playables = game.common.obvious_playables(&game.frame(), player_index);
+				let old_playables = prev.frame().playable_orders(&prev.common, player_index);
+				let new_playables = game.frame().playable_orders(&game.common, player_index);
 				let playables = old_playables.iter().filter(|o| new_playables.contains(o)).collect::<Vec<_>>();
 
 				if playables.is_empty() {
@@ -110,14 +314,20 @@ impl Convention for Reactor {
 						Reactor::interpret_stable(prev, game, action, false)
 					}
 					else {
-						let prev_playables = prev.players[*target].obvious_playables(&prev.frame(), *target);
+						let prev_playables = prev.frame().playable_orders(&prev.players[*target], *target);
 
 						// Urgent fix on previous playable
 						if allowable_fix && clued_resets.iter().chain(duplicate_reveals.iter()).any(|o| prev_playables.contains(o)) {
 							Some(ClueInterp::Fix)
 						}
 						else {
-							Reactor::interpret_reactive(prev, game, action, reacter, false)
+							// A reactive interpretation only writes common knowledge (including the waiting connection) and meta
+							let (common, meta) = (game.common.clone(), game.meta.clone());
+							Reactor::interpret_reactive(prev, game, action, reacter, false).or_else(|| {
+								warn!("reactive clue was impossible, falling back to stable!");
+								(game.common, game.meta) = (common, meta);
+								Reactor::interpret_stable(prev, game, action, false)
+							})
 						}
 					}
 				}
@@ -132,6 +342,14 @@ impl Convention for Reactor {
 
 		basics::elim(game, false);
 
+		// A playable rank clue queues the leftmost touched card immediately
+		if action.clue.kind == ClueKind::RANK && let Some(&leftmost) = game.state.hands[*target].iter().find(|o| action.list.contains(o)) {
+			let thought = &game.common.thoughts[leftmost];
+			if !thought.possibilities().is_empty() && thought.possibilities().iter().all(|i| game.state.is_playable(i)) {
+				game.meta[leftmost].queued = true;
+			}
+		}
+
 		let signalled_plays_after_elim = game.state.hands.concat().into_iter().filter(|&o| game.meta[o].status == CardStatus::CalledToPlay).collect::<Vec<_>>();
 		if signalled_plays_after_elim.len() < signalled_plays.len() {
 			warn!("lost play signal on {:?} after elim!", signalled_plays.iter().filter(|o| !signalled_plays_after_elim.contains(o)).collect::<Vec<_>>());
@@ -150,8 +368,7 @@ impl Convention for Reactor {
 
 		if !game.state.can_clue() {
 			for i in 0..game.state.num_players {
-				if let Some(chop) = Reactor::chop(game, i) {
-					let order = *chop;
+				if let Some(order) = Reactor::chop(game, i) {
 					game.meta[order].status = CardStatus::ZeroClueChop;
 					info!("writing zcs on {order}");
 				}
@@ -220,19 +437,20 @@ impl Convention for Reactor {
 	}
 
 	fn take_action(&self, game: &Game) -> PerformAction {
-		let Game { common, state, meta, .. } = game;
-		let frame = game.frame();
-		let me = game.me();
+		let Game { state, meta, .. } = game;
+		let me = game.us();
 
 		if let Some(urgent) = state.our_hand().iter().map(|&o| &meta[o]).find(|&t| t.urgent) {
 			match urgent.status {
 				CardStatus::CalledToPlay => {
-					if !me.thoughts[urgent.order].possible.iter().all(|i| state.is_basic_trash(i)) {
+					if me.thoughts[urgent.order].possible.iter().any(|i| state.is_playable(i)) {
 						return PerformAction::Play { target: urgent.order }
 					}
 				}
 				CardStatus::CalledToDiscard => {
-					return PerformAction::Discard { target: urgent.order }
+					if !Reactor::unsafe_discard(game, urgent.order) {
+						return PerformAction::Discard { target: urgent.order }
+					}
 				}
 				_ => {
 					warn!("Unexpected urgent card status {:?}", urgent.status);
@@ -246,94 +464,67 @@ impl Convention for Reactor {
 			let mut solver = EndgameSolver::new(true);
 			let cloned_game = game.clone();
 
-			let result = solver.solve_game(&cloned_game);
+			let result = solver.solve_game_default(&cloned_game);
 			match result {
 				Ok((perform, _)) => return perform,
 				Err(err) => {
 					info!("couldn't solve endgame: {err}");
+
+					// Only a full search (rather than sampling arrangements) proves that the game is lost
+					let proven_loss = |err: SolverError| err == SolverError::Unwinnable ||
+						(err == SolverError::NoWinFound && EndgameSolver::new(false).solve_game_default(&cloned_game) == Err(SolverError::Unwinnable));
+
+					if game.auto_terminate && Reactor::no_safe_action(game) && proven_loss(err) {
+						info!("{}", "game is lost and any action risks a strikeout, terminating".purple());
+						return PerformAction::Terminate { target: state.our_player_index, value: 0 };
+					}
 				}
 			}
 		}
 
-		let mut playable_orders = me.thinks_playables(&frame, state.our_player_index);
-		let discard_orders = me.discardable(&frame, state.our_player_index);
+		let all_actions = Reactor::candidate_actions(game);
 
-		// Retain only signalled playables if there is at least 1 such
-		if playable_orders.iter().any(|&o| me.order_kp(&frame, o)) {
-			playable_orders.retain(|&o| me.order_kp(&frame, o));
+		if all_actions.is_empty() {
+			// In safe mode, stall with a clue rather than risk discarding a critical
+			if game.safe_mode && state.can_clue() && let Some(stall) = Reactor::best_stall_clue(game) {
+				info!("safe mode, stalling instead of discarding");
+				return stall;
+			}
+
+			// Locked with only potential criticals: sacrifice the card most likely to have a copy remaining
+			if game.level >= SACRIFICE_LEVEL && state.pace() > 0 && state.our_hand().iter().all(|&o| me.thoughts[o].possibilities().iter().any(|p| state.is_critical(p))) {
+				return PerformAction::Discard { target: me.sacrifice_discard(state, state.our_player_index) };
+			}
+			return PerformAction::Discard { target: me.locked_discard(&game.frame(), state.our_player_index) };
 		}
 
-		info!("playables {playable_orders:?}");
-		info!("discardable {discard_orders:?}");
+		let values = all_actions.iter().map(|(_, action)| Reactor::eval_action(game, action)).collect::<Vec<_>>();
+		let frame = game.frame();
 
-		let all_clues = if !state.can_clue() || common.waiting.as_ref().is_some_and(|w| w.receiver == state.our_player_index) { Vec::new() } else {
-			(1..state.num_players).flat_map(|offset| {
-				let target = (state.our_player_index + offset) % state.num_players;
-				state.all_valid_clues(target)
-			}).map(|clue| {
-				let perform = util::clue_to_perform(&clue);
-				let action = util::perform_to_action(state, &perform, state.our_player_index, None);
-				(perform, action)
-			}).collect()
-		};
-		let num_clues = all_clues.len();
+		// In the endgame, hold with a safe trash discard rather than give a clue worth less than it, or one that would only hurt
+		let best_clue = all_actions.iter().zip(&values).filter(|((perform, _), _)| perform.is_clue()).map(|(_, &value)| value).reduce(f32::max);
+		let best_trash = all_actions.iter().zip(&values).filter(|((perform, _), _)| matches!(perform, PerformAction::Discard { target } if me.order_kt(&frame, *target)))
+			.map(|(_, &value)| value).reduce(f32::max);
 
-		let all_plays = playable_orders.iter().map(|&order| {
-			(PerformAction::Play { target: order },
-			match me.thoughts[order].identity(&IdOptions { infer: true, ..Default::default() }) {
-				Some(Identity { suit_index, rank }) => {
-					Action::play(state.our_player_index, order, suit_index as i32, rank as i32)
-				}
-				None => {
-					Action::play(state.our_player_index, order, -1, -1)
-				}
-			})
-		}).collect::<Vec<_>>();
-		let num_plays = all_plays.len();
+		let best_clue_value = all_actions.iter().filter_map(|(_, action)| match action {
+			Action::Clue(clue) => Some(Reactor::clue_value(game, clue)),
+			_ => None
+		}).reduce(f32::max);
 
-		let cant_discard = state.clue_tokens == Fraction::from(8) ||
-			(state.pace() == 0 && (num_clues > 0 || num_plays > 0)) ||
-			(num_plays > 0 && game.common.waiting.as_ref().is_some_and(|w| w.reacter == state.next_player_index(state.our_player_index)));	// If we have a play and there's a potential inversion
-		info!("can discard: {}", !cant_discard);
+		let hold = state.in_endgame() && best_trash.is_some() &&
+			(best_clue.zip(best_trash).is_some_and(|(clue, trash)| clue < trash) || best_clue_value.is_some_and(|value| value < 0.0));
 
-		let all_discards = if cant_discard { Vec::new() } else {
-			discard_orders.iter().map(|&order| {
-				(PerformAction::Discard { target: order },
-				match me.thoughts[order].id() {
-					Some(Identity { suit_index, rank }) => {
-						Action::discard(state.our_player_index, order, suit_index as i32, rank as i32, false)
-					}
-					None => {
-						Action::discard(state.our_player_index, order, -1, -1, false)
-					}
-				})
-			}).collect::<Vec<_>>()
+		// Saving a critical or a chop card is still worth a clue
+		let saves = |action: &Action| match action {
+			Action::Clue(ClueAction { list, .. }) => list.iter().any(|&o| frame.is_chop(o) || state.deck[o].id().is_some_and(|i| state.is_critical(i))),
+			_ => false
 		};
-		let num_discards = all_discards.len();
 
-		let mut all_actions = all_clues.into_iter().chain(all_plays).chain(all_discards).collect::<Vec<_>>();
-
-		if !cant_discard && (!state.can_clue() || num_plays == 0) && num_discards == 0 && !me.thinks_locked(&frame, state.our_player_index) {
-			if let Some(chop) = Reactor::chop(game, state.our_player_index) {
-				all_actions.push((
-					PerformAction::Discard { target: *chop },
-					Action::discard(state.our_player_index, *chop, -1, -1, false)
-				));
-			}
+		if hold {
+			info!("holding instead of giving a clue worth {}", best_clue.unwrap());
 		}
 
-		if all_actions.is_empty() {
-			return PerformAction::Discard { target: me.locked_discard(state, state.our_player_index) };
-		}
-
-		all_actions.iter().fold((f32::MIN, None), |(best_value, best), curr| {
-			let value = Reactor::eval_action(game, &curr.1);
-			if value > best_value {
-				(value, Some(curr))
-			} else {
-				(best_value, best)
-			}
-		}).1.unwrap().0
+		Reactor::best_action(all_actions.iter().zip(values).filter(|((perform, action), _)| !(hold && perform.is_clue() && !saves(action))))
 	}
 
 	fn update_turn(&self, _prev: &Game, game: &mut Game, action: &TurnAction) {
@@ -347,9 +538,16 @@ impl Convention for Reactor {
 				common.waiting = None;
 			}
 
+			// Called cards in other hands that are known to connect, e.g. the rest of a layered finesse
+			let called_ids = state.hands.iter().enumerate().filter(|&(i, _)| i != *current_player_index as usize).flat_map(|(_, hand)| hand)
+				.filter(|&&o| meta[o].status == CardStatus::CalledToPlay)
+				.filter_map(|&o| common.thoughts[o].identity(&IdOptions { infer: true, ..Default::default() }))
+				.filter(|&id| state.is_playable(id))
+				.collect::<Vec<_>>();
+
 			for &order in &state.hands[*current_player_index as usize] {
 				if meta[order].status == CardStatus::CalledToPlay {
-					common.thoughts[order].inferred.retain(|i| state.is_playable(i));
+					common.thoughts[order].inferred.retain(|i| state.is_playable(i) || called_ids.iter().any(|&c| state.prev_id(i) == Some(c)));
 
 					if common.thoughts[order].inferred.is_empty() {
 						common.thoughts[order].reset_inferences();
@@ -366,7 +564,7 @@ impl Convention for Reactor {
 	}
 
 	fn find_all_clues(&self, game: &Game, giver: usize) -> Vec<PerformAction> {
-		let Game { state, .. } = game;
+		let Game { common, state, meta, .. } = game;
 
 		let level = log::max_level();
 		log::set_max_level(log::LevelFilter::Off);
@@ -378,6 +576,8 @@ impl Convention for Reactor {
 				continue;
 			}
 
+			let playables = game.frame().playable_orders(common, i);
+
 			for clue in state.all_valid_clues(i) {
 				let base_clue = clue.to_base();
 				let list = state.clue_touched(&state.hands[clue.target], &base_clue);
@@ -388,11 +588,13 @@ impl Convention for Reactor {
 				if touched.iter().all(|&o| state.deck[o].clued && state.is_basic_trash(state.deck[o].id().unwrap())) {
 					continue;
 				}
-				info!("{}", format!("===== Predicting value for {} =====", clue.fmt(state)).green());
-				let hypo_game = game.simulate_clue(&action, SimOpts { log: true, ..SimOpts::default() });
-				let value = Reactor::get_result(game, &hypo_game, &action);
 
-				if value > 0.0 {
+				// Do not simulate clues that only retouch cards already known to be playable
+				if touched.iter().all(|&o| state.deck[o].clued && (meta[o].status == CardStatus::CalledToPlay || playables.contains(&o))) {
+					continue;
+				}
+				info!("{}", format!("===== Predicting value for {} =====", clue.fmt(state)).green());
+				if Reactor::clue_value(game, &action) > 0.0 {
 					all_clues.push(util::clue_to_perform(&clue));
 				}
 			}
@@ -404,10 +606,39 @@ impl Convention for Reactor {
 	}
 
 	fn find_all_discards(&self, game: &Game, player_index: usize) -> Vec<PerformAction> {
-		let Game { common, state, .. } = game;
-		let trash = common.discardable(&game.frame(), player_index);
+		let Game { common, .. } = game;
+		let frame = game.frame();
 
-		let target = trash.first().or_else(|| Reactor::chop(game, player_index)).copied().unwrap_or_else(|| game.players[player_index].locked_discard(state, player_index));
+		// Known trash is already discardable, so the only other obvious discard is the chop
+		let target = common.discardable(&frame, player_index).first().copied()
+			.or_else(|| frame.chop(player_index))
+			.unwrap_or_else(|| game.players[player_index].locked_discard(&frame, player_index));
 		vec![PerformAction::Discard { target }]
 	}
+
+	fn find_all_fixes(&self, game: &Game, giver: usize) -> Vec<PerformAction> {
+		let Game { common, state, meta, .. } = game;
+
+		// Called or clued cards that everyone will play as something else, which will bomb
+		let broken = (0..state.num_players).filter(|&i| i != giver).flat_map(|i| &state.hands[i]).filter(|&&o| {
+			let Some(id) = state.deck[o].id() else {
+				return false;
+			};
+			let inferred = &common.thoughts[o].inferred;
+			let will_play = meta[o].status == CardStatus::CalledToPlay ||
+				(state.deck[o].clued && !inferred.is_empty() && inferred.iter().all(|i| state.is_playable(i)));
+
+			will_play && !inferred.contains(id) && !state.is_playable(id)
+		}).copied().collect::<Vec<_>>();
+
+		if broken.is_empty() {
+			return Vec::new();
+		}
+
+		Reactor::fix_clues(game, giver, &broken)
+	}
+
+	fn explain_action(&self, game: &Game, perform: &PerformAction) -> String {
+		Reactor::explain_action(game, perform)
+	}
 }
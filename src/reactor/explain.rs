@@ -0,0 +1,80 @@
+use crate::basics::action::{Action, PerformAction};
+use crate::basics::card::CardStatus;
+use crate::basics::game::{Game, Interp, SimOpts};
+use crate::basics::util;
+use crate::reactor::{ClueInterp, Reactor, ReactorInterp};
+
+impl Reactor {
+	/** Returns a short explanation of why we are taking the given action, suitable for sending in chat. */
+	pub fn explain_action(game: &Game, perform: &PerformAction) -> String {
+		let Game { state, meta, .. } = game;
+
+		match perform {
+			PerformAction::Play { target } | PerformAction::Discard { target } => {
+				let verb = if matches!(perform, PerformAction::Play { .. }) { "Playing" } else { "Discarding" };
+				let slot = state.our_hand().iter().position(|o| o == target).unwrap() + 1;
+
+				let reason = match (meta[*target].status, meta[*target].by) {
+					(CardStatus::CalledToPlay, Some(giver)) => format!(", called to play by {}", state.player_names[giver]),
+					(CardStatus::CalledToDiscard, Some(giver)) => format!(", called to discard by {}", state.player_names[giver]),
+					_ => String::new()
+				};
+				format!("{verb} slot {slot}{reason}.")
+			}
+			PerformAction::Colour { .. } | PerformAction::Rank { .. } => {
+				let Action::Clue(action) = util::perform_to_action(state, perform, state.our_player_index, None) else {
+					unreachable!()
+				};
+				let hypo = game.simulate_clue(&action, SimOpts::default());
+				let hypo_meta = &hypo.meta;
+
+				let interp = match &hypo.last_move {
+					Some(Interp::Reactor(ReactorInterp::Clue(interp))) => interp_name(interp),
+					None => "clue"
+				};
+
+				let called = state.hands.iter().enumerate().flat_map(|(player_index, hand)|
+					hand.iter().enumerate().filter_map(move |(i, &order)| {
+						let status = hypo_meta[order].status;
+						if status == meta[order].status {
+							return None;
+						}
+
+						let called = match status {
+							CardStatus::CalledToPlay => "play",
+							CardStatus::CalledToDiscard => "discard",
+							_ => return None
+						};
+						Some(format!("{}'s slot {} to {called}", state.player_names[player_index], i + 1))
+					})
+				).collect::<Vec<_>>();
+
+				let clue = action.clue.fmt(state, action.target);
+				let clue = clue.trim_matches(|c| c == '(' || c == ')');
+
+				if called.is_empty() {
+					format!("Giving {clue} as a {interp}.")
+				} else {
+					format!("Giving {clue} as a {interp}, calling {}.", called.join(" and "))
+				}
+			}
+			PerformAction::Terminate { .. } => "Terminating, since the game can no longer be won and any action risks a strikeout.".to_string()
+		}
+	}
+}
+
+fn interp_name(interp: &ClueInterp) -> &'static str {
+	match interp {
+		ClueInterp::Illegal => "illegal clue",
+		ClueInterp::Mistake => "mistake",
+		ClueInterp::Reactive => "reactive clue",
+		ClueInterp::RefPlay => "ref play",
+		ClueInterp::RefDiscard => "ref discard",
+		ClueInterp::Lock => "lock",
+		ClueInterp::Reveal => "reveal",
+		ClueInterp::Fix => "fix",
+		ClueInterp::Reclue => "reclue",
+		ClueInterp::Stall => "stall",
+		ClueInterp::FiveStall => "5 stall",
+	}
+}
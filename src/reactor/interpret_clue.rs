@@ -7,29 +7,48 @@ use crate::basics;
 use crate::basics::action::{Action, ClueAction};
 use crate::basics::card::{CardStatus, Identifiable, Identity, IdOptions};
 use crate::basics::clue::{Clue, ClueKind};
-use crate::basics::game::Game;
+use crate::basics::game::{frame::Frame, Game};
 use crate::basics::identity_set::IdentitySet;
-use crate::basics::player::WaitingConnection;
+use crate::basics::player::{Player, WaitingConnection};
 use crate::basics::state::State;
 use crate::basics::util::players_upto;
-use crate::basics::variant::{touch_possibilities, BROWNISH, PINKISH, RAINBOWISH};
+use crate::basics::variant::{touch_possibilities, BROWNISH, PINKISH, PRISM, RAINBOWISH};
 use crate::fix::{check_fix, connectable_simple};
-use crate::reactor::{ClueInterp, Reactor};
+use crate::reactor::{ClueInterp, Reactor, FINESSE_LEVEL, SACRIFICE_LEVEL};
 
 impl Reactor {
+	/**
+	 * Returns the focus slot of a reactive clue.
+	 * Without special variants, this is the leftmost touched card, except that slot 1 is only the focus when no other card is touched.
+	 */
 	fn reactive_focus(state: &State, receiver: usize, action: &ClueAction) -> usize {
 		let ClueAction { list, clue, .. } = &action;
 		let (focus_index, _) = state.hands[receiver].iter().enumerate()
 			.filter(|&(_, o)| list.contains(o))
-			.max_by_key(|&(_, o)| if *o == state.hands[receiver][0] { 0 } else { *o })
+			.max_by_key(|&(i, _)| (i != 0, std::cmp::Reverse(i)))
 			.unwrap();
 
 		match clue.kind {
-			ClueKind::COLOUR => if state.includes_variant(&RAINBOWISH) { clue.value + 1 } else { focus_index + 1 },
+			ClueKind::COLOUR => if state.includes_variant(&RAINBOWISH) || state.includes_variant(&PRISM) { clue.value + 1 } else { focus_index + 1 },
 			ClueKind::RANK => if state.includes_variant(&PINKISH) { clue.value } else { focus_index + 1 }
 		}
 	}
 
+	/**
+	 * Returns the touched cards that looked playable before the clue, but are now revealed to be trash.
+	 * Such a clue is always a stable fix, giving the receiver time to discard while the team fixes any other cards.
+	 */
+	pub(super) fn revealed_trash(prev: &Game, game: &Game, action: &ClueAction) -> Vec<usize> {
+		let ClueAction { target, list, .. } = &action;
+		let prev_playables = prev.common.thinks_playables(&prev.frame(), *target);
+		let frame = game.frame();
+
+		list.iter().filter(|&&o| {
+			let queued = prev.state.deck[o].clued && (prev.meta[o].status == CardStatus::CalledToPlay || prev_playables.contains(&o));
+			queued && game.common.thoughts[o].possible.iter().all(|i| game.common.is_trash(&frame, i, o))
+		}).copied().collect()
+	}
+
 	pub(super) fn interpret_stable(prev: &Game, game: &mut Game, action: &ClueAction, stall: bool) -> Option<ClueInterp> {
 		let ClueAction { giver, target, .. } = &action;
 
@@ -75,10 +94,9 @@ impl Reactor {
 				game.state.is_basic_trash(id) || game.state.is_playable(id)
 			}) {
 				// Move focus to lock card if touched in a pinkish variant
-				if let Some(lock_order) = game.state.hands[*target].iter().filter(|&&o| !prev.state.deck[o].clued).min() {
-					if game.state.includes_variant(&PINKISH) && list.contains(lock_order) {
-						focus = lock_order;
-					}
+				if let Some(lock_order) = game.state.hands[*target].iter().filter(|&&o| !prev.state.deck[o].clued).min() &&
+					game.state.includes_variant(&PINKISH) && list.contains(lock_order) {
+					focus = lock_order;
 				}
 
 				let unnecessary_focus = game.common.thoughts[*focus].possible.iter().all(|i|
@@ -119,17 +137,24 @@ impl Reactor {
 			return Some(ClueInterp::Fix);
 		}
 
+		// 5 Stall: in a stalling situation, a 5 clue that only touches the chop
+		if stall && clue.kind == ClueKind::RANK && clue.value == 5 && let [focus] = newly_touched[..] &&
+			prev.frame().is_chop(focus) && game.meta[focus].status != CardStatus::CalledToPlay {
+			info!("5 stall!");
+			return Some(ClueInterp::FiveStall);
+		}
+
 		let Game { state, common, .. } = &game;
 		let frame = game.frame();
-		let prev_playables = prev.common.obvious_playables(&prev.frame(), *target).into_iter().chain(connectable_simple(prev, state.next_player_index(*giver), *target, None)).unique().collect::<Vec<_>>();
-		let playables = common.obvious_playables(&frame, *target).into_iter().chain(connectable_simple(game, state.next_player_index(*giver), *target, None)).unique().collect::<Vec<_>>();
+		let prev_playables = prev.frame().playable_orders(&prev.common, *target).into_iter().chain(connectable_simple(prev, state.next_player_index(*giver), *target, None)).unique().collect::<Vec<_>>();
+		let playables = frame.playable_orders(common, *target).into_iter().chain(connectable_simple(game, state.next_player_index(*giver), *target, None)).unique().collect::<Vec<_>>();
 
 		info!("playables {playables:?}, prev_playables {prev_playables:?}");
 
 		// Fill-in or hard burn is legal only in a stalling situation
 		if newly_touched.is_empty() {
-			let safe_actions = playables.into_iter().chain(common.thinks_trash(&frame, *target)).collect::<Vec<_>>();
-			let old_safe_actions = prev_playables.into_iter().chain(prev.common.thinks_trash(&frame, *target)).collect::<Vec<_>>();
+			let safe_actions = playables.into_iter().chain(frame.trash_orders(common, *target)).collect::<Vec<_>>();
+			let old_safe_actions = prev_playables.into_iter().chain(frame.trash_orders(&prev.common, *target)).collect::<Vec<_>>();
 
 			if safe_actions.iter().any(|o| !old_safe_actions.contains(o)) {
 				info!("revealed a safe action!");
@@ -139,13 +164,19 @@ impl Reactor {
 				info!("stalling with fill-in/hard-burn!");
 				return Some(ClueInterp::Stall);
 			}
+
+			// Reclue: re-touching a clued card that isn't yet known to be safe calls it to play
+			if let Some(&focus) = list.iter().filter(|&&o| !old_safe_actions.contains(&o) && game.meta[o].status == CardStatus::None && !game.meta[o].trash).max() {
+				info!("reclue on {focus}!");
+				return Reactor::target_play(game, action, focus, false, true).map(|interp| if interp == ClueInterp::RefPlay { ClueInterp::Reclue } else { interp });
+			}
 			warn!("looked like fill-in/hard burn outside of a stalling situation!");
 			return None;
 		}
 
 		let colour_reveal = clue.kind == ClueKind::COLOUR && {
-			let prev_playables = prev.common.obvious_playables(&prev.frame(), *target);
-			let curr_playables = common.obvious_playables(&frame, *target);
+			let prev_playables = prev.frame().playable_orders(&prev.common, *target);
+			let curr_playables = frame.playable_orders(common, *target);
 
 			// A colour clue that reveals a new playable in a previously touched card
 			curr_playables.iter().any(|o| !prev_playables.contains(o) && prev.state.deck[*o].clued)
@@ -155,7 +186,7 @@ impl Reactor {
 		if trash_push {
 			// Brownish TCM if there is at least 1 useful unplayable brown and clue didn't touch chop
 			if state.includes_variant(&BROWNISH) && clue.kind == ClueKind::RANK &&
-				state.variant.suits.iter().enumerate().any(|(suit_index, suit)| BROWNISH.is_match(suit) && state.play_stacks[suit_index] + 1 < state.max_ranks[suit_index]) &&
+				state.variant.suits.iter().enumerate().any(|(suit_index, suit)| BROWNISH.is_match(suit) && state.next_rank_at(suit_index, state.play_stacks[suit_index] + 1).is_some()) &&
 				!newly_touched.contains(&state.hands[*target][0]) {
 					info!("brown direct discard!");
 			}
@@ -205,7 +236,9 @@ impl Reactor {
 
 			let valid_clue = match clue.kind {
 				ClueKind::COLOUR => {
-					let play_target = newly_touched.iter().map(|&o| common.refer(&game.frame(), hand, o, true)).max().unwrap();
+					let Some(play_target) = Reactor::ref_play_target(common, &game.frame(), hand, &newly_touched) else {
+						continue;
+					};
 
 					state.is_playable(state.deck[play_target].id().unwrap()) &&
 					(newly_touched.iter().all(|&o| !state.is_basic_trash(state.deck[o].id().unwrap())) ||
@@ -235,6 +268,24 @@ impl Reactor {
 		None
 	}
 
+	/**
+	 * Returns the card that a colour clue newly touching the given orders would reference for play,
+	 * or None if there is no other untouched card to reference.
+	 */
+	pub(super) fn ref_play_target(common: &Player, frame: &Frame, hand: &[usize], newly_touched: &[usize]) -> Option<usize> {
+		newly_touched.iter().filter_map(|&o| common.refer(frame, hand, o, true)).max()
+	}
+
+	/**
+	 * Returns the card that a rank clue newly touching the given orders would reference for discard,
+	 * i.e. the first untouched card to the right of its focus.
+	 */
+	pub(super) fn ref_discard_target(state: &State, hand: &[usize], list: &[usize], newly_touched: &[usize]) -> Option<usize> {
+		let focus = newly_touched.iter().max()?;
+		let focus_pos = hand.iter().position(|o| o == focus).unwrap();
+		hand.iter().enumerate().find(|&(i, o)| i > focus_pos && !state.deck[*o].clued && !list.contains(o)).map(|(_, &o)| o)
+	}
+
 	pub(super) fn bad_stable(prev: &Game, game: &Game, action: &ClueAction, interp: &ClueInterp, stall: bool) -> bool {
 		let Game { common, state, meta, .. } = game;
 		let ClueAction { target, .. } = action;
@@ -276,11 +327,9 @@ impl Reactor {
 		}
 
 		// Check for bad lock
-		if *interp == ClueInterp::Lock {
-			if let Some(alt_clue) = Reactor::alternative_clue(prev, *target, false) {
-				warn!("alternative clue {} was available!", alt_clue.fmt(state));
-				return true;
-			}
+		if *interp == ClueInterp::Lock && let Some(alt_clue) = Reactor::alternative_clue(prev, *target, false) {
+			warn!("alternative clue {} was available!", alt_clue.fmt(state));
+			return true;
 		}
 
 		if !stall {
@@ -288,11 +337,9 @@ impl Reactor {
 		}
 
 		// Check for bad stall
-		if *interp == ClueInterp::Stall {
-			if let Some(alt_clue) = Reactor::alternative_clue(prev, *target, false) {
-				warn!("alternative clue {} was available!", alt_clue.fmt(state));
-				return true;
-			}
+		if matches!(interp, ClueInterp::Stall | ClueInterp::FiveStall) && let Some(alt_clue) = Reactor::alternative_clue(prev, *target, false) {
+			warn!("alternative clue {} was available!", alt_clue.fmt(state));
+			return true;
 		}
 
 		false
@@ -318,14 +365,11 @@ impl Reactor {
 			turn: state.turn_count
 		});
 
-		if *receiver == state.our_player_index {
-			return Some(ClueInterp::Reactive);
-		}
-
 		let possible_conns = Reactor::delayed_plays(game, *giver, *receiver);
+		let reacter_conns = Reactor::delayed_plays(game, *giver, reacter);
 
-		let old_playables = prev.common.obvious_playables(&prev.frame(), *receiver);
-		let new_playables = game.common.obvious_playables(&game.frame(), *receiver);
+		let old_playables = prev.frame().playable_orders(&prev.common, *receiver);
+		let new_playables = game.frame().playable_orders(&game.common, *receiver);
 		let known_plays = old_playables.iter().filter(|o| new_playables.contains(o)).collect::<Vec<_>>();
 
 		let Game { common, state, meta, .. } = game;
@@ -333,7 +377,7 @@ impl Reactor {
 		// Update play stacks to the reacter's turn
 		let mut play_stacks = state.play_stacks.clone();
 		for i in players_upto(state.num_players, state.next_player_index(*giver), reacter) {
-			let mut playables = prev.common.obvious_playables(&prev.frame(), i);
+			let mut playables = prev.frame().playable_orders(&prev.common, i);
 			if let Some(urgent) = playables.iter().find(|&&o| meta[o].urgent) {
 				playables = vec![*urgent];
 			}
@@ -343,10 +387,16 @@ impl Reactor {
 			}
 		}
 
+		if *receiver == state.our_player_index {
+			let known_plays = known_plays.into_iter().copied().collect::<Vec<_>>();
+			Reactor::receive_reactive(prev, game, action, reacter, focus_slot, &play_stacks, &known_plays);
+			return Some(ClueInterp::Reactive);
+		}
+
 		match clue.kind {
 			ClueKind::COLOUR => {
 				let play_targets = state.hands[*receiver].iter().enumerate()
-					.filter(|&(_, o)| meta[*o].status != CardStatus::CalledToDiscard && !known_plays.contains(&o) && state.deck[*o].id().is_some_and(|i| play_stacks[i.suit_index] + 1 == i.rank))
+					.filter(|&(_, o)| meta[*o].status != CardStatus::CalledToDiscard && !known_plays.contains(&o) && state.deck[*o].id().is_some_and(|i| state.is_playable_on(i, &play_stacks)))
 					.sorted_by_key(|&(i, o)|
 						// Unclued dupe, with a clued dupe
 						if !prev.state.deck[*o].clued && state.hands[*receiver].iter().any(|o2| o2 < o && prev.state.deck[*o2].clued && state.deck[*o].is(&state.deck[*o2])) {
@@ -357,7 +407,7 @@ impl Reactor {
 				// Try targeting all play targets
 				for (index, _) in play_targets {
 					let target_slot = index + 1;
-					let react_slot = Reactor::calc_slot(focus_slot, target_slot);
+					let react_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, target_slot);
 
 					if state.hands[reacter].get(react_slot - 1).is_none() {
 						warn!("Reacter doesn't have slot {react_slot}!");
@@ -365,7 +415,7 @@ impl Reactor {
 					}
 
 					let react_order = state.hands[reacter][react_slot - 1];
-					let prev_trash = prev.common.thinks_trash(&prev.frame(), reacter);
+					let prev_trash = prev.frame().trash_orders(&prev.common, reacter);
 					if prev_trash.contains(&react_order) || (inverted && prev_trash.is_empty() && react_slot == 1) {
 						warn!("attempted dc+play would result in reacter naturally discarding {} {react_order}!", state.log_iden(&state.deck[react_order]));
 						continue;
@@ -376,6 +426,12 @@ impl Reactor {
 						continue;
 					}
 
+					// We can see the reacter's card, so the clue is a mistake if it forces them to discard a critical
+					if reacter != state.our_player_index && state.deck[react_order].id().is_some_and(|i| state.is_critical(i)) {
+						warn!("dc+play would result in reacter discarding critical {} {react_order}!", state.log_iden(&state.deck[react_order]));
+						return None;
+					}
+
 					common.thoughts[react_order].old_inferred = Some(common.thoughts[react_order].inferred);
 					Reactor::target_discard(game, action, react_order, true);
 
@@ -384,7 +440,7 @@ impl Reactor {
 				}
 
 				// Didn't work, so target trash
-				let prev_kt = prev.common.thinks_trash(&prev.frame(), *receiver);
+				let prev_kt = prev.frame().trash_orders(&prev.common, *receiver);
 
 				let mut targets = state.hands[*receiver].iter().enumerate().filter(|&(_, o)|
 					!prev_kt.contains(o) &&
@@ -403,11 +459,11 @@ impl Reactor {
 				.collect::<Vec<_>>();
 
 				// Add sacrifice discard targets
-				if targets.is_empty() {
+				if targets.is_empty() && game.level >= SACRIFICE_LEVEL {
 					targets.extend(state.hands[*receiver].iter().enumerate().filter(|&(_, o)|
 						!prev_kt.contains(o) && !state.is_critical(state.deck[*o].id().unwrap())
 					).sorted_by_key(|(_, o)|
-						-common.playable_away(state.deck[**o].id().unwrap())
+						-common.playable_away(state, state.deck[**o].id().unwrap())
 					));
 
 					if targets.is_empty() {
@@ -423,7 +479,7 @@ impl Reactor {
 					}
 
 					let target_slot = index + 1;
-					let react_slot = Reactor::calc_slot(focus_slot, target_slot);
+					let react_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, target_slot);
 
 					if state.hands[reacter].get(react_slot - 1).is_none() {
 						warn!("reacter doesn't have slot {react_slot}!");
@@ -431,7 +487,7 @@ impl Reactor {
 					}
 
 					let react_order = state.hands[reacter][react_slot - 1];
-					let prev_plays = prev.common.obvious_playables(&prev.frame(), reacter);
+					let prev_plays = prev.frame().playable_orders(&prev.common, reacter);
 					if prev_plays.contains(&react_order) {
 						warn!("attempted play+dc would result in reacter naturally playing {} {react_order}!", state.log_iden(&state.deck[react_order]));
 						continue;
@@ -451,7 +507,7 @@ impl Reactor {
 			}
 			ClueKind::RANK => {
 				let play_targets = state.hands[*receiver].iter().enumerate().filter(|&(_, o)|
-					meta[*o].status != CardStatus::CalledToDiscard && !known_plays.contains(&o) && state.deck[*o].id().is_some_and(|i| play_stacks[i.suit_index] + 1 == i.rank)
+					meta[*o].status != CardStatus::CalledToDiscard && !known_plays.contains(&o) && state.deck[*o].id().is_some_and(|i| state.is_playable_on(i, &play_stacks))
 				).sorted_by_key(|(i, o)| {
 					// Do not target an unclued copy when there is a clued copy
 					let unclued_dupe = !prev.state.deck[**o].clued && state.hands[*receiver].iter().any(|o2| &o2 != o && prev.state.deck[*o2].clued && state.deck[**o].is(&state.deck[*o2]));
@@ -460,7 +516,7 @@ impl Reactor {
 
 				for (index, target) in play_targets {
 					let target_slot = index + 1;
-					let react_slot = Reactor::calc_slot(focus_slot, target_slot);
+					let react_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, target_slot);
 
 					if state.hands[reacter].get(react_slot - 1).is_none() {
 						warn!("reacter doesn't have slot {react_slot}!");
@@ -470,7 +526,7 @@ impl Reactor {
 					let react_order = state.hands[reacter][react_slot - 1];
 					let receive_order = *target;
 
-					let prev_plays = prev.common.obvious_playables(&prev.frame(), reacter);
+					let prev_plays = prev.frame().playable_orders(&prev.common, reacter);
 					if prev_plays.contains(&react_order) {
 						warn!("attempted play+play would result in reacter naturally playing {} {react_order}!", state.log_iden(&state.deck[react_order]));
 						continue;
@@ -488,17 +544,29 @@ impl Reactor {
 					return Some(ClueInterp::Reactive);
 				}
 
-				let finesse_targets = state.hands[*receiver].iter().enumerate().filter(|(_, o)|
-					state.playable_away(state.deck[**o].id().unwrap()) == 1
-				).collect::<Vec<_>>();
+				let finesse_targets = state.hands[*receiver].iter().enumerate().filter(|(_, o)| {
+					let id = state.deck[**o].id().unwrap();
+					match state.playable_away(id) {
+						1 => state.prev_id(id).is_some(),
+						// The reacter's card connects after a delayed play from someone before them
+						2 => reacter_conns.iter().any(|(_, i)| Some(*i) == state.prev_id(id)),
+						_ => false
+					}
+				}).collect::<Vec<_>>();
 
 				if finesse_targets.is_empty() {
 					warn!("reactive clue but receiver had no playable targets!");
 					return None;
 				}
 
-				for react_slot in [1, 5, 4, 3, 2] {
-					let target_slot = Reactor::calc_slot(focus_slot, react_slot);
+				if game.level < FINESSE_LEVEL {
+					warn!("reactive finesses are disabled at level {}!", game.level);
+					return None;
+				}
+
+				// Prefer reacting with slot 1, then from the back of the hand
+				for react_slot in std::iter::once(1).chain((2..=state.hand_size()).rev()) {
+					let target_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, react_slot);
 
 					if state.hands[reacter].get(react_slot - 1).is_none() {
 						continue;
@@ -508,7 +576,7 @@ impl Reactor {
 						let react_order = state.hands[reacter][react_slot - 1];
 						let receive_order = **finesse_target;
 
-						let prev_plays = prev.common.obvious_playables(&prev.frame(), reacter);
+						let prev_plays = prev.frame().playable_orders(&prev.common, reacter);
 						if prev_plays.contains(&react_order) {
 							warn!("attempted finesse would result in reacter naturally playing {} {react_order}!", state.log_iden(&state.deck[react_order]));
 							return None;
@@ -519,9 +587,13 @@ impl Reactor {
 							continue;
 						}
 
+						let Some(connector) = state.prev_id(state.deck[receive_order].id().unwrap()) else {
+							continue;
+						};
+
 						common.thoughts[react_order].old_inferred = Some(common.thoughts[react_order].inferred);
 						Reactor::target_play(game, action, react_order, true, false)?;
-						game.common.thoughts[react_order].inferred = IdentitySet::single(game.state.deck[receive_order].id().unwrap().prev());
+						game.common.thoughts[react_order].inferred = IdentitySet::single(connector);
 
 						info!("reactive finesse, reacter {} (slot {}) receiver {} (slot {}), focus slot {}", game.state.player_names[reacter], react_slot, game.state.player_names[*receiver], target_slot, focus_slot);
 						return Some(ClueInterp::Reactive);
@@ -532,13 +604,93 @@ impl Reactor {
 		}
 	}
 
+	/**
+	 * Narrows our own target of a reactive clue before the reacter responds.
+	 * We can see the reacter's hand, so we know which of their cards they could react with and thus which of our slots may be targeted.
+	 * If only one slot is possible, its inferences are restricted to the identities that any of those reactions would give it.
+	 */
+	fn receive_reactive(prev: &Game, game: &mut Game, action: &ClueAction, reacter: usize, focus_slot: usize, play_stacks: &[usize], known_plays: &[usize]) {
+		let Game { common, state, meta, .. } = game;
+		let ClueAction { target: receiver, clue, .. } = action;
+
+		let prev_plays = prev.frame().playable_orders(&prev.common, reacter);
+		let prev_trash = prev.frame().trash_orders(&prev.common, reacter);
+		let playable_after = |stacks: &[usize]| state.all_ids.filter(|i| state.is_playable_on(i, stacks));
+
+		let mut candidates = Vec::new();
+
+		for (index, &react_order) in state.hands[reacter].iter().enumerate() {
+			let Some(react_id) = state.deck[react_order].id() else {
+				continue;
+			};
+
+			let react_slot = index + 1;
+			let target_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, react_slot);
+			let Some(&target) = state.hands[*receiver].get(target_slot - 1) else {
+				continue;
+			};
+
+			if known_plays.contains(&target) || meta[target].status == CardStatus::CalledToDiscard {
+				continue;
+			}
+
+			let react_playable = state.is_playable_on(react_id, play_stacks) && !prev_plays.contains(&react_order);
+
+			let outcomes = match clue.kind {
+				// Play+play or finesse: the target is playable once the reacter's card is played
+				ClueKind::RANK => {
+					if !react_playable {
+						continue;
+					}
+
+					let mut stacks = play_stacks.to_vec();
+					stacks[react_id.suit_index] += 1;
+					playable_after(&stacks)
+				}
+				ClueKind::COLOUR => {
+					// Dc+play: the target is playable, play+dc: the target is not critical
+					let mut outcomes = IdentitySet::EMPTY;
+					if !prev_trash.contains(&react_order) && !state.is_critical(react_id) {
+						outcomes = outcomes.union(&playable_after(play_stacks));
+					}
+					if react_playable {
+						outcomes = outcomes.union(&state.all_ids.filter(|i| !state.is_critical(i)));
+					}
+
+					if outcomes.is_empty() {
+						continue;
+					}
+					outcomes
+				}
+			};
+			candidates.push((target_slot, target, outcomes));
+		}
+
+		info!("possible reactive targets: slots {:?}", candidates.iter().map(|(slot, ..)| slot).collect::<Vec<_>>());
+
+		let [(target_slot, target, outcomes)] = candidates[..] else {
+			return;
+		};
+
+		let thought = &mut common.thoughts[target];
+		let inferred = thought.inferred.intersect(&outcomes);
+		if inferred.is_empty() {
+			warn!("no plausible reactive outcomes for target {target} (slot {target_slot})!");
+			return;
+		}
+
+		thought.old_inferred = Some(thought.inferred);
+		thought.inferred = inferred;
+		info!("narrowed reactive target {target} (slot {target_slot}) to {}", common.str_infs(state, target));
+	}
+
 	fn delayed_plays(game: &Game, giver: usize, receiver: usize) -> Vec<(usize, Identity)> {
 		let Game { common, state, meta, .. } = game;
 
 		let mut possible_conns = Vec::new();
 
 		for player_index in players_upto(state.num_players, state.next_player_index(giver), receiver) {
-			let mut playables = common.obvious_playables(&game.frame(), player_index);
+			let mut playables = game.frame().playable_orders(common, player_index);
 
 			// If they have an urgent discard, they can't play a connecting card. If they have an urgent playable, they can only play that card.
 			if let Some(urgent) = state.hands[player_index].iter().find(|&&o| meta[o].urgent) {
@@ -556,13 +708,39 @@ impl Reactor {
 				}
 
 				if let Some(id) = common.thoughts[o].identity(&IdOptions { infer: true, ..Default::default() }) {
-					possible_conns.push((o, Identity { suit_index: id.suit_index, rank: id.rank + 1 }));
+					if let Some(next) = state.next_id(id) {
+						possible_conns.push((o, next));
+					}
 				}
 				else {
-					possible_conns.extend(common.thoughts[o].inferred.iter().map(|i| (o, Identity { suit_index: i.suit_index, rank: i.rank + 1 })));
+					// Only connect through the inferences that are closest to playable, since the others would require an unlikely identity
+					let min_away = common.min_playable_away(state, o);
+					possible_conns.extend(common.thoughts[o].inferred.iter().filter(|&i| state.playable_away(i) == min_away).filter_map(|i| state.next_id(i).map(|next| (o, next))));
+				}
+			}
+		}
+
+		// Follow chains of two connecting playables, where a known card becomes playable after an earlier player's play
+		let mut chained_conns = Vec::new();
+		let mut earlier_conns: Vec<(usize, Identity)> = Vec::new();
+
+		for player_index in players_upto(state.num_players, state.next_player_index(giver), receiver) {
+			let own_conns = possible_conns.iter().filter(|(o, _)| state.hands[player_index].contains(o)).copied().collect::<Vec<_>>();
+
+			for &o in &state.hands[player_index] {
+				if !game.frame().is_touched(o) || possible_conns.iter().any(|(c, _)| *c == o) {
+					continue;
+				}
+
+				if let Some(id) = common.thoughts[o].identity(&IdOptions { infer: true, ..Default::default() }) &&
+					let Some(next) = state.next_id(id) && earlier_conns.iter().any(|(_, i)| *i == id) {
+					chained_conns.push((o, next));
 				}
 			}
+			earlier_conns.extend(own_conns);
 		}
+
+		possible_conns.extend(chained_conns);
 		possible_conns
 	}
 
@@ -572,7 +750,10 @@ impl Reactor {
 		let hand = &state.hands[*receiver];
 		let newly_touched = list.iter().filter(|&&o| !prev.state.deck[o].clued).copied().collect::<Vec<_>>();
 
-		let target = newly_touched.iter().map(|&o| common.refer(&prev.frame(), hand, o, true)).max().unwrap();
+		let Some(target) = Reactor::ref_play_target(common, &prev.frame(), hand, &newly_touched) else {
+			warn!("no card to reference!");
+			return None;
+		};
 
 		if game.frame().is_blind_playing(target) {
 			warn!("targeting an already known playable!");
@@ -596,9 +777,10 @@ impl Reactor {
 		let Game { common, state, .. } = game;
 		let new_inferred = common.thoughts[target].inferred.filter(|i| state.is_playable(i) || possible_conns.iter().any(|p| p.1 == i));
 
-		if let Some(id) = game.state.deck[target].id() && let Some((conn_order, _)) = possible_conns.iter().find(|c| c.1.is(&id)) {
+		if let Some(id) = game.state.deck[target].id() && let Some((conn_order, _)) = possible_conns.iter().find(|c| c.1.is(&id)) &&
+			let Some(conn_id) = game.state.prev_id(id) {
 			game.common.thoughts[*conn_order].old_inferred = Some(game.common.thoughts[*conn_order].inferred);
-			game.common.thoughts[*conn_order].inferred = IdentitySet::single(id.prev());
+			game.common.thoughts[*conn_order].inferred = IdentitySet::single(conn_id);
 
 			let meta = &mut game.meta[*conn_order];
 			meta.urgent = true;
@@ -608,7 +790,7 @@ impl Reactor {
 				meta.reasoning.push(game.state.turn_count);
 			}
 
-			info!("updating connecting {} as {} to be urgent", *conn_order, game.state.log_id(id.prev()));
+			info!("updating connecting {} as {} to be urgent", *conn_order, game.state.log_id(conn_id));
 		}
 
 		let reset = new_inferred.is_empty();
@@ -710,12 +892,10 @@ impl Reactor {
 			return Some(ClueInterp::Lock);
 		}
 
-		let focus = newly_touched.iter().max().unwrap();
-		let focus_pos = hand.iter().position(|o| o == focus).unwrap();
-		let target_index = hand.iter().enumerate().position(|(i, &o)| i > focus_pos && !state.deck[o].clued).unwrap();
-		info!("ref discard on {}'s slot {}", state.player_names[*receiver], target_index + 1);
+		let target = Reactor::ref_discard_target(state, hand, list, &newly_touched).unwrap();
+		info!("ref discard on {}'s slot {}", state.player_names[*receiver], hand.iter().position(|&o| o == target).unwrap() + 1);
 
-		let meta = &mut game.meta[hand[target_index]];
+		let meta = &mut game.meta[target];
 		meta.status = CardStatus::CalledToDiscard;
 		meta.by = Some(*giver);
 		// meta.trash = true;
@@ -1,25 +1,21 @@
 use log::{info, warn};
 
 use crate::basics::action::Action;
-use crate::basics::card::{CardStatus, ConvData};
+use crate::basics::card::{CardStatus, ConvData, Identifiable};
 use crate::basics::clue::ClueKind;
 use crate::basics::game::Game;
+use crate::basics::identity_set::IdentitySet;
 use crate::basics::player::{Player, WaitingConnection};
 use crate::basics::state::State;
 use crate::reactor::{ClueInterp, Reactor};
 
 impl Reactor {
-	pub(super) fn calc_slot(focus_slot: usize, slot: usize) -> usize {
-		let other = (focus_slot + 5 - slot) % 5;
-		if other == 0 { 5 } else { other }
-	}
-
 	fn calc_target_slot(prev: &Game, game: &Game, order: usize, wc: &WaitingConnection) -> Option<(usize, usize)> {
 		let Game { state, .. } = game;
 		let &WaitingConnection { reacter, receiver, ref receiver_hand,  focus_slot, .. } = wc;
 
 		let react_slot = prev.state.hands[reacter].iter().position(|&o| o == order).unwrap() + 1;
-		let target_slot = Reactor::calc_slot(focus_slot, react_slot);
+		let target_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, react_slot);
 
 		if receiver_hand.get(target_slot - 1).is_none() {
 			warn!("Receiver no longer has slot {target_slot}!");
@@ -44,7 +40,7 @@ impl Reactor {
 				continue;
 			}
 
-			let react_slot = Reactor::calc_slot(focus_slot, i + 1);
+			let react_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, i + 1);
 			if let Some(react_order) = state.hands[reacter].get(react_slot - 1) {
 				let react_thought = &common.thoughts[*react_order];
 
@@ -68,7 +64,7 @@ impl Reactor {
 				continue;
 			}
 
-			let react_slot = Reactor::calc_slot(focus_slot, i + 1);
+			let react_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, i + 1);
 			if let Some(react_order) = state.hands[reacter].get(react_slot - 1) {
 				let react_thought = &common.thoughts[*react_order];
 				let playable_reacts = react_thought.possible.iter().filter(|&i| state.is_playable(i)).collect::<Vec<_>>();
@@ -90,7 +86,7 @@ impl Reactor {
 				continue;
 			}
 
-			let react_slot = Reactor::calc_slot(focus_slot, i + 1);
+			let react_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, i + 1);
 			if let Some(react_order) = state.hands[reacter].get(react_slot - 1) {
 				let react_thought = &common.thoughts[*react_order];
 
@@ -115,7 +111,7 @@ impl Reactor {
 				continue;
 			}
 
-			let react_slot = Reactor::calc_slot(focus_slot, i + 1);
+			let react_slot = WaitingConnection::calc_slot(state.hand_size(), focus_slot, i + 1);
 			if let Some(react_order) = state.hands[reacter].get(react_slot - 1) {
 				let react_thought = &common.thoughts[*react_order];
 				let playable_reacts = react_thought.possible.iter().filter(|&i| state.is_playable(i)).collect::<Vec<_>>();
@@ -159,30 +155,71 @@ impl Reactor {
 	}
 
 	fn target_iplay(_prev: &Game, game: &mut Game, wc: &WaitingConnection, target_slot: usize) {
-		let Game { common, state, meta, .. } = game;
 		let order = wc.receiver_hand[target_slot - 1];
+		let was_zcs = game.meta[order].status == CardStatus::ZeroClueChop;
+		let Game { common, state, meta, .. } = game;
+		let meta = &mut meta[order];
 
-		if meta[order].status == CardStatus::ZeroClueChop {
-			if let Some(new_zcs) = state.hands[wc.receiver].iter().find(|&&o| o < order && !state.deck[o].clued && meta[o].status == CardStatus::None) {
+		common.thoughts[order].old_inferred = Some(common.thoughts[order].inferred);
+		common.thoughts[order].inferred.retain(|i| state.is_playable(i));
+		meta.status = CardStatus::CalledToPlay;
+		meta.by = Some(wc.giver);
+		meta.focused = true;
+
+		if meta.reasoning.last().is_none_or(|r| *r != state.turn_count) {
+			meta.reasoning.push(state.turn_count);
+		}
+
+		// The zcs moves to the receiver's next chop
+		if was_zcs {
+			if let Some(new_zcs) = game.frame().chop(wc.receiver) {
 				info!("shifting zcs forward to {new_zcs}!");
-				meta[*new_zcs].status = CardStatus::ZeroClueChop;
+				game.meta[new_zcs].status = CardStatus::ZeroClueChop;
 			}
 			else {
 				warn!("unable to shift zcs forward!");
 			}
 		}
+	}
 
-		let meta = &mut game.meta[order];
+	/**
+	 * Continues a reactive finesse where the reacter played a different playable card than the prerequisite they were promised (a layer).
+	 * The prerequisite must still be in their hand, so their next finesse position is called to play it, and the receiver's target waits for it.
+	 */
+	fn continue_layer(prev: &Game, game: &mut Game, order: usize, wc: &WaitingConnection, target_slot: usize) {
+		let &WaitingConnection { giver, reacter, ref receiver_hand, .. } = wc;
 
-		common.thoughts[order].old_inferred = Some(common.thoughts[order].inferred);
-		common.thoughts[order].inferred.retain(|i| state.is_playable(i));
-		meta.status = CardStatus::CalledToPlay;
-		meta.by = Some(wc.giver);
-		meta.focused = true;
+		let [prereq] = prev.common.thoughts[order].inferred.to_vec()[..] else {
+			return;
+		};
 
-		if meta.reasoning.last().is_none_or(|r| *r != state.turn_count) {
-			meta.reasoning.push(state.turn_count);
+		let Game { common, state, meta, .. } = game;
+		let Some(next) = state.next_id(prereq) else {
+			return;
+		};
+
+		if state.deck[order].id().is_none_or(|id| id == prereq) {
+			return;
+		}
+
+		let Some(&finesse) = state.hands[reacter].iter().find(|&&o| !state.deck[o].clued && meta[o].status == CardStatus::None) else {
+			warn!("reacter played a layer, but has no finesse position left for {}!", state.log_id(prereq));
+			return;
+		};
+
+		common.thoughts[finesse].old_inferred = Some(common.thoughts[finesse].inferred);
+		common.thoughts[finesse].inferred = common.thoughts[finesse].possible.intersect(&IdentitySet::single(prereq));
+		meta[finesse].status = CardStatus::CalledToPlay;
+		meta[finesse].by = Some(giver);
+		meta[finesse].focused = true;
+		if meta[finesse].reasoning.last().is_none_or(|r| *r != state.turn_count) {
+			meta[finesse].reasoning.push(state.turn_count);
 		}
+
+		let target = receiver_hand[target_slot - 1];
+		common.thoughts[target].inferred = common.thoughts[target].possible.intersect(&IdentitySet::single(next));
+
+		info!("reacter played a layer, calling {finesse} to play {} before the receiver's {target}", state.log_id(prereq));
 	}
 
 	pub fn react_discard(prev: &Game, game: &mut Game, player_index: usize, order: usize, wc: &WaitingConnection) {
@@ -193,18 +230,18 @@ impl Reactor {
 			return;
 		}
 
-		let known_trash = prev.common.thinks_trash(&prev.frame(), reacter);
+		let known_trash = prev.frame().trash_orders(&prev.common, reacter);
 
 		// We were waiting for a response inversion and they reacted unnaturally
 		if inverted {
-			if if known_trash.is_empty() { prev.state.hands[reacter][0] != order } else { !known_trash.contains(&order) } {
+			if if known_trash.is_empty() { !prev.frame().is_chop(order) } else { !known_trash.contains(&order) } {
 				let rewind_turn = turn;
 				match game.rewind(rewind_turn, Action::interp(ClueInterp::Reactive)) {
 					Ok(new_game) => {
 						*game = new_game;
 						return;
 					}
-					Err(err) => warn!("Failed to rewind a response inversion! {err}")
+					Err(err) => warn!("Failed to rewind a response inversion! {err:?}")
 				}
 			}
 			else {
@@ -239,7 +276,7 @@ impl Reactor {
 			return;
 		}
 
-		let known_playables = prev.common.obvious_playables(&prev.frame(), reacter);
+		let known_playables = prev.frame().playable_orders(&prev.common, reacter);
 
 		// We were waiting for a response inversion and they reacted unnaturally
 		if inverted {
@@ -250,7 +287,7 @@ impl Reactor {
 						*game = new_game;
 						return;
 					}
-					Err(err) => warn!("Failed to rewind a response inversion! {err}")
+					Err(err) => warn!("Failed to rewind a response inversion! {err:?}")
 				}
 			}
 			else {
@@ -263,6 +300,7 @@ impl Reactor {
 				ClueKind::RANK => {
 					Reactor::target_iplay(prev, game, wc, target_slot);
 					Reactor::elim_play_play(&prev.state, &mut game.common, &mut game.meta, reacter, receiver_hand, focus_slot, target_slot);
+					Reactor::continue_layer(prev, game, order, wc, target_slot);
 				},
 				ClueKind::COLOUR => {
 					Reactor::target_idiscard(prev, game, wc, target_slot);
@@ -5,13 +5,76 @@ use log::{info, warn};
 use crate::basics::game::SimOpts;
 use crate::basics::state::State;
 use crate::basics::variant::all_ids;
-use crate::reactor::{ClueInterp, Reactor, ReactorInterp};
-use crate::basics::action::{Action, ClueAction, PlayAction};
-use crate::basics::card::{CardStatus, Identifiable, Identity};
-use crate::basics::game::{Game, Interp};
+use crate::reactor::{ClueInterp, CluePreview, ClueSafety, Reactor, ReactorInterp};
+use crate::basics::action::{Action, ClueAction, PerformAction, PlayAction};
+use crate::basics::clue::Clue;
+use crate::basics::card::{CardStatus, IdOptions, Identifiable, Identity};
+use crate::basics::game::{CachedClue, Game, Interp};
 use crate::basics::clue_result::{bad_touch_result, elim_result, playables_result, BadTouchResult, ElimResult, PlayablesResult};
 
+/** The starting cost of giving a clue, before adding its predicted value. */
+const CLUE_COST: f32 = 0.5;
+/** How much a clue's predicted value is scaled by in the endgame, when we have something to play instead. */
+const ENDGAME_CLUE_MULT: f32 = 0.1;
+/** The bonus for playing a known playable, so that it is played before a clue that can wait. */
+const KNOWN_PLAY_BONUS: f32 = 1.0;
+/**
+ * How much each turn of negative pace costs, since there are no longer enough turns left to play every remaining card.
+ * Unlike the pace multiplier in eval_game, which only scales the loss of useful cards, this also applies to discarding trash.
+ */
+const PACE_DEFICIT_COST: f32 = 0.5;
+
 impl Reactor {
+	/** Returns whether giving the clue would be safe, by simulating it and checking its interpretation and bad touch. */
+	pub fn clue_safety(game: &Game, action: &ClueAction) -> ClueSafety {
+		let ClueAction { giver, target, .. } = action;
+		let hypo = game.simulate_clue(action, SimOpts::default());
+
+		let interp = match &hypo.last_move {
+			Some(Interp::Reactor(ReactorInterp::Clue(interp))) => interp,
+			None => &ClueInterp::Mistake
+		};
+
+		match interp {
+			ClueInterp::Illegal => return ClueSafety::Illegal,
+			ClueInterp::Lock => return ClueSafety::CausesLock,
+			_ => ()
+		}
+
+		let BadTouchResult { bad_touch, .. } = bad_touch_result(game, &hypo, *giver, *target);
+		if !bad_touch.is_empty() {
+			ClueSafety::BadTouch(bad_touch)
+		} else if *interp == ClueInterp::Mistake {
+			ClueSafety::Illegal
+		} else {
+			ClueSafety::Safe
+		}
+	}
+
+	/** Returns what giving the clue would mean, including the card it would reference, without changing the game. */
+	pub fn clue_preview(game: &Game, clue: &Clue) -> CluePreview {
+		let Game { common, state, .. } = game;
+		let hand = &state.hands[clue.target];
+		let list = state.clue_touched(hand, &clue.to_base());
+		let newly_touched = list.iter().filter(|&&o| !state.deck[o].clued).copied().collect::<Vec<_>>();
+
+		let action = ClueAction { giver: state.our_player_index, target: clue.target, list: list.clone(), clue: clue.to_base() };
+		let hypo = game.simulate_clue(&action, SimOpts::default());
+
+		let interp = match hypo.last_move {
+			Some(Interp::Reactor(ReactorInterp::Clue(interp))) => interp,
+			None => ClueInterp::Mistake
+		};
+
+		let ref_target = match interp {
+			ClueInterp::RefPlay => Reactor::ref_play_target(common, &game.frame(), hand, &newly_touched),
+			ClueInterp::RefDiscard => Reactor::ref_discard_target(state, hand, &list, &newly_touched),
+			_ => None
+		};
+
+		CluePreview { focus: newly_touched.iter().max().copied(), newly_touched, ref_target, interp }
+	}
+
 	pub fn get_result(game: &Game, hypo: &Game, action: &ClueAction) -> f32 {
 		let Game { state, common, meta, .. } = game;
 		let Game { state: hypo_state, common: hypo_common, .. } = hypo;
@@ -22,14 +85,14 @@ impl Reactor {
 		let ElimResult { new_touched, fill, elim } = elim_result(game, hypo, &hypo_state.hands[*target], list);
 		let PlayablesResult { playables, .. } = playables_result(game, hypo);
 
-		let revealed_trash = hypo_common.thinks_trash(&hypo_frame, *target).iter().filter(|&o|
-			hypo_state.deck[*o].clued && !common.thinks_trash(&game.frame(), *target).contains(o)).count();
+		let revealed_trash = hypo_frame.trash_orders(hypo_common, *target).iter().filter(|&o|
+			hypo_state.deck[*o].clued && !game.frame().trash_orders(common, *target).contains(o)).count();
 
 		let new_playables = state.hands.concat().iter().filter(|&o| meta[*o].status != CardStatus::CalledToPlay &&
 			hypo.meta[*o].status == CardStatus::CalledToPlay).copied().collect::<Vec<_>>();
 
 		let bad_playable = new_playables.iter().find(|&o|
-			!(hypo.me().hypo_plays.contains(o) || (state.in_endgame() && state.deck[*o].id().is_some_and(|i| state.is_playable(i))))
+			!(hypo.us().hypo_plays.contains(o) || (state.in_endgame() && state.deck[*o].id().is_some_and(|i| state.is_playable(i))))
 		).copied();
 
 		if let Some(bad_playable) = bad_playable {
@@ -43,6 +106,12 @@ impl Reactor {
 			return -100.0;
 		}
 
+		// A legitimate 5 stall gives no new information, so it is valued neutrally apart from its stalling priority (between fill-ins and hard burns)
+		if hypo.last_move == Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::FiveStall))) {
+			info!("clue {} is a 5 stall", clue.fmt(state, *target));
+			return 0.5;
+		}
+
 		if let Some(Interp::Reactor(ReactorInterp::Clue(last_move))) = &hypo.last_move {
 			if (last_move == &ClueInterp::RefPlay || last_move == &ClueInterp::Reclue) && playables.is_empty() && !state.in_endgame() {
 				warn!("clue {} looks like {:?} but gets no playables!", clue.fmt(state, *target), last_move);
@@ -61,7 +130,7 @@ impl Reactor {
 		}
 
 		// Previously-unclued playables whose copies are already touched
-		let duped_playables = hypo.me().hypo_plays.iter().filter(|&&p|
+		let duped_playables = hypo.us().hypo_plays.iter().filter(|&&p|
 			!state.deck[p].clued &&
 			state.hands.concat().iter().any(|&o| o != p && game.frame().is_touched(o) && state.deck[o].is(&state.deck[p]))
 		).count();
@@ -70,9 +139,21 @@ impl Reactor {
 
 		let untouched_plays = playables.iter().filter(|&&p| !hypo_state.deck[p].clued).count();
 
-		info!("good touch: {}, playables: [{}], duped: {}, trash: {}, fill: {}, elim: {}, bad_touch: {:?}, {:?}",
+		// New playables that connect onto an earlier new playable form a chain, which is worth slightly more than isolated plays
+		let mut chain_ids: Vec<Identity> = Vec::new();
+		let chained_plays = hypo.us().hypo_play_order.iter().filter(|o| playables.contains(o)).filter(|&&o| {
+			let Some(id) = hypo.us().thoughts[o].identity(&IdOptions { infer: true, ..Default::default() }) else {
+				return false;
+			};
+			let chained = chain_ids.iter().any(|&i| state.next_id(i) == Some(id));
+			chain_ids.push(id);
+			chained
+		}).count();
+
+		info!("good touch: {}, playables: [{}], chained: {}, duped: {}, trash: {}, fill: {}, elim: {}, bad_touch: {:?}, {:?}",
 			good_touch,
 			playables.iter().map(|&o| state.log_iden(&state.deck[o])).collect::<Vec<String>>().join(", "),
+			chained_plays,
 			duped_playables,
 			trash.len(),
 			fill.len(),
@@ -84,6 +165,7 @@ impl Reactor {
 		let mut value: f32 = good_touch
 			+ (playables.len() as f32 - 2.0*duped_playables as f32)
 			+ 0.2 * untouched_plays as f32
+			+ 0.1 * chained_plays as f32
 			+ if state.in_endgame() { 0.01 } else { 0.1 } * revealed_trash as f32
 			+ if state.in_endgame() { 0.2 } else { 0.1 } * fill.len() as f32
 			+ if state.in_endgame() { 0.1 } else { 0.05 } * elim.len() as f32
@@ -93,6 +175,8 @@ impl Reactor {
 			Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Mistake))) => value -= 10.0,
 			Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Fix))) => value += 1.0,
 			Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Reactive))) => value += 1.0,
+			// When stalling, prefer the most informative clue: fill-ins over 5 stalls over hard burns
+			Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Stall))) if !fill.is_empty() => value += 1.0,
 			_ => ()
 		}
 
@@ -115,10 +199,10 @@ impl Reactor {
 			return Reactor::eval_game(game);
 		}
 
-		let trash = game.players[player_index].thinks_trash(&frame, player_index);
+		let trash = frame.trash_orders(&game.players[player_index], player_index);
 		let urgent_dc = trash.iter().find(|o| meta[**o].urgent);
 
-		let all_playables = game.players[player_index].obvious_playables(&frame, player_index);
+		let all_playables = frame.playable_orders(&game.players[player_index], player_index);
 		if urgent_dc.is_none() && !all_playables.is_empty() {
 			let urgent_play = all_playables.iter().find(|o| meta[**o].urgent);
 
@@ -155,7 +239,7 @@ impl Reactor {
 
 		if game.players[player_index].thinks_locked(&frame, player_index) {
 			return if !state.can_clue() {
-				let locked_dc = game.players[player_index].locked_discard(state, player_index);
+				let locked_dc = game.players[player_index].locked_discard(&frame, player_index);
 				let id = state.deck[locked_dc].id().unwrap();
 				let action = Action::discard(player_index, locked_dc, id.suit_index as i32, id.rank as i32, false);
 				info!("locked discard!");
@@ -163,15 +247,16 @@ impl Reactor {
 			}
 			else {
 				let mut next_game = game.simulate_clean();
-				next_game.state.clue_tokens -= 1;
+				next_game.state.spend_clue();
 
 				Reactor::advance(&next_game, offset + 1)
 			}
 		}
 
-		if state.clue_tokens == Fraction::from(8) {
+		// Discarding isn't allowed at max clues, even for known trash
+		if !state.can_discard() {
 			let mut next_game = game.simulate_clean();
-			next_game.state.clue_tokens -= 1;
+			next_game.state.spend_clue();
 			info!("forced clue at 8 clues!");
 
 			return Reactor::advance(&next_game, offset + 1);
@@ -180,35 +265,34 @@ impl Reactor {
 		let bob = state.next_player_index(player_index);
 
 		if !state.hands[player_index].iter().any(|&o| meta[o].urgent) && offset == 1 && !common.thinks_loaded(&frame, bob) && let Some(chop) = Reactor::chop(game, bob) {
-			let id = state.deck[*chop].id().unwrap();
+			let id = state.deck[chop].id().unwrap();
 
 			// Assume Alice will clue Bob
 			if state.can_clue() && (state.is_critical(id) || state.is_playable(id)) {
 				let mut next_game = game.simulate_clean();
-				next_game.state.clue_tokens -= 1;
+				next_game.state.spend_clue();
 				info!("forcing {} to clue bob!", state.player_names[player_index]);
 				return Reactor::eval_game(&next_game);
 			}
 		}
 
-		let trash = game.players[player_index].thinks_trash(&frame, player_index);
-
 		match urgent_dc.or_else(|| trash.first()) {
 			None => {
-				if let Some(chop) = Reactor::chop(game, player_index) {
-					let id = state.deck[*chop].id().unwrap();
-					let action = Action::discard(player_index, *chop, id.suit_index as i32, id.rank as i32, false);
+				// With no known trash, the obvious discard is the chop
+				if let Some(chop) = frame.chop(player_index) {
+					let id = state.deck[chop].id().unwrap();
+					let action = Action::discard(player_index, chop, id.suit_index as i32, id.rank as i32, false);
 					let dc_game = Reactor::advance_game(game, &action);
 
 					if state.clue_tokens > Fraction::from(2) {
 						let mut clue_game = game.simulate_clean();
-						clue_game.state.clue_tokens -= 1;
+						clue_game.state.spend_clue();
 
 						let clue_prob = if offset == 1 {
 							if common.thinks_loaded(&frame, bob) {
 								0.2
 							} else if let Some(chop) = Reactor::chop(game, bob) {
-								if state.is_basic_trash(state.deck[*chop].id().unwrap()) { 0.2 } else { 0.7 }
+								if state.is_basic_trash(state.deck[chop].id().unwrap()) { 0.2 } else { 0.7 }
 							} else {
 								0.5
 							}
@@ -239,44 +323,85 @@ impl Reactor {
 		}
 	}
 
+	/** Returns the interpretation and value of a clue, if it has already been simulated from this game. */
+	pub fn cached_clue(game: &Game, action: &ClueAction) -> Option<CachedClue> {
+		game.clue_cache.lock().unwrap().get(&game.clue_key(action)).cloned()
+	}
+
+	/** Returns the value of a simulated clue, caching it along with the clue's interpretation. */
+	pub fn cache_clue(game: &Game, hypo: &Game, action: &ClueAction) -> f32 {
+		if let Some(CachedClue { value, .. }) = Reactor::cached_clue(game, action) {
+			return value;
+		}
+
+		let value = if Reactor::illegal_clue(&hypo.last_move) { -100.0 } else { Reactor::get_result(game, hypo, action) };
+		game.clue_cache.lock().unwrap().insert(game.clue_key(action), CachedClue { interp: hypo.last_move.clone(), value });
+		value
+	}
+
+	/** Returns the predicted value of a clue, simulating it if it hasn't been already. */
+	pub fn clue_value(game: &Game, action: &ClueAction) -> f32 {
+		match Reactor::cached_clue(game, action) {
+			Some(CachedClue { value, .. }) => value,
+			None => Reactor::cache_clue(game, &game.simulate_clue(action, SimOpts { log: true, ..SimOpts::default() }), action)
+		}
+	}
+
+	pub(super) fn illegal_clue(interp: &Option<Interp>) -> bool {
+		matches!(interp, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Mistake))) | Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Illegal))))
+	}
+
 	pub fn eval_action(game: &Game, action: &Action) -> f32 {
 		info!("{}", format!("===== Predicting value for {} =====", action.fmt(&game.state)).green());
 
 		let Game { state, .. } = game;
+
+		if let Action::Clue(clue) = action && Reactor::cached_clue(game, clue).is_some_and(|cached| Reactor::illegal_clue(&cached.interp)) {
+			return -100.0;
+		}
+
 		let hypo_game = Reactor::advance_game(game, action);
 
 		let value = match action {
 			Action::Clue(clue) => {
-				if matches!(hypo_game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Mistake))) | Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Illegal)))) {
+				if Reactor::illegal_clue(&hypo_game.last_move) {
+					Reactor::cache_clue(game, &hypo_game, clue);
 					return -100.0;
 				}
 
-				let mult = if !game.me().obvious_playables(&game.frame(), state.our_player_index).is_empty() {
-					if state.in_endgame() { 0.1 } else { 0.25 }
+				let mult = if !game.frame().playable_orders(game.us(), state.our_player_index).is_empty() {
+					if state.in_endgame() { ENDGAME_CLUE_MULT } else { 0.25 }
 				} else {
 					0.5
 				};
 
-				Reactor::get_result(game, &hypo_game, clue) * mult - 0.5
-			},
-			Action::Play(PlayAction { suit_index, rank, .. }) => {
-				if *suit_index == -1 || *rank == -1 {
-					1.5
-				}
-				else {
-					0.0
-				}
+				Reactor::cache_clue(game, &hypo_game, clue) * mult - CLUE_COST
 			},
+			Action::Play(PlayAction { suit_index, rank, .. }) if *suit_index == -1 || *rank == -1 => 1.5,
+			// Unless the next player is about to discard something important
+			Action::Play(PlayAction { order, .. }) if game.us().order_kp(&game.frame(), *order) && !Reactor::urgent_clue(game) => KNOWN_PLAY_BONUS,
 			_ => 0.0
 		};
 
 		info!("starting value {value}");
 
 		let best = value + Reactor::advance(&hypo_game, 1);
-		info!("{}: {} ({:?})", action.fmt(state), best, hypo_game.last_move.unwrap());
+		let (action_str, interp) = (action.fmt(state), hypo_game.last_move.as_ref().map(|interp| format!("{interp:?}")).unwrap_or_default());
+		info!(action = action_str.as_str(), value = best, interp = interp.as_str(); "{action_str}: {best} ({:?})", hypo_game.last_move);
 		best
 	}
 
+	/** Returns the action with the highest predicted value, keeping the first on ties. */
+	pub(super) fn best_action<'a>(evaluated: impl Iterator<Item = (&'a (PerformAction, Action), f32)>) -> PerformAction {
+		evaluated.fold((f32::MIN, None), |(best_value, best), (curr, value)| {
+			if value > best_value {
+				(value, Some(curr))
+			} else {
+				(best_value, best)
+			}
+		}).1.unwrap().0
+	}
+
 	fn eval_state(state: &State) -> f32 {
 		// The first 2 * (# suits) pts are worth 2.
 		let mut score_val = std::cmp::min(state.score(), 2 * state.variant.suits.len()) as f32;
@@ -307,12 +432,16 @@ impl Reactor {
 			0.0
 		};
 
-		info!("state eval: score {score_val}, clues {clue_val}, dc crit {dc_crit_val}, strikes {strikes_val}");
+		// Discarding once pace is negative burns a turn that a remaining card needed
+		let pace_val = PACE_DEFICIT_COST * state.pace().min(0) as f32;
+
+		info!("state eval: score {score_val}, clues {clue_val}, dc crit {dc_crit_val}, strikes {strikes_val}, pace {pace_val}");
 
-		score_val + clue_val + dc_crit_val + strikes_val
+		score_val + clue_val + dc_crit_val + strikes_val + pace_val
 	}
 
-	fn eval_game(game: &Game) -> f32 {
+	/** Returns the value of the game's current position, including cards called to play or discard and cards already discarded. */
+	pub fn eval_game(game: &Game) -> f32 {
 		let mut value = 0.0;
 		let Game { state, meta, .. } = game;
 
@@ -322,6 +451,9 @@ impl Reactor {
 
 		value += Reactor::eval_state(state);
 
+		// Losing useful cards gets costlier as pace runs out
+		let pace_mult = 1.0 + 0.5 * (2 * state.num_players as i32 - state.pace()).max(0) as f32 / state.num_players as f32;
+
 		let mut future_val = 0.0;
 
 		for &order in &state.hands.concat() {
@@ -350,14 +482,14 @@ impl Reactor {
 					},
 					Some(id) => if state.is_basic_trash(id) {
 						future_val += 1.0;
-					} else if game.me().is_sieved(&game.frame(), state.deck[order].id().unwrap(), order) {
+					} else if game.us().is_sieved(&game.frame(), state.deck[order].id().unwrap(), order) {
 						future_val += 0.5;
 					} else if state.is_critical(id) {
-						future_val -= (5.0 - state.playable_away(id) as f32) * 10.0;
+						future_val -= (5.0 - state.playable_away(id) as f32) * 10.0 * pace_mult;
 					} else if by != state.our_player_index {
 						continue;
 					}else {
-						future_val -= (5.0 - state.playable_away(id) as f32) * 0.5;
+						future_val -= (5.0 - state.playable_away(id) as f32) * 0.5 * pace_mult;
 					}
 				}
 			}
@@ -368,19 +500,20 @@ impl Reactor {
 		let mut bdr_val = 0.0;
 
 		for id in all_ids(&state.variant) {
-			if state.is_basic_trash(id) || id.rank == 5 {
+			// Discarding a single-copy card is already counted in the max score
+			if state.is_basic_trash(id) || state.card_count(id) == 1 {
 				continue;
 			}
 
-			let discarded = &state.discard_stacks[id.suit_index][id.rank - 1];
+			let discarded = state.discards(id);
 
 			if discarded.is_empty() {
 				continue;
 			}
 
 			// Trust others to discard stuff duplicated in our hand
-			let duplicated = state.hands.concat().iter().any(|&o| state.deck[o].is(&id)) ||
-				(discarded.iter().all(|&o| meta[o].by.is_some_and(|by| by != state.our_player_index)) && state.our_hand().iter().any(|&o| game.me().thoughts[o].possible.contains(id)));
+			let duplicated = state.visible_count(id) > 0 ||
+				(discarded.iter().all(|&o| meta[o].by.is_some_and(|by| by != state.our_player_index)) && state.our_hand().iter().any(|&o| game.us().thoughts[o].possible.contains(id)));
 
 			if duplicated {
 				bdr_val -= 0.1;
@@ -395,7 +528,7 @@ impl Reactor {
 			}
 		}
 
-		bdr_val *= 2.5;
+		bdr_val *= 2.5 * pace_mult;
 
 		value += bdr_val;
 
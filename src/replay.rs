@@ -0,0 +1,86 @@
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::basics::action::{Action, DrawAction, GameOverAction, PerformAction, TurnAction};
+use crate::basics::{card::Identity, game::Game, state::State, util, variant::Variant};
+use crate::reactor::Reactor;
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayOptions {
+	pub variant: String
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GameData {
+	pub players: Vec<String>,
+	pub deck: Vec<Identity>,
+	pub actions: Vec<PerformAction>,
+	pub options: Option<ReplayOptions>,
+}
+
+impl GameData {
+	pub fn parse(json: &str) -> Result<Self, String> {
+		serde_json::from_str(json).map_err(|err| format!("Failed to deserialize game data: {err}"))
+	}
+
+	pub fn variant_name(&self) -> &str {
+		self.options.as_ref().map(|opts| opts.variant.as_str()).unwrap_or("No Variant")
+	}
+}
+
+/** Parses a hanab.live shared replay, returning the game at the end of the replay from the perspective of the given player. */
+pub fn load_shared_replay(json: &str, index: usize, variant: Arc<Variant>) -> Result<Game, String> {
+	replay_game(&GameData::parse(json)?, index, variant)
+}
+
+/** Drives the deck and actions of a replay through the game, returning the game at the end of the replay. */
+pub fn replay_game(data: &GameData, index: usize, variant: Arc<Variant>) -> Result<Game, String> {
+	let GameData { players, deck, actions, .. } = data;
+
+	if index >= players.len() {
+		return Err(format!("Replay only has {} players!", players.len()));
+	}
+
+	if data.variant_name() != variant.name {
+		return Err(format!("Replay has variant {}, but was given {}", data.variant_name(), variant.name));
+	}
+
+	let state = State::new(players.clone(), index, variant);
+	let mut game = Game::new(0, state, false, Arc::new(Reactor));
+	game.catchup = true;
+
+	let draw = |game: &mut Game, player_index: usize| {
+		let order = game.state.card_order;
+		game.handle_action(&Action::Draw(DrawAction {
+			player_index,
+			order,
+			suit_index: if player_index == index { -1 } else { deck[order].suit_index as i32 },
+			rank: if player_index == index { -1 } else { deck[order].rank as i32 }
+		}));
+	};
+
+	for player_index in 0..game.state.num_players {
+		for _ in 0..game.state.hand_size() {
+			draw(&mut game, player_index);
+		}
+	}
+
+	for action in actions {
+		let mut player_index = game.state.current_player_index;
+		game.handle_action(&util::perform_to_action(&game.state, action, player_index, Some(deck)));
+
+		if game.state.card_order < deck.len() && matches!(action, PerformAction::Play { .. } | PerformAction::Discard { .. }) {
+			draw(&mut game, player_index);
+		}
+
+		if let PerformAction::Play { .. } = action && game.state.strikes == 3 {
+			game.handle_action(&Action::GameOver(GameOverAction { player_index, end_condition: 0 }));
+		}
+
+		player_index = game.state.next_player_index(game.state.current_player_index);
+		game.handle_action(&Action::Turn(TurnAction { num: game.state.turn_count, current_player_index: player_index as i32 }));
+	}
+
+	game.catchup = false;
+	Ok(game)
+}
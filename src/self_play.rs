@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use crate::basics::action::{Action, DrawAction, PerformAction, TurnAction};
+use crate::basics::{card::Identity, game::Game, state::State, util};
+use crate::basics::variant::Variant;
+use crate::logger;
+use crate::reactor::Reactor;
+
+pub const PLAYER_NAMES: [&str; 6] = ["Alice", "Bob", "Cathy", "Donald", "Emily", "Frank"];
+
+/** Returns the name hanab.live gives to the nth seed of a variant for this many players (e.g. "p3v0s1"). */
+pub fn seed_name(num_players: usize, variant: &Variant, n: usize) -> String {
+	format!("p{num_players}v{}s{n}", variant.id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelfPlayOutcome {
+	Perfect, Strikeout, DiscardedCrit, OutOfPace
+}
+
+pub struct GameSummary {
+	pub score: usize,
+	pub result: SelfPlayOutcome,
+	pub actions: Vec<PerformAction>,
+	pub notes: Vec<Vec<String>>
+}
+
+/** Plays out a game on the given deck with the given number of players, with every player driven by the bot. */
+pub fn simulate_game(deck: &[Identity], variant: &Variant, num_players: usize) -> GameSummary {
+	let mut games = Vec::new();
+
+	for i in 0..num_players {
+		let player_names = PLAYER_NAMES[..num_players].iter().map(|name| name.to_string()).collect();
+		let state = State::new(player_names, i, Arc::new(variant.clone()));
+		let mut game = Game::new(0, state, false, Arc::new(Reactor));
+		game.catchup = true;
+
+		for player_index in 0..game.state.num_players {
+			for _ in 0..game.state.hand_size() {
+				let order = game.state.card_order;
+				game.handle_action(&Action::Draw(DrawAction {
+					player_index,
+					order,
+					suit_index: if player_index == i { -1 } else { deck[order].suit_index as i32 },
+					rank: if player_index == i { -1 } else { deck[order].rank as i32 }
+				}));
+			}
+		}
+		games.push(game);
+	}
+
+	let mut actions = Vec::new();
+
+	while !games[0].state.ended() {
+		let current_player_index = games[0].state.current_player_index;
+		let current_game = &games[current_player_index];
+		logger::set_context(None, Some(current_game.state.turn_count));
+		let perform = current_game.take_action();
+		actions.push(perform);
+
+		for game in &mut games {
+			let Game { state, .. } = game;
+			let action = util::perform_to_action(state, &perform, current_player_index, Some(deck));
+
+			game.handle_action(&action);
+
+			if game.state.ended() {
+				break;
+			}
+
+			if game.state.card_order < deck.len() {
+				match perform {
+					PerformAction::Play { .. } | PerformAction::Discard { .. } => {
+						let player_index = current_player_index;
+						let order = game.state.card_order;
+
+						game.handle_action(&Action::Draw(DrawAction {
+							player_index,
+							order,
+							suit_index: if player_index == game.state.our_player_index { -1 } else { deck[order].suit_index as i32 },
+							rank: if player_index == game.state.our_player_index { -1 } else { deck[order].rank as i32 }
+						}));
+					}
+					_ => {}
+				}
+			}
+
+			game.handle_action(&Action::Turn(TurnAction {
+				num: game.state.turn_count,
+				current_player_index: game.state.next_player_index(current_player_index) as i32 }));
+		}
+	}
+
+	let target = games[0].state.last_player_index(games[0].state.current_player_index);
+	actions.push(PerformAction::Terminate {  target, value: 0 });
+
+	let State { strikes, max_ranks, .. } = &games[0].state;
+
+	let result = if *strikes == 3 {
+		SelfPlayOutcome::Strikeout
+	} else if games[0].state.score() == games[0].state.max_score() {
+		SelfPlayOutcome::Perfect
+	} else if max_ranks.iter().any(|max| *max != 5) {
+		SelfPlayOutcome::DiscardedCrit
+	} else {
+		SelfPlayOutcome::OutOfPace
+	};
+
+	GameSummary {
+		actions,
+		score: games[0].state.score(),
+		result,
+		notes: games.iter().map(|game| (0..game.state.card_order).map(|i|
+			game.notes.get(&(i as u64)).map_or("".to_owned(), |note| note.full())).collect()
+		).collect()
+	}
+}
@@ -38,6 +38,7 @@ async fn spawn_ws_client(req: Request<()>, mut debug_receiver: mpsc::UnboundedRe
 	spawn(async move {
 		let variant_manager = VariantManager::new().await;
 		let mut client = BotClient::new(sender, variant_manager);
+		client.bot_names = (1..).map_while(|i| dotenvy::var(format!("HANABI_USERNAME{i}")).ok()).collect();
 
 		loop {
 			tokio::select! {
@@ -1,10 +1,19 @@
 use fraction::Fraction;
-use rust_bot::basics::action::PerformAction;
+use rust_bot::basics::action::{Action, EndCondition, PerformAction, StatusAction, StrikeAction, TurnAction};
+use rust_bot::basics::card::{CardStatus, Identifiable, Identity};
 use rust_bot::basics::clue::ClueKind;
+use rust_bot::basics::player::Link;
+use rust_bot::basics::util::parse_clue;
+use serde_json::json;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 
-use rust_bot::basics::{game::Game};
-use rust_bot::reactor::Reactor;
+use rust_bot::basics::game::{Game, RewindError, SimOpts};
+use rust_bot::basics::variant::{Variant, VariantManager, VariantOpts};
+use rust_bot::command::BotClient;
+use rust_bot::reactor::{ClueInterp, Reactor};
+use rust_bot::seed::deck_from_seed;
+use rust_bot::self_play::{seed_name, simulate_game};
 
 use crate::util::{fully_known, pre_clue, take_turn, Player, TestClue, TestOptions};
 
@@ -47,3 +56,750 @@ fn it_doesnt_discard_an_easily_winnable_endgame() {
 	let action = game.take_action();
 	assert!(!matches!(action, PerformAction::Discard { .. }));
 }
+
+#[test]
+fn it_sacrifices_the_card_most_likely_to_have_a_copy() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "y1", "g1", "b1", "p1"],
+		&["r2", "y2", "g2", "b2", "p2"],
+	], TestOptions {
+		clue_tokens: Fraction::from(0),
+		discarded: &["r3", "r4", "y4", "g4", "b4"],
+		init: Box::new(|game: &mut Game| {
+			// Alice's hand is fully clued with 5, 4, 3, 5, 5.
+			for (slot, rank) in [5, 4, 3, 5, 5].into_iter().enumerate() {
+				pre_clue(game, Player::Alice, slot + 1, &[TestClue { kind: ClueKind::RANK, value: rank, giver: Player::Bob }]);
+			}
+		}),
+		..TestOptions::default()
+	});
+
+	// Alice's 4 is probably critical (only p4 has a copy remaining), but her 3 is probably not (only r3 is critical).
+	let action = game.take_action();
+	assert_eq!(action, PerformAction::Discard { target: game.state.hands[Player::Alice as usize][2] });
+}
+
+#[test]
+fn it_stalls_instead_of_discarding_in_safe_mode() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r4", "y4", "g3", "b3", "p4"],
+		&["r3", "y3", "g4", "b4", "p3"],
+	], TestOptions {
+		clue_tokens: Fraction::from(1),
+		..TestOptions::default()
+	});
+
+	// No clue gets a card played, so Alice would normally discard her chop.
+	assert_eq!(game.take_action(), PerformAction::Discard { target: game.state.hands[Player::Alice as usize][0] });
+
+	// In safe mode, her chop might be a 5, so she stalls with a clue instead.
+	game.safe_mode = true;
+	assert!(game.take_action().is_clue());
+}
+
+#[test]
+fn it_steps_between_adjacent_identities() {
+	let r1 = Identity { suit_index: 0, rank: 1 };
+	let r5 = Identity { suit_index: 0, rank: 5 };
+
+	assert_eq!(r1.next(), Identity { suit_index: 0, rank: 2 });
+	assert_eq!(r5.prev(), Identity { suit_index: 0, rank: 4 });
+	assert_eq!(r1.next().prev(), r1);
+
+	// There is nothing before a 1 or after a 5.
+	assert!(std::panic::catch_unwind(|| r1.prev()).is_err());
+	assert!(std::panic::catch_unwind(|| r5.next()).is_err());
+}
+
+#[test]
+fn it_distinguishes_us_from_the_current_player() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r2", "y2", "g3", "p3", "y3"],
+		&["r3", "g4", "p2", "y4", "r4"],
+	], TestOptions {
+		starting: Player::Bob,
+		..TestOptions::default()
+	});
+
+	// It's Bob's turn, but we are still Alice.
+	assert!(std::ptr::eq(game.current_player(), &game.players[Player::Bob as usize]));
+	assert!(std::ptr::eq(game.us(), &game.players[Player::Alice as usize]));
+	assert!(!game.is_our_turn());
+}
+
+#[test]
+fn it_writes_reasoning_turns_in_verbose_notes() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+	game.verbose_notes = true;
+
+	take_turn(&mut game, "Alice clues green to Bob");
+	take_turn(&mut game, "Bob plays b1, drawing y3");
+	take_turn(&mut game, "Cathy clues 5 to Bob");
+	take_turn(&mut game, "Alice discards y4 (slot 4)");
+
+	// Bob's g5 was touched by green on turn 1 and by 5 on turn 3.
+	let order = game.state.hands[Player::Bob as usize][4];
+	assert!(game.notes[&(order as u64)].last().contains("(t1,t3)"));
+}
+
+#[test]
+fn it_caches_clue_results() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	let action = parse_clue(&game.state, "Alice clues green to Bob").unwrap();
+	assert_eq!(Reactor::cached_clue(&game, &action), None);
+
+	let hypo_game = game.simulate_clue(&action, SimOpts::default());
+	let cold = Reactor::get_result(&game, &hypo_game, &action);
+
+	// The first value is computed and cached, while the second comes from the cache.
+	assert_eq!(Reactor::cache_clue(&game, &hypo_game, &action), cold);
+	assert_eq!(Reactor::cached_clue(&game, &action).map(|cached| (cached.interp, cached.value)), Some((hypo_game.last_move.clone(), cold)));
+
+	let value = Reactor::eval_action(&game, &Action::Clue(action.clone()));
+	assert_eq!(Reactor::eval_action(&game, &Action::Clue(action.clone())), value);
+
+	// The clue is simulated again once the game changes.
+	take_turn(&mut game, "Alice clues green to Bob");
+	assert_eq!(Reactor::cached_clue(&game, &action), None);
+}
+
+#[test]
+fn it_finds_finessed_cards() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			// Bob's b1 is called to play without a clue, while his g5 is clued and called to play.
+			let b1 = game.state.hands[Player::Bob as usize][0];
+			let g5 = game.state.hands[Player::Bob as usize][4];
+			game.meta[b1].status = CardStatus::CalledToPlay;
+			game.meta[g5].status = CardStatus::CalledToPlay;
+			pre_clue(game, Player::Bob, 5, &[TestClue { kind: ClueKind::RANK, value: 5, giver: Player::Alice }]);
+		}),
+		..TestOptions::default()
+	});
+
+	assert_eq!(game.common.thinks_finessed(&game.frame(), Player::Bob as usize), vec![game.state.hands[Player::Bob as usize][0]]);
+	assert!(game.common.thinks_finessed(&game.frame(), Player::Cathy as usize).is_empty());
+}
+
+#[test]
+fn it_finds_playable_and_trash_orders_from_the_frame() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["y4", "r2", "b3", "r1", "p4"],
+		&["g3", "y2", "b4", "p1", "g4"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 0]),
+		init: Box::new(|game: &mut Game| {
+			util::fully_known(game, Player::Bob, 2, "r2");
+			util::fully_known(game, Player::Bob, 4, "r1");
+		}),
+		..TestOptions::default()
+	});
+
+	let frame = game.frame();
+	let bob_hand = &game.state.hands[Player::Bob as usize];
+
+	// Everyone can see that Bob's known r2 is playable and his known r1 is trash
+	for perspective in game.players.iter().chain(std::iter::once(&game.common)) {
+		assert_eq!(frame.playable_orders(perspective, Player::Bob as usize), vec![bob_hand[1]]);
+		assert_eq!(frame.trash_orders(perspective, Player::Bob as usize), vec![bob_hand[3]]);
+	}
+
+	// Cathy's cards are unclued, so nobody knows anything about them
+	assert!(frame.playable_orders(&game.common, Player::Cathy as usize).is_empty());
+	assert!(frame.trash_orders(&game.common, Player::Cathy as usize).is_empty());
+}
+
+#[test]
+fn it_prunes_overwritten_notes_when_rewinding() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["p4", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	take_turn(&mut game, "Alice clues green to Bob");
+
+	// Bob's b1 is called to play by the ref play
+	let order = game.state.hands[Player::Bob as usize][0] as u64;
+	assert_eq!(game.notes[&order].entries, [(2, "[f] [r1,y1,b1,p1]".to_owned())]);
+	assert_eq!(game.notes[&order].full(), "t2: [f] [r1,y1,b1,p1]");
+
+	// Reinterpreting the clue as reactive overwrites what was noted on that turn
+	let rewound = game.rewind(1, Action::interp(ClueInterp::Reactive)).unwrap();
+	assert!(!rewound.notes[&order].full().contains("[f]"));
+	assert!(rewound.notes[&order].full().starts_with("t2: dc"));
+}
+
+#[test]
+fn it_limits_the_rewind_depth() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	take_turn(&mut game, "Alice clues 5 to Bob");
+	take_turn(&mut game, "Bob clues 5 to Cathy");
+	take_turn(&mut game, "Cathy clues 2 to Bob");
+	take_turn(&mut game, "Alice clues 3 to Bob");
+
+	let stall = Action::interp(ClueInterp::Stall);
+	let rewind_3 = |game: &Game| -> Result<Game, RewindError> {
+		game.rewind(1, stall.clone())?.rewind(2, stall.clone())?.rewind(3, stall.clone())
+	};
+
+	// Three nested rewinds are allowed by default, but not a fourth.
+	let deep = rewind_3(&game).unwrap();
+	assert_eq!(deep.rewind(4, stall.clone()).err(), Some(RewindError::TooDeep));
+	assert_eq!(game.rewind(1, stall.clone()).unwrap().rewind(1, stall.clone()).err(), Some(RewindError::AlreadyRewound));
+
+	game.max_rewind_depth = 4;
+	let deep = rewind_3(&game).unwrap();
+	assert!(deep.rewind(4, stall.clone()).is_ok());
+}
+
+#[test]
+fn it_keeps_the_base_when_blanking_a_blank_game() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			pre_clue(game, Player::Bob, 5, &[TestClue { kind: ClueKind::RANK, value: 5, giver: Player::Alice }]);
+		}),
+		..TestOptions::default()
+	});
+
+	// A nested rewind blanks a game that was itself blanked, which must still start from the original base.
+	let twice = game.blank(true).blank(true);
+	let g5 = game.state.hands[Player::Bob as usize][4];
+
+	assert!(Arc::ptr_eq(&twice.base, &game.base));
+	assert_eq!(twice.meta.len(), game.meta.len());
+	assert_eq!(twice.common.thoughts[g5].inferred, game.common.thoughts[g5].inferred);
+}
+
+#[test]
+fn it_handles_strike_actions() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		starting: Player::Bob,
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	let order = game.state.hands[Player::Bob as usize][3];
+	take_turn(&mut game, "Bob bombs r3, drawing y1");
+	game.handle_action(&Action::Strike(StrikeAction { num: 1, turn: 1, order }));
+
+	// The strike isn't double-counted, and the bombed card is known.
+	assert_eq!(game.state.strikes, 1);
+	assert_eq!(game.common.thoughts[order].id(), Some(game.state.expand_short("r3")));
+
+	// A strike reported on its own still updates the count.
+	game.handle_action(&Action::Strike(StrikeAction { num: 2, turn: 2, order }));
+	assert_eq!(game.state.strikes, 2);
+}
+
+#[test]
+fn it_corrects_clue_tokens_from_a_status() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	game.handle_action(&Action::Status(StatusAction { clues: 7, score: 0, max_score: 25 }));
+	assert_eq!(game.state.clue_tokens, Fraction::from(7));
+
+	game.handle_action(&Action::Status(StatusAction { clues: 5, score: 0, max_score: 25 }));
+	assert_eq!(game.state.clue_tokens, Fraction::from(5));
+}
+
+#[test]
+fn it_reads_clue_starved_tokens_from_a_status() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::new(15_u64, 2_u64),
+		variant: "Clue Starved (5 Suits)",
+		..TestOptions::default()
+	});
+
+	// The server counts clue tokens in halves
+	game.handle_action(&Action::Status(StatusAction { clues: 15, score: 0, max_score: 25 }));
+	assert_eq!(game.state.clue_tokens, Fraction::new(15_u64, 2_u64));
+
+	game.handle_action(&Action::Status(StatusAction { clues: 9, score: 0, max_score: 25 }));
+	assert_eq!(game.state.clue_tokens, Fraction::new(9_u64, 2_u64));
+}
+
+#[test]
+fn it_doesnt_locked_discard_a_finesse_connector() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r3", "g2", "b2", "b3", "g4"],
+		&["g1", "b1", "p2", "b1", "g4"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 0]),
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Alice, 1, "r2");
+			fully_known(game, Player::Alice, 2, "y5");
+			fully_known(game, Player::Alice, 3, "g5");
+			fully_known(game, Player::Alice, 4, "b5");
+			fully_known(game, Player::Alice, 5, "p5");
+
+			// Bob's r3 is called to play, waiting on Alice's r2
+			let order = game.state.hands[Player::Bob as usize][0];
+			game.meta[order].status = CardStatus::CalledToPlay;
+		}),
+		..TestOptions::default()
+	});
+
+	let r2 = game.state.hands[Player::Alice as usize][0];
+	assert_ne!(game.us().locked_discard(&game.frame(), Player::Alice as usize), r2);
+}
+
+#[test]
+fn it_avoids_a_double_discard() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["y4", "g3", "b3", "p4", "b4"],
+		&["r3", "g4", "y3", "b2", "p2"],
+	], TestOptions {
+		starting: Player::Cathy,
+		clue_tokens: Fraction::from(4),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Cathy discards r3 (slot 1), drawing y2");
+	assert_eq!(game.state.last_discard(), Some((Player::Cathy as usize, game.state.expand_short("r3"))));
+
+	// Our chop might be the other r3, so we should clue instead of discarding.
+	let action = game.take_action();
+	assert!(!matches!(action, PerformAction::Discard { .. }));
+}
+
+#[test]
+fn it_navigates_to_a_mid_turn_draw() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	take_turn(&mut game, "Alice clues blue to Bob");
+	take_turn(&mut game, "Bob plays b1, drawing y3");
+	take_turn(&mut game, "Cathy clues 5 to Alice (slot 5)");
+
+	let actions = game.state.action_list.concat();
+	let draw_index = actions.iter().rposition(|action| matches!(action, Action::Draw(draw) if draw.player_index == Player::Bob as usize)).unwrap();
+
+	// Just before the draw, Bob has played b1 but not replaced it
+	let before_draw = game.navigate_to_action(draw_index);
+	assert_eq!(before_draw.state.hands[Player::Bob as usize].len(), 4);
+	assert_eq!(before_draw.state.play_stacks[3], 1);
+
+	let after_draw = game.navigate_to_action(draw_index + 1);
+	let new_card = after_draw.state.hands[Player::Bob as usize][0];
+	assert_eq!(after_draw.state.hands[Player::Bob as usize].len(), 5);
+	assert_eq!(after_draw.state.deck[new_card].id(), Some(after_draw.state.expand_short("y3")));
+	assert_eq!(after_draw.state.action_list, game.state.action_list);
+}
+
+#[test]
+fn it_shadows_another_player() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	take_turn(&mut game, "Alice clues blue to Bob");
+	take_turn(&mut game, "Bob plays b1, drawing y3");
+
+	// Bob can't see his own cards, but can see Cathy's
+	let bob = game.shadow(Player::Bob as usize);
+	assert_eq!(bob.state.our_player_index, Player::Bob as usize);
+	assert!(bob.state.our_hand().iter().all(|&o| bob.state.deck[o].id().is_none()));
+	assert_eq!(bob.state.deck[bob.state.hands[Player::Cathy as usize][0]].id(), Some(bob.state.expand_short("g1")));
+	assert_eq!(bob.state.play_stacks[3], 1);
+
+	// Cathy sees the card Bob drew
+	let cathy = game.shadow(Player::Cathy as usize);
+	assert_eq!(cathy.state.our_player_index, Player::Cathy as usize);
+	assert!(cathy.state.our_hand().iter().all(|&o| cathy.state.deck[o].id().is_none()));
+	assert_eq!(cathy.state.deck[cathy.state.hands[Player::Bob as usize][0]].id(), Some(cathy.state.expand_short("y3")));
+	assert_eq!(cathy.state.action_list.len(), game.state.action_list.len());
+}
+
+#[test]
+fn it_ranks_candidate_action_values() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	let values = Reactor::action_values(&game);
+	assert!(values.windows(2).all(|w| w[0].1 >= w[1].1));
+	assert_eq!(values[0].0, game.take_action());
+}
+
+#[test]
+fn it_self_plays_a_seed_deterministically() {
+	let variant = Variant::new(0, "No Variant", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts::default());
+	let deck = deck_from_seed(&variant, &seed_name(3, &variant, 14));
+
+	assert_eq!(simulate_game(&deck, &variant, 3).actions, simulate_game(&deck, &variant, 3).actions);
+}
+
+#[test]
+fn it_routes_actions_between_tables() {
+	let (sender, _receiver) = mpsc::unbounded_channel::<String>();
+	let mut client = BotClient::new(sender, VariantManager::default());
+
+	let hands: [&[&str]; 3] = [
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	];
+
+	for table_id in [1, 2] {
+		let mut game = util::setup(Arc::new(Reactor), &hands, TestOptions::default());
+		game.table_id = table_id;
+		client.add_game(game);
+	}
+
+	let send = |client: &mut BotClient, table_id: u32, action: Action| {
+		client.handle_msg(format!("gameAction {}", json!({ "tableID": table_id, "action": action })));
+	};
+
+	// Interleave a blue clue to Bob at table 1 with a red clue to Bob at table 2
+	let blue = parse_clue(&client.games[&1].state, "Alice clues blue to Bob").unwrap();
+	let red = parse_clue(&client.games[&2].state, "Alice clues red to Bob").unwrap();
+
+	send(&mut client, 1, Action::Clue(blue));
+	send(&mut client, 2, Action::Clue(red));
+	send(&mut client, 2, Action::Turn(TurnAction { num: 1, current_player_index: Player::Bob as i32 }));
+	send(&mut client, 1, Action::Turn(TurnAction { num: 1, current_player_index: Player::Bob as i32 }));
+
+	for (table_id, clued_slots) in [(1, [true, false, false, false, false]), (2, [false, false, true, true, false])] {
+		let game = &client.games[&table_id];
+		let bob_hand = &game.state.hands[Player::Bob as usize];
+
+		assert_eq!(game.state.turn_count, 2);
+		assert_eq!(game.state.clue_tokens, Fraction::from(7));
+		assert_eq!(bob_hand.iter().map(|&o| game.state.deck[o].clued).collect::<Vec<_>>(), clued_slots);
+	}
+}
+
+#[test]
+fn it_forgets_a_table_after_leaving_it() {
+	let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+	let mut client = BotClient::new(sender, VariantManager::default());
+
+	for table_id in [1, 2] {
+		client.handle_msg(format!("joined {}", json!({ "tableID": table_id })));
+
+		let mut game = util::setup(Arc::new(Reactor), &[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["b1", "g2", "r2", "r3", "g5"],
+			&["g1", "b5", "p2", "b1", "g4"],
+		], TestOptions::default());
+		game.table_id = table_id;
+		client.add_game(game);
+	}
+
+	client.handle_msg(format!("left {}", json!({ "tableID": 1 })));
+	assert!(!client.games.contains_key(&1));
+	assert!(client.games.contains_key(&2));
+
+	// Chat from a table without a game is still answered at that table
+	client.handle_msg(format!("chat {}", json!({ "msg": "/explain on", "who": "cathy", "room": "table1", "recipient": "" })));
+
+	let reply = receiver.try_recv().unwrap();
+	assert!(reply.starts_with("chat ") && reply.contains(r#""room":"table1""#), "{reply}");
+}
+
+#[test]
+fn it_finds_the_chop() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3"],
+		&["g1", "b5", "p2", "g4"],
+		&["r4", "y4", "b4", "p4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			// Bob's hand is fully clued, Cathy's slots 1 and 2 are clued
+			for slot in 1..=4 {
+				fully_known(game, Player::Bob, slot, ["b1", "g2", "r2", "r3"][slot - 1]);
+			}
+			pre_clue(game, Player::Cathy, 1, &[TestClue { kind: ClueKind::RANK, value: 1, giver: Player::Alice }]);
+			pre_clue(game, Player::Cathy, 2, &[TestClue { kind: ClueKind::RANK, value: 5, giver: Player::Alice }]);
+		}),
+		..TestOptions::default()
+	});
+
+	let frame = game.frame();
+	let cathy_hand = &game.state.hands[Player::Cathy as usize];
+	let donald_hand = &game.state.hands[Player::Donald as usize];
+
+	assert_eq!(frame.chop(Player::Bob as usize), None);
+	assert!(game.state.hands[Player::Bob as usize].iter().all(|&o| !frame.is_chop(o)));
+
+	assert_eq!(frame.chop(Player::Cathy as usize), Some(cathy_hand[2]));
+	assert!(frame.is_chop(cathy_hand[2]) && !frame.is_chop(cathy_hand[0]) && !frame.is_chop(cathy_hand[3]));
+
+	assert_eq!(frame.chop(Player::Donald as usize), Some(donald_hand[0]));
+	assert!(frame.is_chop(donald_hand[0]) && !frame.is_chop(donald_hand[1]));
+}
+
+#[test]
+fn it_records_why_the_game_ended() {
+	for (code, end_condition) in [(1, EndCondition::Normal), (2, EndCondition::Strikeout), (4, EndCondition::Terminated), (10, EndCondition::TerminatedByVote), (42, EndCondition::Unknown(42))] {
+		let mut game = util::setup(Arc::new(Reactor), &[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["b1", "g2", "r2", "r3", "g5"],
+			&["g1", "b5", "p2", "b1", "g4"],
+		], TestOptions {
+			play_stacks: Some(&[3, 2, 0, 1, 0]),
+			..TestOptions::default()
+		});
+
+		game.handle_action(&Action::game_over(code, Player::Alice as usize));
+
+		assert!(!game.in_progress);
+		assert_eq!(game.end_condition, Some(end_condition));
+		assert_eq!(game.result().score, 6);
+	}
+
+	assert_eq!(EndCondition::from(2).reason(), "the team struck out");
+	assert_eq!(EndCondition::from(42).reason(), "unknown end condition 42");
+}
+
+#[test]
+fn it_dissolves_promises_played_elsewhere() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r3", "r4", "g2", "b3", "y5"],
+		&["r2", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 0]),
+		starting: Player::Cathy,
+		init: Box::new(|game: &mut Game| {
+			for slot in [1, 2] {
+				pre_clue(game, Player::Bob, slot, &[TestClue { kind: ClueKind::COLOUR, value: 0, giver: Player::Alice }]);
+			}
+
+			// One of Bob's red cards was promised to be r2
+			let Game { state, common, .. } = game;
+			let orders = state.hands[Player::Bob as usize][0..2].to_vec();
+			common.links.push(Link::Promised { orders, id: state.expand_short("r2"), target: state.hands[Player::Bob as usize][0] });
+		}),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Cathy plays r2, drawing r1");
+
+	let r2 = game.state.expand_short("r2");
+	assert!(game.common.links.iter().all(|link| !matches!(link, Link::Promised { .. })));
+
+	for &order in &game.state.hands[Player::Bob as usize][0..2] {
+		assert!(!game.common.thoughts[order].inferred.contains(r2));
+	}
+}
+
+#[test]
+fn it_lists_legal_clues() {
+	let hands: [&[&str]; 3] = [
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["r1", "y1", "g1", "b1", "p1"],
+	];
+	let game = util::setup(Arc::new(Reactor), &hands, TestOptions::default());
+	let clues = |game: &Game, player: Player| game.state.legal_clues(player as usize).iter().map(|clue| clue.fmt(&game.state)).collect::<Vec<_>>();
+
+	assert_eq!(clues(&game, Player::Bob), ["(1 to Bob)", "(2 to Bob)", "(3 to Bob)", "(5 to Bob)", "(red to Bob)", "(green to Bob)", "(blue to Bob)"]);
+
+	// No clues can be given without a clue token
+	let game = util::setup(Arc::new(Reactor), &hands, TestOptions { clue_tokens: Fraction::from(0), ..TestOptions::default() });
+	assert!(game.state.legal_clues(Player::Bob as usize).is_empty());
+
+	// Colour clues can't touch white ones, so they can't be given to Cathy
+	let game = util::setup(Arc::new(Reactor), &hands, TestOptions { variant: "White-Ones (5 Suits)", ..TestOptions::default() });
+	assert_eq!(clues(&game, Player::Cathy), ["(1 to Cathy)"]);
+	assert!(clues(&game, Player::Bob).contains(&"(green to Bob)".to_string()));
+
+	// Rank clues touch cards as usual, but can't be given at all
+	let game = util::setup(Arc::new(Reactor), &hands, TestOptions { variant: "Number Mute (5 Suits)", ..TestOptions::default() });
+	assert!(game.state.all_valid_clues(Player::Bob as usize).iter().any(|clue| clue.kind == ClueKind::RANK));
+	assert_eq!(clues(&game, Player::Bob), ["(red to Bob)", "(green to Bob)", "(blue to Bob)"]);
+}
+
+#[test]
+fn it_doesnt_underflow_clues_after_the_final_turn() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			game.state.cards_left = 0;
+			game.state.endgame_turns = Some(0);
+		}),
+		..TestOptions::default()
+	});
+
+	let clue = parse_clue(&game.state, "Alice clues blue to Bob").unwrap();
+	game.handle_action(&Action::Clue(clue));
+
+	assert_eq!(game.state.endgame_turns, Some(0));
+	assert!(game.state.ended());
+}
+
+#[test]
+fn it_rechecks_an_action_after_the_delay() {
+	// Time is paused, so the delay elapses only when advanced
+	let runtime = tokio::runtime::Builder::new_current_thread().enable_all().start_paused(true).build().unwrap();
+	let _guard = runtime.enter();
+
+	let sent_actions = |receiver: &mut mpsc::UnboundedReceiver<String>| {
+		std::iter::from_fn(|| receiver.try_recv().ok()).filter(|cmd| cmd.starts_with("action ")).count()
+	};
+
+	for move_on in [false, true] {
+		let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+		let client = &mut BotClient::new(sender, VariantManager::default());
+
+		let mut game = util::setup(Arc::new(Reactor), &[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["b1", "g2", "r2", "r3", "g5"],
+			&["g1", "b5", "p2", "b1", "g4"],
+		], TestOptions::default());
+		game.in_progress = true;
+		game.catchup = false;
+		game.table_id = 1;
+		client.add_game(game);
+
+		let send = |client: &mut BotClient, action: Action| {
+			client.handle_msg(format!("gameAction {}", json!({ "tableID": 1, "action": action })));
+		};
+
+		send(client, Action::turn(0, Player::Alice as i32));
+
+		// Alice's turn is taken elsewhere before the chosen action is sent
+		if move_on {
+			let clue = parse_clue(&client.games[&1].state, "Alice clues blue to Bob").unwrap();
+			send(client, Action::Clue(clue));
+			send(client, Action::turn(1, Player::Bob as i32));
+		}
+
+		runtime.block_on(async {
+			tokio::task::yield_now().await;
+			tokio::time::advance(client.action_delay).await;
+			tokio::task::yield_now().await;
+		});
+		assert_eq!(sent_actions(&mut receiver), if move_on { 0 } else { 1 });
+	}
+}
+
+#[test]
+fn it_doesnt_build_notes_in_simulations() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+	game.in_progress = true;
+
+	let clue = parse_clue(&game.state, "Alice clues green to Bob").unwrap();
+	let bob_slot1 = game.state.hands[Player::Bob as usize][0];
+
+	let mut hypo = game.simulate_clue(&clue, SimOpts::default());
+	hypo.handle_action(&Action::turn(1, Player::Bob as i32));
+	assert!(hypo.queued_cmds.is_empty());
+	assert!(hypo.notes.is_empty());
+
+	let hypo = game.simulate_action(&Action::Clue(clue.clone()), None).simulate_action(&Action::turn(1, Player::Bob as i32), None);
+	assert!(hypo.queued_cmds.is_empty());
+	assert!(hypo.notes.is_empty());
+
+	// Notes can still be requested explicitly
+	let mut hypo = game.simulate_clue(&clue, SimOpts { notes: true, ..SimOpts::default() });
+	hypo.handle_action(&Action::turn(1, Player::Bob as i32));
+	assert!(hypo.notes.contains_key(&(bob_slot1 as u64)));
+
+	take_turn(&mut game, "Alice clues green to Bob");
+	assert!(game.notes.contains_key(&(bob_slot1 as u64)));
+}
+
+#[test]
+fn it_doesnt_regain_clues_on_5s_when_the_variant_says_so() {
+	for (variant, clue_tokens) in [("No Variant", 6), ("Throw It in a Hole (5 Suits)", 5)] {
+		let mut game = util::setup(Arc::new(Reactor), &[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["r5", "g2", "r2", "r3", "g5"],
+			&["g1", "b5", "p2", "b1", "g4"],
+		], TestOptions {
+			play_stacks: Some(&[4, 0, 0, 0, 0]),
+			clue_tokens: Fraction::from(5),
+			starting: Player::Bob,
+			variant,
+			init: Box::new(|game: &mut Game| {
+				fully_known(game, Player::Bob, 1, "r5");
+			}),
+			..TestOptions::default()
+		});
+
+		take_turn(&mut game, "Bob plays r5, drawing y3");
+
+		assert_eq!(game.state.play_stacks[0], 5);
+		assert_eq!(game.state.clue_tokens, Fraction::from(clue_tokens));
+	}
+}
+
+#[test]
+fn it_reads_no_five_regain_from_the_variant_name() {
+	let variants = r#"[
+		{ "id": 0, "name": "No Variant", "suits": ["Red", "Yellow", "Green", "Blue", "Purple"] },
+		{ "id": 1, "name": "Throw It in a Hole (5 Suits)", "suits": ["Red", "Yellow", "Green", "Blue", "Purple"] }
+	]"#;
+	let suits = r#"[
+		{ "name": "Red", "abbreviation": "R" },
+		{ "name": "Yellow", "abbreviation": "Y" },
+		{ "name": "Green", "abbreviation": "G" },
+		{ "name": "Blue", "abbreviation": "B" },
+		{ "name": "Purple", "abbreviation": "P" }
+	]"#;
+	let mut variant_manager = VariantManager::from_json(variants, suits);
+
+	assert_eq!(variant_manager.get_variant("No Variant").no_five_regain, Some(false));
+	assert_eq!(variant_manager.get_variant("Throw It in a Hole (5 Suits)").no_five_regain, Some(true));
+}
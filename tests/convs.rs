@@ -1,4 +1,12 @@
+use rust_bot::command::convention_by_name;
+
 pub mod reactor;
 
 pub mod util;
 pub mod ex_asserts;
+
+#[test]
+fn it_finds_conventions_by_name() {
+	assert!(convention_by_name("Reactor 1.0").is_some());
+	assert!(convention_by_name("Unknown 1.0").is_none());
+}
@@ -1,5 +1,7 @@
-use rust_bot::basics::{clue::ClueKind};
+use rust_bot::basics::{clue::{BaseClue, ClueKind}};
+use rust_bot::basics::card::Identity;
 use rust_bot::basics::game::Game;
+use rust_bot::basics::variant::{id_touched, Variant, VariantManager, VariantOpts};
 use rust_bot::reactor::Reactor;
 use std::sync::Arc;
 
@@ -17,6 +19,29 @@ fn it_fails_impossible_setup() {
 	], TestOptions::default());
 }
 
+#[test]
+fn it_expands_short_forms() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "t2", "g3", "b4", "p5"],
+	], TestOptions { variant: "6 Suits", ..TestOptions::default() });
+
+	let ids = game.state.expand_shorts(&["r1", "t5", "t5"]);
+	assert_eq!(ids.len(), 2);
+	assert!(ids.contains(Identity { suit_index: 5, rank: 5 }));
+}
+
+#[test]
+#[should_panic(expected = "Colour x3 doesn't exist in selected variant")]
+fn it_rejects_unknown_short_forms() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "t2", "g3", "b4", "p5"],
+	], TestOptions { variant: "6 Suits", ..TestOptions::default() });
+
+	game.state.expand_shorts(&["r1", "t5", "x3"]);
+}
+
 #[test]
 fn it_elims_from_count() {
 	let mut game = util::setup(Arc::new(Reactor), &[
@@ -82,3 +107,91 @@ fn it_visibly_elims_mixed_cards() {
 	ex_asserts::has_possible(&game, None, Player::Bob, 3, &["r4", "r5"]);
 	ex_asserts::has_possible(&game, None, Player::Bob, 4, &["r4", "r5"]);
 }
+
+#[test]
+fn it_touches_muddy_rainbow_with_colours_only() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "y3", "g3", "b3", "b4"],
+	], TestOptions {
+		starting: Player::Bob,
+		variant: "Muddy Rainbow (5 Suits)",
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Bob clues red to Alice (slot 5)");
+	ex_asserts::has_possible(&game, Some(Player::Alice), Player::Alice, 5, &["r1", "r2", "r3", "r4", "r5", "m1", "m2", "m3", "m4", "m5"]);
+
+	take_turn(&mut game, "Alice discards r1 (slot 1)");
+	take_turn(&mut game, "Bob clues 5 to Alice (slot 5)");
+	ex_asserts::has_possible(&game, Some(Player::Alice), Player::Alice, 5, &["r5"]);
+}
+
+#[test]
+fn it_touches_dual_colour_suits_with_both_colours() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "y3", "g3", "b3", "b4"],
+	], TestOptions {
+		starting: Player::Bob,
+		variant: "Dual-Color (5 Suits)",
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Bob clues red to Alice (slot 5)");
+	ex_asserts::has_possible(&game, Some(Player::Alice), Player::Alice, 5, &["r1", "r2", "r3", "r4", "r5", "o1", "o2", "o3", "o4", "o5"]);
+
+	take_turn(&mut game, "Alice discards r1 (slot 1)");
+	take_turn(&mut game, "Bob clues yellow to Alice (slot 5)");
+	ex_asserts::has_possible(&game, Some(Player::Alice), Player::Alice, 5, &["o1", "o2", "o3", "o4", "o5"]);
+
+	// Only red, yellow, green and blue are clue colours.
+	assert_eq!(game.state.variant.colourable_suits.as_ref().unwrap().len(), 4);
+}
+
+#[test]
+fn it_reads_clue_colours_from_suit_data() {
+	let variants = r#"[{ "id": 2, "name": "Dual-Color (5 Suits)", "suits": ["Red", "Yellow", "Green", "Blue", "Orange D"] }]"#;
+	let suits = r#"[
+		{ "name": "Red", "abbreviation": "R" },
+		{ "name": "Yellow", "abbreviation": "Y" },
+		{ "name": "Green", "abbreviation": "G" },
+		{ "name": "Blue", "abbreviation": "B" },
+		{ "name": "Orange", "id": "Orange D", "abbreviation": "O", "clueColors": ["Red", "Yellow"] }
+	]"#;
+	let variant = VariantManager::from_json(variants, suits).get_variant("Dual-Color (5 Suits)");
+
+	assert_eq!(variant.colourable_suits.as_ref().unwrap(), &["Red", "Yellow", "Green", "Blue"]);
+
+	let orange = Identity { suit_index: 4, rank: 3 };
+	let touched_by = |value| id_touched(orange, &variant, &BaseClue { kind: ClueKind::COLOUR, value });
+	assert_eq!((0..4).map(touched_by).collect::<Vec<_>>(), [true, true, false, false]);
+}
+
+#[test]
+fn it_touches_deceptive_ones_with_other_ranks() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "y3", "g3", "b3", "b4"],
+	], TestOptions {
+		starting: Player::Bob,
+		variant: "Deceptive-Ones (5 Suits)",
+		..TestOptions::default()
+	});
+
+	// Red and purple 1s are touched by 2.
+	take_turn(&mut game, "Bob clues 2 to Alice (slot 5)");
+	ex_asserts::has_possible(&game, Some(Player::Alice), Player::Alice, 5, &["r1", "p1", "r2", "y2", "g2", "b2", "p2"]);
+
+	let variant = &game.state.variant;
+	let touching_rank = |suit_index| (1..=5).find(|&value| id_touched(Identity { suit_index, rank: 1 }, variant, &BaseClue { kind: ClueKind::RANK, value })).unwrap();
+	assert_eq!((0..5).map(touching_rank).collect::<Vec<_>>(), vec![2, 3, 4, 5, 2]);
+}
+
+#[test]
+fn it_skips_the_special_rank_for_deceptive_threes() {
+	let variant = Variant::new(2, "Deceptive-Threes", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts { special_rank: Some(3), deceptive_s: Some(true), ..VariantOpts::default() });
+
+	let touching_rank = |suit_index| (1..=5).find(|&value| id_touched(Identity { suit_index, rank: 3 }, &variant, &BaseClue { kind: ClueKind::RANK, value })).unwrap();
+	assert_eq!((0..5).map(touching_rank).collect::<Vec<_>>(), vec![1, 2, 4, 5, 1]);
+}
@@ -1,13 +1,16 @@
 use fraction::{ConstZero,ConstOne};
-use rust_bot::basics::action::PerformAction;
+use rust_bot::basics::action::{Action, PerformAction};
+use rust_bot::basics::card::Identifiable;
+use rust_bot::basics::clue::ClueKind;
 use std::sync::Arc;
+use std::time::Duration;
 
-use rust_bot::basics::{endgame::EndgameSolver, game::Game};
+use rust_bot::basics::{endgame::{EndgameSolver, SolverError}, game::Game, state::StackDirection};
 use rust_bot::reactor::Reactor;
 
 type Frac = fraction::Fraction;
 
-use crate::util::{fully_known, Player, TestOptions};
+use crate::util::{fully_known, pre_clue, take_turn, Player, TestClue, TestOptions};
 
 pub mod util;
 pub mod ex_asserts;
@@ -43,7 +46,7 @@ fn it_clues_to_start_b45_endgame() {
 
 	assert_eq!(game.state.cards_left, 1);
 
-	match EndgameSolver::new(true).solve_game(&game) {
+	match EndgameSolver::new(true).solve_game_default(&game) {
 		Err(msg) => panic!("Game should be winnable! {msg}"),
 		Ok((perform, winrate)) => {
 			assert_eq!(winrate, Frac::ONE);
@@ -52,6 +55,38 @@ fn it_clues_to_start_b45_endgame() {
 	}
 }
 
+#[test]
+fn it_times_out_with_a_tiny_deadline() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx"],
+		&["b4", "y1", "g1", "b5"],
+		&["g1", "b1", "b1", "r5"],
+		&["b4", "p1", "p1", "r1"],
+	], TestOptions {
+		play_stacks: Some(&[4, 4, 5, 3, 5]),
+		discarded: &[
+			"r2", "r3",
+			"y2", "y3",
+			"g2", "g3", "g4",
+			"b2", "b3",
+			"p2", "p3", "p4"
+		],	// Missing: r1, y1, r4, y4
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Alice, 1, "y5");
+
+			fully_known(game, Player::Bob, 1, "b4");
+			fully_known(game, Player::Bob, 4, "b5");
+
+			fully_known(game, Player::Cathy, 4, "r5");
+
+			fully_known(game, Player::Donald, 1, "b4");
+		}),
+		..TestOptions::default()
+	});
+
+	assert_eq!(EndgameSolver::new(true).solve_game(&game, Duration::ZERO), Err(SolverError::Timeout));
+}
+
 #[test]
 fn it_clues_to_start_endgame_on_a_double_player() {
 	let game = util::setup(Arc::new(Reactor), &[
@@ -80,7 +115,7 @@ fn it_clues_to_start_endgame_on_a_double_player() {
 
 	assert_eq!(game.state.cards_left, 1);
 
-	match EndgameSolver::new(true).solve_game(&game) {
+	match EndgameSolver::new(true).solve_game_default(&game) {
 		Err(msg) => panic!("Game should be winnable! {msg}"),
 		Ok((perform, winrate)) => {
 			assert_eq!(winrate, Frac::ONE);
@@ -118,7 +153,7 @@ fn it_plays_to_start_simple_endgame() {
 
 	assert_eq!(game.state.cards_left, 1);
 
-	match EndgameSolver::new(true).solve_game(&game) {
+	match EndgameSolver::new(true).solve_game_default(&game) {
 		Err(msg) => panic!("Game should be winnable! {msg}"),
 		Ok((perform, winrate)) => {
 			assert_eq!(winrate, Frac::ONE);
@@ -156,7 +191,7 @@ fn it_plays_to_start_endgame_when_other_has_dupes() {
 
 	assert_eq!(game.state.cards_left, 1);
 
-	match EndgameSolver::new(true).solve_game(&game) {
+	match EndgameSolver::new(true).solve_game_default(&game) {
 		Err(msg) => panic!("Game should be winnable! {msg}"),
 		Ok((perform, winrate)) => {
 			assert_eq!(winrate, Frac::ONE);
@@ -202,7 +237,7 @@ fn it_plays_to_start_a_complex_endgame_where_all_cards_are_seen() {
 	// Alice plays p3 (1 left), Bob stalls, Cathy stalls
 	// Alice plays p4 (0 left), Bob plays p5, Cathy plays g5
 	// Alice plays r5.
-	match EndgameSolver::new(true).solve_game(&game) {
+	match EndgameSolver::new(true).solve_game_default(&game) {
 		Err(msg) => panic!("Game should be winnable! {msg}"),
 		Ok((perform, winrate)) => {
 			assert_eq!(winrate, Frac::ONE);
@@ -240,7 +275,8 @@ fn it_calculates_basic_winrate_correctly() {
 
 	assert_eq!(game.state.cards_left, 2);
 
-	match EndgameSolver::new(false).solve_game(&game) {
+	// Enumerating every arrangement can exceed the default time limit in unoptimized builds.
+	match EndgameSolver::new(false).solve_game(&game, Duration::from_secs(10)) {
 		Err(msg) => panic!("Game should be winnable! {msg}"),
 		Ok((perform, winrate)) => {
 			// We win if Bob draws y5, and lose if Bob doesn't. There are 6 locations that y5 could be.
@@ -250,3 +286,403 @@ fn it_calculates_basic_winrate_correctly() {
 		}
 	}
 }
+
+#[test]
+fn it_returns_the_full_winning_line() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "r1", "g1", "p5", "p2"],
+		&["g1", "b1", "r4", "r1", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[3, 5, 4, 5, 1]),
+		discarded: &[
+			"r2", "r3",
+			"y2", "y3", "y4",
+			"g2", "g3", "g4",
+			"b2", "b3", "b4",
+			"p2", "p3"
+		],	// Missing: y1, y1, p1, p1, p4
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Alice, 2, "p3");
+			fully_known(game, Player::Alice, 3, "p4");
+			fully_known(game, Player::Alice, 4, "r5");
+			fully_known(game, Player::Alice, 5, "r4");
+
+			fully_known(game, Player::Bob, 4, "p5");
+			fully_known(game, Player::Bob, 5, "p2");
+
+			fully_known(game, Player::Cathy, 5, "g5");
+		}),
+		..TestOptions::default()
+	});
+
+	let play = |player: Player, slot: usize| PerformAction::Play { target: game.state.hands[player as usize][slot - 1] };
+
+	// Alice plays r4 (3 left), Bob plays p2 (2 left), Cathy stalls
+	// Alice plays p3 (1 left), Bob stalls, Cathy stalls
+	// Alice plays p4 (0 left), Bob plays p5, Cathy plays g5
+	// Alice plays r5.
+	match EndgameSolver::new(true).solve_game_line(&game, Duration::from_secs(10)) {
+		Err(msg) => panic!("Game should be winnable! {msg}"),
+		Ok((line, winrate)) => {
+			assert_eq!(winrate, Frac::ONE);
+			assert_eq!(line, vec![
+				play(Player::Alice, 5),
+				play(Player::Bob, 5),
+				PerformAction::Rank { target: Player::Alice as usize, value: 1 },
+				play(Player::Alice, 2),
+				PerformAction::Rank { target: Player::Alice as usize, value: 1 },
+				PerformAction::Colour { target: Player::Alice as usize, value: 4 },
+				play(Player::Alice, 3),
+				play(Player::Bob, 4),
+				play(Player::Cathy, 5),
+				play(Player::Alice, 4),
+			]);
+		}
+	}
+}
+
+#[test]
+fn it_only_regains_half_a_clue_in_clue_starved() {
+	let setup = |variant| util::setup(Arc::new(Reactor), &[
+		&["b1", "p1", "y2", "g2", "b2"],
+		&["r3", "r4", "r5", "y1", "g1"],
+	], TestOptions {
+		play_stacks: Some(&[2, 5, 5, 5, 5]),
+		discarded: &[
+			"r1", "r1", "r2", "r3", "r4",
+			"y3", "y4",
+			"g3", "g4",
+			"b3", "b4",
+			"p1", "p2", "p3", "p4"
+		],	// Missing: y1, g1, b1
+		clue_tokens: Frac::ZERO,
+		variant,
+		init: Box::new(|game: &mut Game| {
+			for (slot, id) in ["b1", "p1", "y2", "g2", "b2"].iter().enumerate() {
+				fully_known(game, Player::Alice, slot + 1, id);
+			}
+
+			fully_known(game, Player::Bob, 1, "r3");
+			fully_known(game, Player::Bob, 2, "r4");
+			fully_known(game, Player::Bob, 3, "r5");
+		}),
+		..TestOptions::default()
+	});
+
+	// Alice discards (2 left), Bob plays r3 (1 left)
+	// Alice stalls with the regained clue, Bob plays r4 (0 left)
+	// Alice discards, Bob plays r5.
+	let game = setup("No Variant");
+	assert_eq!(game.state.cards_left, 3);
+	assert!(EndgameSolver::new(false).solve_game(&game, Duration::from_secs(10)).is_ok());
+
+	// In Clue Starved, Alice only has half a clue after discarding, so she can't stall.
+	let game = setup("Clue Starved (5 Suits)");
+	assert!(EndgameSolver::new(false).solve_game(&game, Duration::from_secs(10)).is_err());
+}
+
+#[test]
+fn it_doesnt_draw_past_the_end_of_the_deck() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "y3", "g3", "b3", "b4"],
+		&["y4", "g4", "b2", "p2", "r2"],
+	], TestOptions::default());
+	game.state.cards_left = 0;
+	game.state.endgame_turns = Some(3);
+
+	let r1 = game.state.hands[Player::Bob as usize][0];
+	let hypo_game = game.simulate_action(&Action::play(Player::Bob as usize, r1, 0, 1), None);
+
+	assert_eq!(hypo_game.state.hands[Player::Bob as usize].len(), 4);
+	assert_eq!(hypo_game.state.endgame_turns, Some(2));
+
+	// A draw with no cards left is ignored.
+	let mut drawn_game = hypo_game.clone();
+	drawn_game.handle_action(&Action::draw(Player::Bob as usize, hypo_game.state.card_order, 1, 1));
+	assert_eq!(drawn_game.state.hands[Player::Bob as usize].len(), 4);
+	assert_eq!(drawn_game.state.cards_left, 0);
+}
+
+#[test]
+fn it_stalls_to_pass_the_last_draw() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r4", "r5", "g2", "g3", "g4"],
+		&["b1", "b2", "b3", "b4", "p1"],
+	], TestOptions {
+		play_stacks: Some(&[3, 5, 5, 5, 5]),
+		discarded: &[
+			"r1", "r1", "r2", "r3", "r4",
+			"y1", "g1", "b1",
+			"p1", "p2", "p3"
+		],	// Missing: p4
+		clue_tokens: Frac::from(2),
+		init: Box::new(|game: &mut Game| {
+			for (slot, short) in ["y1", "y2", "y3", "y4", "g1"].iter().enumerate() {
+				fully_known(game, Player::Alice, slot + 1, short);
+			}
+			for (slot, short) in ["r4", "r5", "g2", "g3", "g4"].iter().enumerate() {
+				fully_known(game, Player::Bob, slot + 1, short);
+			}
+			for (slot, short) in ["b1", "b2", "b3", "b4", "p1"].iter().enumerate() {
+				fully_known(game, Player::Cathy, slot + 1, short);
+			}
+		}),
+		..TestOptions::default()
+	});
+
+	assert_eq!(game.state.cards_left, 1);
+
+	// If Alice discards, she draws the last card and Bob only has time to play r4.
+	// Alice should stall so that Bob draws the last card after playing r4, giving him a final turn for r5.
+	match EndgameSolver::new(false).solve_game_default(&game) {
+		Err(msg) => panic!("Game should be winnable! {msg}"),
+		Ok((perform, winrate)) => {
+			assert_eq!(winrate, Frac::ONE);
+			assert!(perform.is_clue());
+		}
+	}
+}
+
+#[test]
+fn it_terminates_a_lost_game_before_striking_out() {
+	let setup = |strikes: u8| util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g2", "g3", "g4", "y2", "y3"],
+		&["b2", "b3", "b4", "p2", "p3"],
+	], TestOptions {
+		play_stacks: Some(&[3, 5, 5, 5, 5]),
+		discarded: &[
+			"r1", "r1", "r2", "r3",
+			"y1", "y1", "y4",
+			"g1", "g1",
+			"b1", "b1",
+			"p1"
+		],	// Missing: r4, r4, r5, p1, p4
+		clue_tokens: Frac::ZERO,
+		strikes,
+		init: Box::new(|game: &mut Game| {
+			game.state.endgame_turns = Some(1);
+			game.auto_terminate = true;
+		}),
+		..TestOptions::default()
+	});
+
+	// Only Alice has a turn left, so r4 and r5 can't both be played.
+	// She can't clue, doesn't know any card is playable and any card could be the critical r5.
+	let game = setup(2);
+	assert_eq!(game.take_action(), PerformAction::Terminate { target: Player::Alice as usize, value: 0 });
+
+	// With a strike to spare, she can still try to play.
+	let game = setup(1);
+	assert!(!matches!(game.take_action(), PerformAction::Terminate { .. }));
+}
+
+#[test]
+fn it_models_draws_into_other_hands() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r5", "g1", "g1", "g2", "g3"],
+		&["g4", "b1", "b1", "b2", "b3"],
+	], TestOptions {
+		play_stacks: Some(&[3, 5, 5, 5, 5]),
+		discarded: &[
+			"r1", "r1", "r2", "r3", "r4",
+			"b4",
+			"p1", "p1", "p2", "p3"
+		],	// Missing: r4, p4
+		init: Box::new(|game: &mut Game| {
+			for (slot, short) in ["y1", "y1", "y2", "y3", "y4"].iter().enumerate() {
+				fully_known(game, Player::Alice, slot + 1, short);
+			}
+			for (slot, short) in ["r5", "g1", "g1", "g2", "g3"].iter().enumerate() {
+				fully_known(game, Player::Bob, slot + 1, short);
+			}
+			for (slot, short) in ["g4", "b1", "b1", "b2", "b3"].iter().enumerate() {
+				fully_known(game, Player::Cathy, slot + 1, short);
+			}
+		}),
+		..TestOptions::default()
+	});
+
+	assert_eq!(game.state.cards_left, 2);
+
+	// Alice can't discard at 8 clues, so she must stall and let someone else draw r4 or p4.
+	// r4 must be drawn first, so that there is still time to play it and then r5.
+	match EndgameSolver::new(false).solve_game_default(&game) {
+		Err(msg) => panic!("Game should be winnable! {msg}"),
+		Ok((perform, winrate)) => {
+			assert!(perform.is_clue());
+			assert_eq!(winrate, Frac::new(1_u64, 2_u64));
+		}
+	}
+}
+
+#[test]
+fn it_keeps_stalling_after_many_clues() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r4", "y4", "g1", "g1", "g2"],
+		&["g3", "g4", "b1", "b1", "b2"],
+	], TestOptions {
+		play_stacks: Some(&[3, 5, 5, 5, 5]),
+		discarded: &[
+			"r1", "r1", "r2", "r3", "r4",
+			"b3", "b4",
+			"p1", "p1", "p2", "p3"
+		],	// Missing: p4
+		starting: Player::Cathy,
+		init: Box::new(|game: &mut Game| {
+			for (slot, short) in ["r5", "y1", "y1", "y2", "y3"].iter().enumerate() {
+				fully_known(game, Player::Alice, slot + 1, short);
+			}
+			for (slot, short) in ["y4", "g1", "g1", "g2"].iter().enumerate() {
+				fully_known(game, Player::Bob, slot + 2, short);
+			}
+			for (slot, short) in ["g3", "g4", "b1", "b1", "b2"].iter().enumerate() {
+				fully_known(game, Player::Cathy, slot + 1, short);
+			}
+		}),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Cathy clues 1 to Alice (slots 2,3)");
+	take_turn(&mut game, "Alice clues yellow to Bob");
+	take_turn(&mut game, "Bob clues green to Cathy");
+	take_turn(&mut game, "Cathy clues 5 to Alice (slot 1)");
+
+	assert_eq!(game.state.cards_left, 1);
+
+	// After 4 consecutive clues, Alice still needs to clue Bob's r4 so that he plays it and draws the last card.
+	match EndgameSolver::new(false).solve_game_default(&game) {
+		Err(msg) => panic!("Game should be winnable! {msg}"),
+		Ok((perform, winrate)) => {
+			assert!(perform.is_clue());
+			assert_eq!(winrate, Frac::ONE);
+		}
+	}
+}
+
+#[test]
+fn it_solves_a_known_deck_exactly() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "r1", "g1", "y1", "r2"],
+		&["g1", "b1", "r1", "y1", "p1"],
+	], TestOptions {
+		play_stacks: Some(&[5, 5, 5, 5, 3]),
+		discarded: &["r3", "r4", "y3", "y4", "g3", "g4", "b3", "b4", "p1"],
+		clue_tokens: Frac::ZERO,
+		..TestOptions::default()
+	});
+
+	assert_eq!(game.state.cards_left, 3);
+
+	// Alice holds g2, b2, p2, p3, y2 and p5 is on the bottom of the deck
+	let alice = ["g2", "b2", "p2", "p3", "y2"].map(|short| game.state.expand_short(short));
+	let mut deck = (0..game.state.card_order).map(|order| game.state.deck[order].id().unwrap_or_else(|| {
+		let slot = game.state.hands[Player::Alice as usize].iter().position(|&o| o == order).unwrap();
+		alice[slot]
+	})).collect::<Vec<_>>();
+	deck.extend(["p4", "p4", "p5"].map(|short| game.state.expand_short(short)));
+
+	let mut solver = EndgameSolver::new(false);
+	assert_eq!(solver.solve_game_known_deck(&game, &deck, Duration::ZERO), Err(SolverError::Timeout));
+
+	// The timeout shouldn't have been cached as a loss
+	assert_eq!(solver.solve_game_known_deck(&game, &deck, Duration::from_secs(60)), Ok(true));
+}
+
+#[test]
+fn it_saves_a_critical_instead_of_holding_in_the_endgame() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g2", "b2", "p2", "r3", "b5"],
+		&["g3", "b3", "p3", "r4", "g4"],
+	], TestOptions {
+		play_stacks: Some(&[3, 3, 3, 3, 3]),
+		discarded: &["r1", "y1", "y1", "g1", "g1", "b1", "b1", "p1", "p1", "r2", "y2"],
+		clue_tokens: Frac::from(7),
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Alice, 5, "r1");
+
+			for (slot, short) in ["g2", "b2", "p2", "r3"].into_iter().enumerate() {
+				fully_known(game, Player::Bob, slot + 1, short);
+			}
+			pre_clue(game, Player::Bob, 5, &[TestClue { kind: ClueKind::RANK, value: 5, giver: Player::Alice }]);
+			for (slot, short) in ["g3", "b3", "p3", "r4", "g4"].into_iter().enumerate() {
+				fully_known(game, Player::Cathy, slot + 1, short);
+			}
+		}),
+		..TestOptions::default()
+	});
+
+	let blue_to_bob = PerformAction::Colour { target: Player::Bob as usize, value: 3 };
+	assert_eq!(Reactor::action_values(&game)[0].0, blue_to_bob);
+
+	// Filling in Bob's b5 can't get anything played before b4 is found, but it is still worth more than discarding her known r1.
+	assert_eq!(game.take_action(), blue_to_bob);
+}
+
+#[test]
+fn it_discards_trash_instead_of_a_bad_endgame_clue() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g2", "b2", "p2", "r3", "y3"],
+		&["g3", "b3", "p3", "r4", "g4"],
+	], TestOptions {
+		play_stacks: Some(&[3, 3, 3, 3, 3]),
+		discarded: &["r1", "y1", "y1", "g1", "g1", "b1", "b1", "p1", "p1", "r2", "y2"],
+		clue_tokens: Frac::from(7),
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Alice, 5, "r1");
+
+			for (slot, short) in ["g2", "b2", "p2", "r3", "y3"].into_iter().enumerate() {
+				fully_known(game, Player::Bob, slot + 1, short);
+			}
+			for (slot, short) in ["g3", "b3", "p3", "r4", "g4"].into_iter().enumerate() {
+				fully_known(game, Player::Cathy, slot + 1, short);
+			}
+		}),
+		..TestOptions::default()
+	});
+
+	assert!(game.state.in_endgame());
+
+	// Every clue only retouches known cards, so Alice should discard her known r1 rather than spend a clue.
+	assert_eq!(game.take_action(), PerformAction::Discard { target: game.state.hands[Player::Alice as usize][4] });
+}
+
+#[test]
+fn it_counts_a_5_starting_a_down_stack_as_one_point() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r2", "y2", "g2", "p2", "p5"],
+		&["r3", "y3", "g3", "p3", "y4"],
+	], TestOptions {
+		play_stacks: Some(&[5, 5, 5, 0, 4]),
+		discarded: &["r4", "b2", "b2", "b4", "b4"],	// Missing: g4, p4, b1, b3, b3
+		variant: "Up or Down (5 Suits)",
+		init: Box::new(|game: &mut Game| {
+			// Every stack except blue has been built upwards
+			game.state.stack_direction = vec![StackDirection::Up, StackDirection::Up, StackDirection::Up, StackDirection::Undecided, StackDirection::Up];
+
+			fully_known(game, Player::Alice, 1, "b5");
+			fully_known(game, Player::Bob, 5, "p5");
+		}),
+		..TestOptions::default()
+	});
+
+	// Blue can only reach a height of 1 in either direction, so playing b5 and p5 finishes the game.
+	assert_eq!(game.state.max_score(), 21);
+
+	match EndgameSolver::new(true).solve_game_default(&game) {
+		Err(msg) => panic!("Game should be winnable! {msg}"),
+		Ok((perform, winrate)) => {
+			assert_eq!(winrate, Frac::ONE);
+			assert_eq!(perform, PerformAction::Play { target: game.state.hands[Player::Alice as usize][0] });
+		}
+	}
+}
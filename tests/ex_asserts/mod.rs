@@ -1,3 +1,4 @@
+use rust_bot::basics::card::CardStatus;
 use rust_bot::basics::game::Game;
 
 use crate::util::Player;
@@ -8,7 +9,7 @@ pub fn has_inferences(game: &Game, player_index: Option<Player>, target: Player,
 	let player = player_index.map(|i| &players[i as usize]).unwrap_or(common);
 	let thought = &player.thoughts[*order];
 
-	assert!(thought.inferred.len() == inferences.len() && inferences.iter().all(|&i| thought.inferred.contains(state.expand_short(i))),
+	assert!(thought.inferred == state.expand_shorts(inferences),
 		"Differing inferences. Expected {}, got {}", inferences.join(","), player.str_infs(state, *order));
 }
 
@@ -18,6 +19,22 @@ pub fn has_possible(game: &Game, player_index: Option<Player>, target: Player, s
 	let player = player_index.map(|i| &players[i as usize]).unwrap_or(common);
 	let thought = &player.thoughts[*order];
 
-	assert!(thought.possible.len() == possible.len() && possible.iter().all(|&i| thought.possible.contains(state.expand_short(i))),
+	assert!(thought.possible == state.expand_shorts(possible),
 		"Differing possibilities. Expected {}, got {}", possible.join(","), player.str_poss(state, *order));
 }
+
+pub fn has_waiting(game: &Game, reacter: Player, receiver: Player, focus_slot: usize) {
+	let Game { common, state, .. } = game;
+	let wc = common.waiting.as_ref().unwrap_or_else(|| panic!("No waiting connection"));
+
+	assert!(wc.reacter == reacter as usize && wc.receiver == receiver as usize && wc.focus_slot == focus_slot,
+		"Differing waiting connection. Expected reacter {}, receiver {}, focus slot {focus_slot}, got reacter {}, receiver {}, focus slot {}",
+		state.player_names[reacter as usize], state.player_names[receiver as usize], state.player_names[wc.reacter], state.player_names[wc.receiver], wc.focus_slot);
+}
+
+pub fn has_status(game: &Game, target: Player, slot: usize, status: CardStatus) {
+	let Game { state, meta, .. } = game;
+	let order = state.hands[target as usize].get(slot - 1).unwrap_or_else(|| panic!("Slot {slot} doesn't exist"));
+
+	assert!(meta[*order].status == status, "Differing status. Expected {status:?}, got {:?}", meta[*order].status);
+}
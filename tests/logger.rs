@@ -0,0 +1,57 @@
+use colored::Colorize;
+use log::Level;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+
+use rust_bot::logger;
+
+#[test]
+fn it_writes_json_log_lines() {
+	let path = std::env::temp_dir().join(format!("rust_bot_log_{}.jsonl", std::process::id()));
+	let table_path = logger::table_path(path.to_str().unwrap(), 1234);
+	let _ = fs::remove_file(&path);
+	let _ = fs::remove_file(&table_path);
+
+	logger::init_with(path.to_str(), true).unwrap();
+	log::info!("{}", "Alice clues \"red\" to Bob".green());
+
+	logger::set_context(Some(1234), Some(3));
+	log::info!(action = "Alice clues red to Bob", value = 0.5f32, interp = "Reactor(Clue(RefPlay))", slot = "05", note = "1e3", count = 3usize; "Alice clues red to Bob: 0.5");
+	logger::set_context(None, None);
+	log::logger().flush();
+
+	let contents = fs::read_to_string(&path).unwrap();
+	let table_contents = fs::read_to_string(&table_path).unwrap();
+	let _ = fs::remove_file(&path);
+	let _ = fs::remove_file(&table_path);
+
+	let json: Value = serde_json::from_str(contents.lines().last().unwrap()).unwrap();
+
+	// Colours are stripped from the file
+	assert_eq!(json["level"], "INFO");
+	assert_eq!(json["message"], "Alice clues \"red\" to Bob");
+	assert!(json.get("turn").is_none());
+
+	// Lines logged while handling a table go to its own file, along with the turn and structured fields
+	let json: Value = serde_json::from_str(table_contents.lines().last().unwrap()).unwrap();
+	assert_eq!(json["turn"], 3);
+	assert_eq!(json["action"], "Alice clues red to Bob");
+	assert_eq!(json["value"], 0.5);
+	assert_eq!(json["interp"], "Reactor(Clue(RefPlay))");
+	assert_eq!(json["count"], 3);
+
+	// Strings that look like numbers stay strings
+	assert_eq!(json["slot"], "05");
+	assert_eq!(json["note"], "1e3");
+	assert!(!contents.contains("0.5"));
+
+	let json: Value = serde_json::from_str(&logger::json_line(Level::Warn, "multi\nline", None, Map::new())).unwrap();
+	assert_eq!(json["message"], "multi\nline");
+}
+
+#[test]
+fn it_names_table_log_files_after_the_main_one() {
+	assert_eq!(logger::table_path("logs/bot.log", 1234), PathBuf::from("logs/bot.1234.log"));
+	assert_eq!(logger::table_path("bot", 1234), PathBuf::from("bot.1234"));
+}
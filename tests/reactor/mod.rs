@@ -1,10 +1,11 @@
 use fraction::Fraction;
 use rust_bot::basics::action::{Action, ClueAction, PerformAction};
 use rust_bot::basics::card::CardStatus;
-use rust_bot::basics::clue::BaseClue;
+use rust_bot::basics::identity_set::IdentitySet;
+use rust_bot::basics::clue::{BaseClue, Clue};
 use rust_bot::basics::{clue::ClueKind};
-use rust_bot::basics::game::{Game, SimOpts};
-use rust_bot::reactor::{Reactor};
+use rust_bot::basics::game::{Convention, Game, SimOpts};
+use rust_bot::reactor::{ClueInterp, CluePreview, ClueSafety, Reactor};
 use std::sync::Arc;
 
 use crate::ex_asserts;
@@ -65,7 +66,7 @@ fn it_elims_from_focus() {
 
 	// Alice's slot 2 should be known trash.
 	let hand = &game.state.hands[Player::Alice as usize];
-	assert!(game.common.thinks_trash(&game.frame(), Player::Alice as usize).contains(&hand[1]));
+	assert!(game.frame().trash_orders(&game.common, Player::Alice as usize).contains(&hand[1]));
 }
 
 #[test]
@@ -113,7 +114,7 @@ fn it_understands_a_reverse_reactive_clue() {
 
 	take_turn(&mut game, "Bob plays b1, drawing y3");
 
-	assert!(game.common.obvious_playables(&game.frame(), Player::Cathy as usize).contains(&game.state.hands[Player::Cathy as usize][1]));
+	assert!(game.frame().playable_orders(&game.common, Player::Cathy as usize).contains(&game.state.hands[Player::Cathy as usize][1]));
 }
 
 #[test]
@@ -152,6 +153,31 @@ fn it_doesnt_give_a_bad_reverse_reactive_clue() {
 	assert!(result < 8.0);
 }
 
+#[test]
+fn it_doesnt_give_a_reverse_reactive_clue_discarding_a_critical() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "g3", "y4", "b1", "p4"],
+		&["g4", "b4", "r4", "y5", "p3"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Bob, 4, "b1");
+		}),
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	// Purple to Bob targets his r1, but Cathy would react by discarding y5.
+	let clue = ClueAction {
+		giver: Player::Alice as usize,
+		target: Player::Bob as usize,
+		list: vec![game.state.hands[Player::Bob as usize][4]],
+		clue: BaseClue { kind: ClueKind::COLOUR, value: Colour::Purple as usize }
+	};
+
+	assert_eq!(Reactor::eval_action(&game, &Action::Clue(clue)), -100.0);
+}
+
 #[test]
 fn it_understands_targeting_dupes() {
 	let mut game = util::setup(Arc::new(Reactor), &[
@@ -300,7 +326,9 @@ fn it_discards_zcs() {
 	// Taking the team down to 0 clues - Bob writes ZCS on y4, Cathy writes ZCS on p4.
 	take_turn(&mut game, "Alice clues 5 to Cathy");
 
-	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::ZeroClueChop);
+	let y4 = game.state.hands[Player::Bob as usize][0];
+	assert_eq!(game.meta[y4].status, CardStatus::ZeroClueChop);
+	assert!(game.notes[&(y4 as u64)].last().starts_with("[zcs]"));
 
 	take_turn(&mut game, "Bob plays g1, drawing b1");
 
@@ -310,5 +338,408 @@ fn it_discards_zcs() {
 	take_turn(&mut game, "Alice discards p4 (slot 1)");
 
 	// Bob's chop should be slot 2.
-	assert_eq!(Reactor::chop(&game, Player::Bob as usize), Some(&game.state.hands[Player::Bob as usize][1]));
+	assert_eq!(Reactor::chop(&game, Player::Bob as usize), Some(game.state.hands[Player::Bob as usize][1]));
+}
+
+#[test]
+fn it_explains_a_ref_play_clue() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g4", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	let clue = PerformAction::Colour { target: Player::Bob as usize, value: Colour::Green as usize };
+	assert_eq!(Reactor::explain_action(&game, &clue), "Giving green to Bob as a ref play, calling Bob's slot 1 to play.");
+}
+
+#[test]
+fn it_explains_a_reactive_play() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		starting: Player::Cathy,
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Cathy clues 2 to Bob");
+
+	let action = game.take_action();
+	assert_eq!(Reactor::explain_action(&game, &action), "Playing slot 1, called to play by Cathy.");
+}
+
+#[test]
+fn it_previews_a_ref_play_target() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b4", "g3", "r4", "y3", "p4"],
+		&["b4", "r1", "g4", "y4", "p4"],
+	], TestOptions::default());
+
+	// Green touches Cathy's g4, which refers to her r1 on its left.
+	let hand = &game.state.hands[Player::Cathy as usize];
+	let preview = Reactor::clue_preview(&game, &Clue { kind: ClueKind::COLOUR, value: Colour::Green as usize, target: Player::Cathy as usize });
+	assert_eq!(preview, CluePreview { newly_touched: vec![hand[2]], focus: Some(hand[2]), ref_target: Some(hand[1]), interp: ClueInterp::RefPlay });
+}
+
+#[test]
+fn it_previews_a_ref_discard_target() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b4", "g3", "r4", "y3", "p4"],
+		&["p4", "r3", "b4", "y4", "g4"],
+	], TestOptions::default());
+
+	// 3 touches Cathy's r3, which refers to her b4 on its right.
+	let hand = &game.state.hands[Player::Cathy as usize];
+	let preview = Reactor::clue_preview(&game, &Clue { kind: ClueKind::RANK, value: 3, target: Player::Cathy as usize });
+	assert_eq!(preview, CluePreview { newly_touched: vec![hand[1]], focus: Some(hand[1]), ref_target: Some(hand[2]), interp: ClueInterp::RefDiscard });
+}
+
+#[test]
+fn it_finds_a_safe_clue() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g4", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	let action = ClueAction { giver: Player::Alice as usize, target: Player::Bob as usize, list: vec![game.state.hands[Player::Bob as usize][1], game.state.hands[Player::Bob as usize][4]], clue: BaseClue { kind: ClueKind::COLOUR, value: Colour::Green as usize } };
+	assert_eq!(Reactor::clue_safety(&game, &action), ClueSafety::Safe);
+}
+
+#[test]
+fn it_finds_a_bad_touch_clue() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g4", "b5", "p2", "g2", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		init: Box::new(|game: &mut Game| {
+			// Bob's g2 is already clued.
+			pre_clue(game, Player::Bob, 2, &[TestClue { kind: ClueKind::RANK, value: 2, giver: Player::Cathy }]);
+		}),
+		..TestOptions::default()
+	});
+
+	// Green touches Cathy's g2, which duplicates Bob's clued g2.
+	let cathy_g2 = game.state.hands[Player::Cathy as usize][3];
+	let action = ClueAction { giver: Player::Alice as usize, target: Player::Cathy as usize, list: vec![game.state.hands[Player::Cathy as usize][0], cathy_g2, game.state.hands[Player::Cathy as usize][4]], clue: BaseClue { kind: ClueKind::COLOUR, value: Colour::Green as usize } };
+	assert!(matches!(Reactor::clue_safety(&game, &action), ClueSafety::BadTouch(orders) if orders.contains(&cathy_g2)));
+}
+
+#[test]
+fn it_finds_a_clue_that_locks() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["p4", "p2", "p2", "b5", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	// Cathy is next after Bob, so a 4 from Bob is stable and touches her lock card.
+	let action = ClueAction { giver: Player::Bob as usize, target: Player::Cathy as usize, list: vec![game.state.hands[Player::Cathy as usize][0], game.state.hands[Player::Cathy as usize][4]], clue: BaseClue { kind: ClueKind::RANK, value: 4 } };
+	assert_eq!(Reactor::clue_safety(&game, &action), ClueSafety::CausesLock);
+}
+
+#[test]
+fn it_scores_an_illegal_clue_uniformly() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "g2", "y2", "b3", "g5"],
+		&["b4", "r3", "p4", "y4", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(5),
+		init: Box::new(|game: &mut Game| {
+			// Bob's slot 1 is called to play, inferred as r1 or r2
+			pre_clue(game, Player::Bob, 1, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Red as usize, giver: Player::Cathy }]);
+			let order = game.state.hands[Player::Bob as usize][0];
+			game.common.thoughts[order].inferred = game.state.expand_shorts(&["r1", "r2"]);
+			game.meta[order].status = CardStatus::CalledToPlay;
+		}),
+		..TestOptions::default()
+	});
+
+	// Purple to Cathy has no legal interpretation, so it scores the same whether simulated or read from the cache.
+	let action = ClueAction { giver: Player::Alice as usize, target: Player::Cathy as usize, list: vec![game.state.hands[Player::Cathy as usize][2]], clue: BaseClue { kind: ClueKind::COLOUR, value: Colour::Purple as usize } };
+	assert_eq!(Reactor::clue_safety(&game, &action), ClueSafety::Illegal);
+	assert_eq!(Reactor::clue_value(&game, &action), -100.0);
+	assert_eq!(Reactor::eval_action(&game, &Action::Clue(action)), -100.0);
+
+	let interp: ClueInterp = serde_json::from_str(&serde_json::to_string(&ClueInterp::Illegal).unwrap()).unwrap();
+	assert_eq!(interp, ClueInterp::Illegal);
+}
+
+#[test]
+fn it_doesnt_reclue_known_playables() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["p1", "p1", "y1", "p4", "r5"],
+		&["y1", "b1", "r1", "g1", "p5"],
+		&["g1", "b1", "r4", "r1", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[4, 5, 4, 5, 3]),
+		discarded: &[
+			"r2", "r3",
+			"y2", "y3", "y4",
+			"g2", "g3", "g4",
+			"b2", "b3", "b4",
+			"p2", "p3"
+		],
+		init: Box::new(|game: &mut Game| {
+			pre_clue(game, Player::Alice, 1, &[TestClue { kind: ClueKind::RANK, value: 1, giver: Player::Bob }]);
+			pre_clue(game, Player::Alice, 2, &[TestClue { kind: ClueKind::RANK, value: 1, giver: Player::Bob }]);
+			pre_clue(game, Player::Alice, 3, &[TestClue { kind: ClueKind::RANK, value: 1, giver: Player::Bob }]);
+			fully_known(game, Player::Alice, 4, "p4");
+			fully_known(game, Player::Alice, 5, "r5");
+			fully_known(game, Player::Bob, 5, "p5");
+			fully_known(game, Player::Cathy, 5, "g5");
+		}),
+		..TestOptions::default()
+	});
+
+	// Red only retouches Alice's r5, which she already knows is playable.
+	let clues = Reactor.find_all_clues(&game, Player::Bob as usize);
+	assert!(!clues.contains(&PerformAction::Colour { target: Player::Alice as usize, value: Colour::Red as usize }));
+	assert!(!clues.is_empty());
+}
+
+#[test]
+fn it_fixes_a_called_card_that_became_trash() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g4", "b3", "r4", "y4", "p4"],
+		&["g1", "b1", "y3", "y1", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[4, 0, 0, 1, 0]),
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Alice, 1, "r5");
+
+			// Bob's blue card is queued to play as b2 from good touch, but it is actually b3
+			pre_clue(game, Player::Bob, 2, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Blue as usize, giver: Player::Alice }]);
+			let order = game.state.hands[Player::Bob as usize][1];
+			game.common.thoughts[order].inferred = IdentitySet::single(game.state.expand_short("b2"));
+			game.meta[order].status = CardStatus::CalledToPlay;
+		}),
+		..TestOptions::default()
+	});
+
+	// Alice should fix Bob's blue card with 3 rather than playing r5.
+	assert_eq!(game.take_action(), PerformAction::Rank { target: Player::Bob as usize, value: 3 });
+}
+
+#[test]
+fn it_finds_fixes_for_bad_touched_cards() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g4", "b3", "r4", "y4", "p4"],
+		&["g1", "b1", "y3", "y1", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[0, 0, 0, 1, 0]),
+		init: Box::new(|game: &mut Game| {
+			// Bob's blue card is thought to be b2, but it is actually b3
+			pre_clue(game, Player::Bob, 2, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Blue as usize, giver: Player::Alice }]);
+			let order = game.state.hands[Player::Bob as usize][1];
+			game.common.thoughts[order].inferred = IdentitySet::single(game.state.expand_short("b2"));
+		}),
+		..TestOptions::default()
+	});
+
+	let fixes = Reactor.find_all_fixes(&game, Player::Alice as usize);
+	assert!(fixes.contains(&PerformAction::Rank { target: Player::Bob as usize, value: 3 }));
+	assert!(fixes.iter().all(|fix| matches!(fix, PerformAction::Rank { target, .. } | PerformAction::Colour { target, .. } if *target == Player::Bob as usize)));
+}
+
+#[test]
+fn it_doesnt_play_a_called_card_that_cant_be_playable() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g4", "b4", "r4", "y4", "p4"],
+		&["g1", "b1", "y3", "y1", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[0, 0, 0, 1, 0]),
+		init: Box::new(|game: &mut Game| {
+			// Alice was called to play slot 1, but it has since been revealed as b3
+			fully_known(game, Player::Alice, 1, "b3");
+			let order = game.state.hands[Player::Alice as usize][0];
+			game.meta[order].status = CardStatus::CalledToPlay;
+			game.meta[order].urgent = true;
+			game.common.thoughts[order].old_inferred = Some(game.common.thoughts[order].inferred);
+		}),
+		..TestOptions::default()
+	});
+
+	assert_ne!(game.take_action(), PerformAction::Play { target: game.state.hands[Player::Alice as usize][0] });
+}
+
+#[test]
+fn it_waits_a_turn_before_playing_a_good_touch_queued_card() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g4", "b4", "r4", "y4", "b3"],
+		&["p4", "b1", "y3", "y1", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[0, 0, 0, 0, 3]),
+		starting: Player::Cathy,
+		clue_tokens: Fraction::from(0),
+		init: Box::new(|game: &mut Game| {
+			pre_clue(game, Player::Alice, 1, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Purple as usize, giver: Player::Bob }]);
+			fully_known(game, Player::Cathy, 1, "p4");
+		}),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Cathy plays p4, drawing r1");
+
+	// Alice's purple card is now p5 from good touch, but Bob hasn't had a chance to fix it yet.
+	let purple = game.state.hands[Player::Alice as usize][0];
+	ex_asserts::has_inferences(&game, None, Player::Alice, 1, &["p5"]);
+	assert_ne!(game.take_action(), PerformAction::Play { target: purple });
+
+	take_turn(&mut game, "Alice discards y2 (slot 5)");
+
+	// Once Bob's turn starts, the card is queued to play.
+	assert!(game.meta[purple].queued);
+}
+
+#[test]
+fn it_plays_a_known_playable_over_a_clue() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["y1", "b1", "g1", "p1", "r3"],
+		&["p4", "b5", "p2", "b4", "g4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Alice, 5, "r1");
+		}),
+		..TestOptions::default()
+	});
+
+	// Alice should play r1 first, since the 1 clue to Bob can wait.
+	assert_eq!(game.take_action(), PerformAction::Play { target: game.state.hands[Player::Alice as usize][4] });
+}
+
+#[test]
+fn it_penalises_discards_more_at_low_pace() {
+	let discard_value = |cards_left: usize| {
+		let game = util::setup(Arc::new(Reactor), &[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["g4", "b5", "y4", "p4", "g5"],
+			&["y5", "b4", "p5", "r5", "b4"],
+		], TestOptions {
+			play_stacks: Some(&[3, 3, 3, 3, 3]),
+			clue_tokens: Fraction::from(4),
+			init: Box::new(move |game: &mut Game| {
+				game.state.cards_left = cards_left;
+			}),
+			..TestOptions::default()
+		});
+
+		// Alice discards r4 from slot 5
+		let order = game.state.hands[Player::Alice as usize][4];
+		Reactor::eval_action(&game, &Action::discard(Player::Alice as usize, order, 0, 4, false))
+	};
+
+	// Pace 13 vs pace 3
+	assert!(discard_value(10) < discard_value(20));
+}
+
+#[test]
+fn it_prefers_a_clue_to_a_discard_when_out_of_pace() {
+	let clue_over_discard = |cards_left: usize| {
+		let game = util::setup(Arc::new(Reactor), &[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["g4", "b5", "y4", "p4", "g5"],
+			&["y5", "b4", "p5", "r5", "b4"],
+		], TestOptions {
+			play_stacks: Some(&[3, 3, 3, 3, 3]),
+			clue_tokens: Fraction::from(4),
+			init: Box::new(move |game: &mut Game| {
+				game.state.cards_left = cards_left;
+				// Alice's slot 5 is known trash.
+				pre_clue(game, Player::Alice, 5, &[TestClue { kind: ClueKind::RANK, value: 1, giver: Player::Bob }]);
+			}),
+			..TestOptions::default()
+		});
+
+		let order = game.state.hands[Player::Alice as usize][4];
+		let discard = Reactor::eval_action(&game, &Action::discard(Player::Alice as usize, order, -1, -1, false));
+
+		let bob = &game.state.hands[Player::Bob as usize];
+		let clue = ClueAction { giver: Player::Alice as usize, target: Player::Bob as usize, list: vec![bob[0], bob[2], bob[3]], clue: BaseClue { kind: ClueKind::RANK, value: 4 } };
+		Reactor::eval_action(&game, &Action::Clue(clue)) - discard
+	};
+
+	// At pace 0, discarding leaves too few turns to play every card.
+	assert!(clue_over_discard(7) > 0.0);
+	assert!(clue_over_discard(7) > clue_over_discard(20));
+}
+
+#[test]
+fn it_combines_the_pace_deficit_with_the_pace_multiplier() {
+	let eval = |cards_left: usize| {
+		let mut game = util::setup(Arc::new(Reactor), &[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["g4", "b5", "y4", "p4", "g5"],
+			&["y5", "b4", "p5", "r5", "b4"],
+		], TestOptions {
+			play_stacks: Some(&[3, 3, 3, 3, 3]),
+			clue_tokens: Fraction::from(4),
+			init: Box::new(move |game: &mut Game| {
+				game.state.cards_left = cards_left + 1;
+			}),
+			..TestOptions::default()
+		});
+
+		take_turn(&mut game, "Alice discards r4 (slot 5)");
+		Reactor::eval_game(&game)
+	};
+
+	// From pace 0 to pace -2, the deficit costs 2 * 0.5 and the discarded r4 costs 2.5 * (8/6 - 6/6) more.
+	let expected = -1.0 - 2.5 * (1.0 / 3.0);
+	assert!((eval(5) - eval(7) - expected).abs() < 1e-4);
+}
+
+#[test]
+fn it_values_chained_playables_above_isolated_ones() {
+	let hands: [&[&str]; 3] = [
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g1", "g2", "y1", "r4", "p3"],
+		&["y4", "b4", "r3", "g4", "p4"],
+	];
+	let game = util::setup(Arc::new(Reactor), &hands, TestOptions::default());
+	let bob_hand = &game.state.hands[Player::Bob as usize];
+
+	// Compare revealing Bob's g1 and g2 against revealing his g1 and y1
+	let [chained, isolated] = [[1, 2], [1, 3]].map(|slots| {
+		let hypo = util::setup(Arc::new(Reactor), &hands, TestOptions {
+			init: Box::new(move |game: &mut Game| {
+				for slot in slots {
+					fully_known(game, Player::Bob, slot, hands[Player::Bob as usize][slot - 1]);
+				}
+			}),
+			..TestOptions::default()
+		});
+		assert_eq!(hypo.us().hypo_play_order, slots.map(|slot| bob_hand[slot - 1]));
+
+		let action = ClueAction {
+			giver: Player::Alice as usize,
+			target: Player::Bob as usize,
+			list: slots.iter().map(|slot| bob_hand[slot - 1]).collect(),
+			clue: BaseClue { kind: ClueKind::COLOUR, value: Colour::Green as usize }
+		};
+		Reactor::get_result(&game, &hypo, &action)
+	});
+
+	assert!(chained > isolated);
 }
@@ -2,11 +2,13 @@ use fraction::Fraction;
 use rust_bot::basics::action::{PerformAction};
 use rust_bot::basics::card::CardStatus;
 use rust_bot::basics::clue::ClueKind;
+use rust_bot::basics::game::Game;
+use rust_bot::basics::util::parse_clue;
 use rust_bot::reactor::Reactor;
 use std::sync::Arc;
 
 use crate::ex_asserts;
-use crate::util::{self, pre_clue, take_turn, Colour, Player, TestClue, TestOptions};
+use crate::util::{self, fully_known, pre_clue, take_turn, Colour, Player, TestClue, TestOptions};
 
 #[test]
 fn it_understands_a_reactive_play_play() {
@@ -18,15 +20,40 @@ fn it_understands_a_reactive_play_play() {
 
 	take_turn(&mut game, "Alice clues 5 to Cathy");
 
-	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_waiting(&game, Player::Bob, Player::Cathy, 2);
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToPlay);
 	ex_asserts::has_inferences(&game, None, Player::Bob, 1, &["r1", "y1", "b1", "p1"]);
 
 	take_turn(&mut game, "Bob plays b1, drawing p1");
 
-	assert_eq!(game.meta[game.state.hands[Player::Cathy as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Cathy, 1, CardStatus::CalledToPlay);
 	ex_asserts::has_inferences(&game, None, Player::Cathy, 1, &["r1", "y1", "g1", "b2", "p1"]);
 }
 
+#[test]
+#[should_panic(expected = "No waiting connection")]
+fn it_fails_to_find_a_missing_waiting_connection() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	ex_asserts::has_waiting(&game, Player::Bob, Player::Cathy, 2);
+}
+
+#[test]
+#[should_panic(expected = "Differing status. Expected CalledToPlay, got None")]
+fn it_reports_a_differing_status() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToPlay);
+}
+
 #[test]
 fn it_reacts_to_a_reactive_play_play() {
 	let mut game = util::setup(Arc::new(Reactor), &[
@@ -40,7 +67,7 @@ fn it_reacts_to_a_reactive_play_play() {
 
 	take_turn(&mut game, "Cathy clues 2 to Bob");
 
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Alice, 1, CardStatus::CalledToPlay);
 	ex_asserts::has_inferences(&game, None, Player::Alice, 1, &["r1", "y1", "g1", "p1"]);
 
 	let action = game.take_action();
@@ -63,7 +90,31 @@ fn it_receives_a_reactive_play_play() {
 	take_turn(&mut game, "Cathy plays g1, drawing y3");
 
 	// Alice's slot 2 is called to play.
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][1]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Alice, 2, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Alice, 2, &["r1", "y1", "g2", "b1", "p1"]);
+}
+
+#[test]
+fn it_narrows_a_reactive_target_before_the_reaction() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "r4", "g4"],
+	], TestOptions {
+		starting: Player::Bob,
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Bob clues 4 to Alice (slot 3)");
+
+	// Cathy can only react by playing g1 (slot 1), so Alice's slot 2 must be playable afterward.
+	ex_asserts::has_inferences(&game, None, Player::Alice, 2, &["r1", "y1", "g2", "b1", "p1"]);
+	ex_asserts::has_status(&game, Player::Alice, 2, CardStatus::None);
+
+	take_turn(&mut game, "Cathy plays g1, drawing y3");
+
+	ex_asserts::has_status(&game, Player::Alice, 2, CardStatus::CalledToPlay);
 	ex_asserts::has_inferences(&game, None, Player::Alice, 2, &["r1", "y1", "g2", "b1", "p1"]);
 }
 
@@ -85,7 +136,7 @@ fn it_reacts_to_a_reverse_reactive_play_play() {
 	take_turn(&mut game, "Cathy plays g1, drawing y3");
 	take_turn(&mut game, "Alice clues 1 to Bob");			// Reverse reactive, getting Cathy's g2 and Bob's b2 (4 + 2 = 1)
 
-	assert_eq!(game.meta[game.state.hands[Player::Cathy as usize][3]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Cathy, 4, CardStatus::CalledToPlay);
 
 	take_turn(&mut game, "Bob plays b1, drawing y5");
 	ex_asserts::has_inferences(&game, None, Player::Cathy, 4, &["r1", "y1", "g2", "p1"]);
@@ -109,10 +160,10 @@ fn it_elims_a_reactive_play_play() {
 
 	take_turn(&mut game, "Alice clues 4 to Cathy");
 
-	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToPlay);
 
 	take_turn(&mut game, "Bob plays b1, drawing p1");
-	assert_eq!(game.meta[game.state.hands[Player::Cathy as usize][3]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Cathy, 4, CardStatus::CalledToPlay);
 
 	// Since Bob cannot play a known 3, Cathy can't write !playable on slot 1.
 	assert!(["r1", "y1", "g1", "b1", "p1"].iter().all(|i| game.common.thoughts[game.state.hands[Player::Cathy as usize][0]].inferred.contains(game.state.expand_short(i))));
@@ -138,13 +189,13 @@ fn it_understands_a_reactive_dc_play() {
 
 	take_turn(&mut game, "Alice clues blue to Cathy");
 
-	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToDiscard);
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToDiscard);
 	// ex_asserts::has_inferences(&game, None, Player::Bob, 1, &["r1", "y1", "b1", "p1"]);
-	assert!(game.common.thinks_trash(&game.frame(), Player::Bob as usize).contains(&game.state.hands[Player::Bob as usize][0]));
+	assert!(game.frame().trash_orders(&game.common, Player::Bob as usize).contains(&game.state.hands[Player::Bob as usize][0]));
 
 	take_turn(&mut game, "Bob discards r3 (slot 1), drawing p3");
 
-	assert_eq!(game.meta[game.state.hands[Player::Cathy as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Cathy, 1, CardStatus::CalledToPlay);
 	ex_asserts::has_inferences(&game, None, Player::Cathy, 1, &["r1", "y1", "g1", "p1"]);
 }
 
@@ -168,10 +219,10 @@ fn it_elims_a_reactive_dc_play() {
 	});
 
 	take_turn(&mut game, "Alice clues green to Cathy");
-	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToDiscard);
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToDiscard);
 
 	take_turn(&mut game, "Bob discards b1, drawing b4");
-	assert_eq!(game.meta[game.state.hands[Player::Cathy as usize][3]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Cathy, 4, CardStatus::CalledToPlay);
 
 	// Since Bob cannot discard a known 5, Cathy can't write !playable on slot 1.
 	assert!(["r1", "r2", "y1", "b1"].iter().all(|i| game.common.thoughts[game.state.hands[Player::Cathy as usize][0]].inferred.contains(game.state.expand_short(i))));
@@ -195,13 +246,129 @@ fn it_reacts_to_a_reactive_finesse() {
 	take_turn(&mut game, "Cathy clues 3 to Bob");
 
 	// We should play slot 1 to target Bob's r2.
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Alice, 1, CardStatus::CalledToPlay);
 	ex_asserts::has_inferences(&game, None, Player::Alice, 1, &["r1"]);
 
 	let action = game.take_action();
 	assert_eq!(action, PerformAction::Play { target: game.state.hands[Player::Alice as usize][0] });
 }
 
+#[test]
+fn it_doesnt_use_reactive_finesses_at_a_low_level() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b2", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		starting: Player::Cathy,
+		init: Box::new(|game: &mut Game| {
+			game.level = 1;
+		}),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Cathy clues 3 to Bob");
+
+	// At level 1, Alice doesn't react to target Bob's r2.
+	ex_asserts::has_status(&game, Player::Alice, 1, CardStatus::None);
+
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["p4", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			game.level = 1;
+		}),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Alice clues green to Bob");
+
+	// Ref plays are still understood.
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToPlay);
+}
+
+#[test]
+fn it_doesnt_reuse_clue_values_from_another_level() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b2", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	let clue = parse_clue(&game.state, "Alice clues 3 to Bob").unwrap();
+	Reactor::clue_value(&game, &clue);
+	assert!(Reactor::cached_clue(&game, &clue).is_some());
+
+	// The clue cache is shared with every copy of the game, so the level must be part of the key.
+	game.level = 1;
+	assert!(Reactor::cached_clue(&game, &clue).is_none());
+}
+
+#[test]
+fn it_understands_a_reactive_finesse_through_a_delayed_play() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx"],
+		&["b4", "y3", "g3", "r1"],
+		&["r2", "b3", "p3", "y5"],
+		&["r3", "y4", "g4", "p4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Bob, 4, "r1");
+		}),
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	// Bob will play r1, so Cathy can react with slot 1 to target Donald's r3 (focus slot 2).
+	take_turn(&mut game, "Alice clues 4 to Donald");
+
+	// Both connecting cards are called to play.
+	ex_asserts::has_status(&game, Player::Bob, 4, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Cathy, 1, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Cathy, 1, &["r2"]);
+
+	take_turn(&mut game, "Bob plays r1, drawing b1");
+	take_turn(&mut game, "Cathy plays r2, drawing g1");
+
+	// Donald's r3 is now called to play.
+	ex_asserts::has_status(&game, Player::Donald, 1, CardStatus::CalledToPlay);
+}
+
+#[test]
+fn it_understands_a_layered_reactive_finesse() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "r1", "g3", "y4", "p4"],
+		&["g4", "b5", "r2", "r3", "g5"],
+	], TestOptions {
+		clue_tokens: Fraction::from(6),
+		..TestOptions::default()
+	});
+
+	// Bob reacts with slot 1 to target Cathy's r2 (focus slot 4).
+	take_turn(&mut game, "Alice clues 3 to Cathy");
+	ex_asserts::has_inferences(&game, None, Player::Bob, 1, &["r1"]);
+
+	// Bob's b1 was a layer, so his next finesse position is called to play r1, and Cathy's r2 waits for it.
+	take_turn(&mut game, "Bob plays b1, drawing y1");
+	ex_asserts::has_status(&game, Player::Bob, 2, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Bob, 2, &["r1"]);
+	ex_asserts::has_inferences(&game, None, Player::Cathy, 3, &["r2"]);
+
+	take_turn(&mut game, "Cathy discards g4, drawing p1");
+
+	// Cathy's r2 is still called to play while Bob's r1 is pending.
+	ex_asserts::has_status(&game, Player::Cathy, 3, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Cathy, 3, &["r2"]);
+
+	take_turn(&mut game, "Alice discards y4 (slot 5)");
+	take_turn(&mut game, "Bob plays r1 (slot 2), drawing g1");
+
+	assert!(game.common.thinks_playables(&game.frame(), Player::Cathy as usize).contains(&game.state.hands[Player::Cathy as usize][2]));
+}
+
 #[test]
 fn it_doesnt_play_target_an_unclued_dupe() {
 	let mut game = util::setup(Arc::new(Reactor), &[
@@ -222,7 +389,7 @@ fn it_doesnt_play_target_an_unclued_dupe() {
 	take_turn(&mut game, "Cathy clues green to Bob");
 
 	// We should discard slot 5 (so that Bob plays slot 2).
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][4]].status, CardStatus::CalledToDiscard);
+	ex_asserts::has_status(&game, Player::Alice, 5, CardStatus::CalledToDiscard);
 }
 
 #[test]
@@ -240,13 +407,13 @@ fn it_doesnt_play_target_a_discarding_dupe() {
 	take_turn(&mut game, "Cathy clues red to Bob");
 	take_turn(&mut game, "Alice plays r2 (slot 3)");	// Targeting discard on r3
 
-	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToDiscard);
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToDiscard);
 
 	take_turn(&mut game, "Bob clues 1 to Cathy");
 	take_turn(&mut game, "Cathy clues yellow to Bob");
 
 	// We should discard slot 4 (so that Bob plays the non-discarding dupe in slot 4).
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][3]].status, CardStatus::CalledToDiscard);
+	ex_asserts::has_status(&game, Player::Alice, 4, CardStatus::CalledToDiscard);
 }
 
 #[test]
@@ -268,7 +435,7 @@ fn it_doesnt_dc_target_an_unclued_dupe() {
 	take_turn(&mut game, "Cathy clues green to Bob");
 
 	// We should play slot 4 (so that Bob discards slot 1).
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][3]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Alice, 4, CardStatus::CalledToPlay);
 }
 
 #[test]
@@ -286,7 +453,7 @@ fn it_reacts_to_a_sacrifice() {
 	take_turn(&mut game, "Cathy clues green to Bob");
 
 	// We should play slot 2 (Bob discards y3 in slot 3).
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][1]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Alice, 2, CardStatus::CalledToPlay);
 }
 
 #[test]
@@ -307,10 +474,10 @@ fn it_shifts_a_reaction() {
 	take_turn(&mut game, "Cathy clues 3 to Bob");
 
 	// Normally, Alice would play slot 3 -> Bob slot 1 = 4. However, slot 3 is a known 5.
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][2]].status, CardStatus::None);
+	ex_asserts::has_status(&game, Player::Alice, 3, CardStatus::None);
 
 	// Instead, Alice should play slot 1 -> Bob slot 3 as a finesse.
-	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_status(&game, Player::Alice, 1, CardStatus::CalledToPlay);
 	ex_asserts::has_inferences(&game, None, Player::Alice, 1, &["r1"]);
 
 	let action = game.take_action();
@@ -321,3 +488,88 @@ fn it_shifts_a_reaction() {
 	// Bob's slot 1 should still be allowed to be b1.
 	assert!(game.common.thoughts[game.state.hands[Player::Bob as usize][0]].inferred.contains(game.state.expand_short("b1")));
 }
+
+#[test]
+fn it_understands_a_reactive_play_play_with_6_players() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx"],
+		&["g2", "b1", "r2"],
+		&["b5", "g1", "p2"],
+		&["r3", "y4", "p4"],
+		&["y3", "b4", "g4"],
+		&["r4", "p3", "y2"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	// Focus is slot 1 and the target is Cathy's slot 2, so Bob reacts with slot 2 (1 + 3 - 2).
+	take_turn(&mut game, "Alice clues 5 to Cathy");
+
+	ex_asserts::has_status(&game, Player::Bob, 2, CardStatus::CalledToPlay);
+
+	take_turn(&mut game, "Bob plays b1, drawing p1");
+
+	ex_asserts::has_status(&game, Player::Cathy, 2, CardStatus::CalledToPlay);
+}
+
+#[test]
+fn it_focuses_the_leftmost_card_other_than_slot_1() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r3", "g2", "r2", "y3", "g5"],
+		&["b5", "g1", "b4", "p2", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	// Blue touches slots 1 and 3, so the focus is slot 3 (not slot 1).
+	take_turn(&mut game, "Alice clues blue to Cathy");
+
+	// Bob discards slot 1 to target Cathy's g1 in slot 2 (1 + 2 = 3).
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToDiscard);
+	ex_asserts::has_status(&game, Player::Bob, 4, CardStatus::None);
+
+	take_turn(&mut game, "Bob discards r3 (slot 1), drawing p3");
+	ex_asserts::has_status(&game, Player::Cathy, 2, CardStatus::CalledToPlay);
+}
+
+#[test]
+fn it_focuses_slot_1_when_only_slot_1_is_touched() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r3", "g2", "r2", "y3", "g5"],
+		&["b5", "g4", "p4", "p2", "g1"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	// Blue only touches slot 1, so the focus is slot 1.
+	take_turn(&mut game, "Alice clues blue to Cathy");
+
+	// Bob discards slot 1 to target Cathy's g1 in slot 5 (1 + 5 = 1).
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToDiscard);
+
+	take_turn(&mut game, "Bob discards r3 (slot 1), drawing p3");
+	ex_asserts::has_status(&game, Player::Cathy, 5, CardStatus::CalledToPlay);
+}
+
+#[test]
+fn it_notes_the_reacters_obligation() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	take_turn(&mut game, "Alice clues 5 to Cathy");
+
+	// Bob's b1 reacts to target Cathy's g1 in slot 1
+	let react_order = game.state.hands[Player::Bob as usize][0];
+	assert!(game.notes[&(react_order as u64)].last().contains("react: slot 1"));
+
+	let target_order = game.state.hands[Player::Cathy as usize][0];
+	assert!(game.notes[&(target_order as u64)].last().contains("target of Bob slot 1"));
+}
@@ -1,8 +1,9 @@
+use fraction::Fraction;
 use rust_bot::basics::action::{PerformAction};
 use rust_bot::basics::card::CardStatus;
 use rust_bot::basics::clue::ClueKind;
-use rust_bot::basics::game::Game;
-use rust_bot::reactor::Reactor;
+use rust_bot::basics::game::{Game, Interp};
+use rust_bot::reactor::{ClueInterp, Reactor, ReactorInterp};
 use std::sync::Arc;
 
 use crate::ex_asserts;
@@ -86,6 +87,11 @@ fn it_understands_a_lock() {
 	take_turn(&mut game, "Alice clues 4 to Bob");
 
 	assert!(game.common.thinks_locked(&game.frame(), Player::Bob as usize));
+
+	// Bob's unclued cards are chop moved, and annotated as such.
+	for &order in &game.state.hands[Player::Bob as usize][1..3] {
+		assert!(game.notes[&(order as u64)].last().contains("[cm]"));
+	}
 }
 
 #[test]
@@ -110,3 +116,205 @@ fn it_doesnt_focus_the_wrong_card_for_the_last_id() {
 	let action = game.take_action();
 	assert_eq!(action, PerformAction::Play { target: 0 });
 }
+
+#[test]
+fn it_promises_trash_after_the_last_copy_is_discarded() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["y1", "g1", "b1", "r4", "y1"],
+		&["r3", "b5", "p2", "b1", "g5"],
+	], TestOptions {
+		starting: Player::Cathy,
+		play_stacks: Some(&[2, 4, 4, 4, 4]),
+		discarded: &["r3"],
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Cathy discards r3 (slot 1), drawing y3");
+	assert_eq!(game.state.max_ranks[Colour::Red as usize], 2);
+
+	// With r4 unreachable, every 4 is trash
+	take_turn(&mut game, "Alice clues 4 to Bob");
+	assert!(game.meta[game.state.hands[Player::Bob as usize][3]].trash);
+}
+
+#[test]
+fn it_promises_trash_when_all_copies_start_discarded() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["y1", "g1", "b1", "r4", "y1"],
+		&["y3", "b5", "p2", "b1", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[2, 4, 4, 4, 4]),
+		discarded: &["r3", "r3"],
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	assert_eq!(game.state.max_ranks[Colour::Red as usize], 2);
+
+	take_turn(&mut game, "Alice clues 4 to Bob");
+	assert!(game.meta[game.state.hands[Player::Bob as usize][3]].trash);
+}
+
+#[test]
+fn it_understands_consecutive_trash_fixes() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b4", "r4", "y4", "g4", "b3"],
+		&["p2", "r3", "y3", "b4", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[1, 1, 2, 1, 2]),
+		init: Box::new(|game: &mut Game| {
+			// Both 2s look playable from good touch
+			pre_clue(game, Player::Alice, 1, &[TestClue { kind: ClueKind::RANK, value: 2, giver: Player::Cathy }]);
+			pre_clue(game, Player::Cathy, 1, &[TestClue { kind: ClueKind::RANK, value: 2, giver: Player::Bob }]);
+
+			let Game { common, state, .. } = game;
+			for order in [state.hands[Player::Alice as usize][0], state.hands[Player::Cathy as usize][0]] {
+				common.thoughts[order].inferred.retain(|i| state.is_playable(i));
+			}
+		}),
+		..TestOptions::default()
+	});
+
+	let cathy_2 = game.state.hands[Player::Cathy as usize][0];
+	let alice_2 = game.state.hands[Player::Alice as usize][0];
+
+	// Purple reveals Cathy's 2 as trash, so it is a fix rather than a reactive clue for Bob.
+	take_turn(&mut game, "Alice clues purple to Cathy");
+
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Fix))));
+	assert!(game.frame().trash_orders(&game.common, Player::Cathy as usize).contains(&cathy_2));
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][1]].status, CardStatus::None);
+
+	// Green then fixes Alice's 2, which is also not a reactive clue for Cathy.
+	take_turn(&mut game, "Bob clues green to Alice (slot 1)");
+
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Fix))));
+	assert!(game.meta[alice_2].trash);
+	assert!(game.state.hands[Player::Cathy as usize].iter().all(|&o| game.meta[o].status == CardStatus::None));
+}
+
+#[test]
+fn it_doesnt_connect_through_unlikely_inferences() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r1", "g2", "y2", "b3", "g5"],
+		&["b4", "r3", "p4", "y4", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(5),
+		init: Box::new(|game: &mut Game| {
+			// Bob's slot 1 is called to play, inferred as r1 or r2
+			pre_clue(game, Player::Bob, 1, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Red as usize, giver: Player::Cathy }]);
+			let order = game.state.hands[Player::Bob as usize][0];
+			game.common.thoughts[order].inferred = game.state.expand_shorts(&["r1", "r2"]);
+			game.meta[order].status = CardStatus::CalledToPlay;
+		}),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Alice clues purple to Cathy");
+
+	// Cathy's r3 can only connect if Bob's card is r2, which isn't playable, so this doesn't target it.
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Mistake))));
+	assert_eq!(game.meta[game.state.hands[Player::Cathy as usize][1]].status, CardStatus::None);
+
+	// Bob's card is still expected to be a playable r1, rather than an urgent r2.
+	ex_asserts::has_inferences(&game, None, Player::Bob, 1, &["r1"]);
+	assert!(!game.meta[game.state.hands[Player::Bob as usize][0]].urgent);
+}
+
+#[test]
+fn it_falls_back_to_stable_when_reactive_is_impossible() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g4", "b5", "p2", "r4", "g3"],
+		&["r2", "b1", "y1", "r3", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	// Bob would have to discard b5 to react, so the clue must be a stable ref play on y1.
+	take_turn(&mut game, "Alice clues red to Cathy");
+
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::RefPlay))));
+	assert_eq!(game.meta[game.state.hands[Player::Cathy as usize][2]].status, CardStatus::CalledToPlay);
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][1]].status, CardStatus::None);
+}
+
+#[test]
+fn it_understands_a_reclue() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b4", "g3", "r2", "y4", "p4"],
+		&["g4", "b5", "p2", "y3", "g5"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 0]),
+		clue_tokens: Fraction::from(7),
+		init: Box::new(|game: &mut Game| {
+			// Bob's r2 was clued earlier, but wasn't called to play.
+			pre_clue(game, Player::Bob, 3, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Red as usize, giver: Player::Cathy }]);
+		}),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Alice clues red to Bob");
+
+	// Red touches no new cards, so it re-focuses Bob's clued card as playable.
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Reclue))));
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][2]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Bob, 3, &["r2"]);
+}
+
+#[test]
+fn it_rejects_a_ref_play_into_a_fully_touched_hand() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["g1", "b2", "y4", "b3", "p3"],
+		&["b4", "g3", "r4", "y3", "p4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			// Every card in Bob's hand except g1 is already clued.
+			pre_clue(game, Player::Bob, 2, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Blue as usize, giver: Player::Cathy }]);
+			pre_clue(game, Player::Bob, 3, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Yellow as usize, giver: Player::Cathy }]);
+			pre_clue(game, Player::Bob, 4, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Blue as usize, giver: Player::Cathy }]);
+			pre_clue(game, Player::Bob, 5, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Purple as usize, giver: Player::Cathy }]);
+		}),
+		..TestOptions::default()
+	});
+
+	// Green only touches g1, so there is no other card to reference.
+	take_turn(&mut game, "Alice clues green to Bob");
+
+	// Bob's g1 shouldn't be called to play by referencing itself.
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Mistake))));
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::None);
+}
+
+#[test]
+fn it_eliminates_unreachable_identities_after_the_last_copy_is_discarded() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["y1", "g1", "b1", "y4", "y1"],
+		&["r3", "b5", "p2", "b1", "g5"],
+	], TestOptions {
+		starting: Player::Cathy,
+		play_stacks: Some(&[2, 2, 4, 4, 4]),
+		discarded: &["r3"],
+		clue_tokens: Fraction::from(7),
+		init: Box::new(|game: &mut Game| {
+			pre_clue(game, Player::Bob, 4, &[TestClue { kind: ClueKind::RANK, value: 4, giver: Player::Alice }]);
+		}),
+		..TestOptions::default()
+	});
+
+	ex_asserts::has_inferences(&game, None, Player::Bob, 4, &["r4", "y4"]);
+
+	// Discarding the last r3 makes r4 unreachable, so Bob's 4 can only be y4
+	take_turn(&mut game, "Cathy discards r3 (slot 1), drawing y3");
+	assert_eq!(game.state.max_ranks[Colour::Red as usize], 2);
+	ex_asserts::has_inferences(&game, None, Player::Bob, 4, &["y4"]);
+}
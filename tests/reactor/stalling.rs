@@ -1,8 +1,13 @@
+use fraction::Fraction;
+use rust_bot::basics::action::{ClueAction, PerformAction};
 use rust_bot::basics::card::CardStatus;
-use rust_bot::reactor::{Reactor};
+use rust_bot::basics::clue::{BaseClue, ClueKind};
+use rust_bot::basics::game::{Game, Interp, SimOpts};
+use rust_bot::basics::util::parse_clue;
+use rust_bot::reactor::{ClueInterp, Reactor, ReactorInterp};
 use std::sync::Arc;
 
-use crate::util::{self, take_turn, Player, TestOptions};
+use crate::util::{self, fully_known, pre_clue, take_turn, Colour, Player, TestClue, TestOptions};
 
 #[test]
 fn it_understands_a_bad_play() {
@@ -59,3 +64,150 @@ fn it_doesnt_react_to_untargetable_cathy_1s() {
 	// Bob's slot 5 is not called to play, since colour can't given to Cathy.
 	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][4]].status, CardStatus::None);
 }
+
+#[test]
+fn it_gives_a_5_stall() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r5", "y4", "g4", "b4", "p4"],
+		&["b5", "r4", "y3", "g3", "r1"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 0]),
+		discarded: &["y4", "g4", "b4", "p4", "r4", "y3", "g3"],
+		clue_tokens: Fraction::from(8),
+		..TestOptions::default()
+	});
+
+	// Every other clue calls a critical card to discard or trash to play, so we should 5 stall on Bob's chop.
+	let action = game.take_action();
+	assert_eq!(action, PerformAction::Rank { target: Player::Bob as usize, value: 5 });
+
+	take_turn(&mut game, "Alice clues 5 to Bob");
+
+	// Bob's slot 2 should not be called to discard.
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::FiveStall))));
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][1]].status, CardStatus::None);
+}
+
+#[test]
+fn it_values_a_5_stall_neutrally() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r5", "y4", "g4", "b4", "p4"],
+		&["b5", "r4", "y3", "g3", "r1"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 0]),
+		discarded: &["y4", "g4", "b4", "p4", "r4", "y3", "g3"],
+		clue_tokens: Fraction::from(8),
+		..TestOptions::default()
+	});
+
+	let clue = ClueAction {
+		giver: Player::Alice as usize,
+		target: Player::Bob as usize,
+		list: vec![game.state.hands[Player::Bob as usize][0]],
+		clue: BaseClue { kind: ClueKind::RANK, value: 5 }
+	};
+	let hypo = game.simulate_clue(&clue, SimOpts::default());
+
+	// The 5 stall gets no information value, only its priority as a stall.
+	assert_eq!(hypo.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::FiveStall))));
+	assert_eq!(Reactor::get_result(&game, &hypo, &clue), 0.5);
+}
+
+#[test]
+fn it_prefers_a_fill_in_over_a_5_stall() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r5", "y4", "g4", "b4", "p4"],
+		&["b5", "r4", "y3", "g3", "r1"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 0]),
+		discarded: &["y4", "g4", "b4", "p4", "r4", "y3", "g3"],
+		clue_tokens: Fraction::from(8),
+		init: Box::new(|game: &mut Game| {
+			pre_clue(game, Player::Cathy, 2, &[TestClue { kind: ClueKind::COLOUR, value: Colour::Red as usize, giver: Player::Bob }]);
+		}),
+		..TestOptions::default()
+	});
+
+	// Filling in Cathy's r4 is more informative than a 5 stall.
+	let action = game.take_action();
+	assert_eq!(action, PerformAction::Rank { target: Player::Cathy as usize, value: 4 });
+}
+
+#[test]
+fn it_prefers_a_5_stall_over_a_hard_burn() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r5", "y4", "g4", "b4", "p4"],
+		&["b5", "r4", "y3", "g3", "r1"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 0]),
+		discarded: &["y4", "g4", "b4", "p4", "r4", "y3", "g3"],
+		clue_tokens: Fraction::from(8),
+		init: Box::new(|game: &mut Game| {
+			pre_clue(game, Player::Cathy, 2, &[
+				TestClue { kind: ClueKind::COLOUR, value: Colour::Red as usize, giver: Player::Bob },
+				TestClue { kind: ClueKind::RANK, value: 4, giver: Player::Bob }
+			]);
+		}),
+		..TestOptions::default()
+	});
+
+	// Cluing 4 to Cathy only retouches her known r4, which would be a hard burn.
+	let burn = parse_clue(&game.state, "Alice clues 4 to Cathy").unwrap();
+	let hypo = game.simulate_clue(&burn, SimOpts::default());
+	assert_eq!(hypo.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Stall))));
+
+	// A 5 stall on Bob's chop is preferred.
+	let action = game.take_action();
+	assert_eq!(action, PerformAction::Rank { target: Player::Bob as usize, value: 5 });
+}
+
+#[test]
+fn it_understands_a_stall_after_a_5_restores_8_clues() {
+	let setup = |clue_tokens: usize| util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r5", "y4", "g4", "b4", "p4"],
+		&["p5", "r4", "y3", "g3", "r1"],
+	], TestOptions {
+		play_stacks: Some(&[1, 0, 0, 0, 4]),
+		discarded: &["y4", "g4", "b4", "r4", "y3", "g3"],
+		clue_tokens: Fraction::from(clue_tokens),
+		starting: Player::Cathy,
+		init: Box::new(|game: &mut Game| {
+			fully_known(game, Player::Cathy, 1, "p5");
+		}),
+		..TestOptions::default()
+	});
+
+	// Cathy's p5 brings the team back to 8 clues, so Alice's clue is a stall.
+	let mut game = setup(7);
+	take_turn(&mut game, "Cathy plays p5, drawing b1");
+	take_turn(&mut game, "Alice clues 5 to Bob");
+
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::FiveStall))));
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][1]].status, CardStatus::None);
+
+	// Below 8 clues, the same clue is not a stall.
+	let mut game = setup(6);
+	take_turn(&mut game, "Cathy plays p5, drawing b1");
+	take_turn(&mut game, "Alice clues 5 to Bob");
+
+	assert_ne!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::FiveStall))));
+}
+
+#[test]
+fn it_allows_a_reactive_clue_at_8_clues_on_turn_1() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	take_turn(&mut game, "Alice clues 5 to Cathy");
+
+	assert_eq!(game.last_move, Some(Interp::Reactor(ReactorInterp::Clue(ClueInterp::Reactive))));
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToPlay);
+}
@@ -1,10 +1,15 @@
-use rust_bot::basics::action::{PerformAction};
+use fraction::Fraction;
+use rust_bot::basics::action::{Action, PerformAction};
 use rust_bot::basics::card::CardStatus;
+use rust_bot::basics::clue::ClueKind;
+use rust_bot::basics::game::Game;
+use rust_bot::basics::state::State;
+use rust_bot::basics::variant::all_ids;
 use rust_bot::reactor::Reactor;
 use std::sync::Arc;
 
 use crate::ex_asserts;
-use crate::util::{self, take_turn, Player, TestOptions};
+use crate::util::{self, pre_clue, take_turn, Player, TestClue, TestOptions};
 
 #[test]
 fn it_understands_a_playable_pink_promise() {
@@ -27,10 +32,30 @@ fn it_understands_a_playable_pink_promise() {
 	ex_asserts::has_inferences(&game, None, Player::Alice, 2, &["r2", "g2", "b2"]);
 
 	// Alice's slot 4 is not playable.
-	let playables = game.common.obvious_playables(&game.frame(), Player::Alice as usize);
+	let playables = game.frame().playable_orders(&game.common, Player::Alice as usize);
 	assert!(playables.len() == 1 && playables[0] == game.state.hands[Player::Alice as usize][1]);
 }
 
+#[test]
+fn it_keeps_white_possibilities_on_a_rank_clue() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "w2", "b1", "g4"],
+	], TestOptions {
+		variant: "White (5 Suits)",
+		starting: Player::Bob,
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Bob clues 3 to Alice (slot 2)");
+	ex_asserts::has_possible(&game, None, Player::Alice, 2, &["r3", "y3", "g3", "b3", "w3"]);
+
+	// A colour clue that doesn't touch slot 2 removes red, but white can never be touched by colour.
+	take_turn(&mut game, "Cathy clues red to Alice (slot 5)");
+	ex_asserts::has_possible(&game, None, Player::Alice, 2, &["y3", "g3", "b3", "w3"]);
+}
+
 #[test]
 fn it_understands_a_brown_tcm() {
 	let mut game = util::setup(Arc::new(Reactor), &[
@@ -47,7 +72,302 @@ fn it_understands_a_brown_tcm() {
 	take_turn(&mut game, "Cathy clues 1 to Alice (slots 2,4)");
 
 	// Alice does not have a playable.
-	let playables = game.common.obvious_playables(&game.frame(), Player::Alice as usize);
+	let playables = game.frame().playable_orders(&game.common, Player::Alice as usize);
 	assert!(playables.is_empty());
 	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][0]].status, CardStatus::None);
 }
+
+#[test]
+fn it_understands_a_prism_ref_play() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["i4", "b5", "i2", "b1", "g4"],
+	], TestOptions {
+		variant: "Prism (5 Suits)",
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Alice clues green to Bob");
+
+	// Green touches g2 and g5, so Bob's b1 is called to play. It could still be a prism 1 (touched by red).
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Bob, 1, &["r1", "y1", "b1", "i1"]);
+}
+
+#[test]
+fn it_understands_a_ref_play_on_white_ones() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["y2", "r1", "r2", "b3", "g5"],
+		&["g4", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		variant: "White-Ones (5 Suits)",
+		..TestOptions::default()
+	});
+
+	// Red doesn't touch r1, so it only touches r2 and Bob's r1 in slot 2 is called to play.
+	take_turn(&mut game, "Alice clues red to Bob");
+
+	assert!(!game.state.deck[game.state.hands[Player::Bob as usize][1]].clued);
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][1]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Bob, 2, &["r1", "y1", "g1", "b1", "p1"]);
+}
+
+#[test]
+fn it_focuses_rank_clues_on_white_ones() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		variant: "White-Ones (5 Suits)",
+		starting: Player::Bob,
+		..TestOptions::default()
+	});
+
+	// 1s can't be touched by any colour, but are still touched by rank.
+	take_turn(&mut game, "Bob clues 1 to Alice (slots 2,3)");
+	ex_asserts::has_possible(&game, Some(Player::Alice), Player::Alice, 2, &["r1", "y1", "g1", "b1", "p1"]);
+
+	// The playable promise focuses slot 2.
+	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][1]].status, CardStatus::CalledToPlay);
+
+	take_turn(&mut game, "Cathy clues purple to Alice (slot 5)");
+	ex_asserts::has_possible(&game, Some(Player::Alice), Player::Alice, 5, &["p2", "p3", "p4", "p5"]);
+}
+
+#[test]
+fn it_focuses_a_playable_promise_with_deceptive_ones() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g4", "b5", "p3", "b1", "g4"],
+	], TestOptions {
+		variant: "Deceptive-Ones (5 Suits)",
+		play_stacks: Some(&[1, 1, 1, 1, 1]),
+		starting: Player::Bob,
+		..TestOptions::default()
+	});
+
+	// 2 touches trash r1 and p1 as well as all the 2s, but every 2 is playable.
+	take_turn(&mut game, "Bob clues 2 to Alice (slots 3,4)");
+
+	assert_eq!(game.meta[game.state.hands[Player::Alice as usize][2]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Alice, 3, &["r2", "y2", "g2", "b2", "p2"]);
+}
+
+#[test]
+fn it_counts_the_deck_for_6_suits_with_rainbow() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["m4", "b5", "m2", "b1", "g4"],
+	], TestOptions {
+		variant: "Rainbow (6 Suits)",
+		..TestOptions::default()
+	});
+
+	let state = State::new(game.state.player_names.clone(), 0, game.state.variant.clone());
+	assert_eq!(all_ids(&state.variant).count(), 30);
+	assert_eq!(state.cards_left, 60);
+	assert_eq!(state.remaining_multiplicity(all_ids(&state.variant)), 60);
+
+	// 15 cards have been dealt.
+	assert_eq!(game.state.cards_left, 45);
+}
+
+#[test]
+fn it_understands_a_rainbow_ref_play() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "m2", "r2", "r3", "g5"],
+		&["m4", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		variant: "Rainbow (6 Suits)",
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Alice clues green to Bob");
+
+	// Green touches m2 and g5, so Bob's b1 is called to play. It can't be a rainbow 1 or g1, since green would have touched it.
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Bob, 1, &["r1", "y1", "b1", "p1"]);
+}
+
+#[test]
+fn it_focuses_prism_colour_clues_by_value() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r3", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "r4", "y4", "g4"],
+	], TestOptions {
+		variant: "Prism (5 Suits)",
+		clue_tokens: Fraction::from(7),
+		..TestOptions::default()
+	});
+
+	// Yellow focuses slot 2, regardless of which cards it touched.
+	take_turn(&mut game, "Alice clues yellow to Cathy");
+
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToDiscard);
+
+	take_turn(&mut game, "Bob discards r3 (slot 1), drawing i3");
+
+	assert_eq!(game.meta[game.state.hands[Player::Cathy as usize][0]].status, CardStatus::CalledToPlay);
+}
+
+#[test]
+fn it_saves_a_critical_4_in_critical_fours() {
+	let game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r4", "y3", "g3", "b3", "p3"],
+		&["b2", "g4", "y4", "p2", "r2"],
+	], TestOptions {
+		clue_tokens: Fraction::from(4),
+		variant: "Critical Fours (5 Suits)",
+		..TestOptions::default()
+	});
+
+	let r4 = game.state.expand_short("r4");
+	assert_eq!(game.state.card_count(r4), 1);
+	assert!(game.state.is_critical(r4));
+
+	// Bob's r4 is on chop, so we should clue it rather than discard.
+	let action = game.take_action();
+	assert_eq!(action, PerformAction::Rank { target: Player::Bob as usize, value: 4 });
+}
+
+#[test]
+fn it_understands_a_ref_play_in_up_or_down() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b5", "g2", "r2", "r3", "g4"],
+		&["p4", "b1", "p2", "b3", "y4"],
+	], TestOptions {
+		variant: "Up or Down (5 Suits)",
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Alice clues green to Bob");
+
+	// Stacks can start from either end, so the referenced card could be any non-green 1 or 5.
+	assert_eq!(game.meta[game.state.hands[Player::Bob as usize][0]].status, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Bob, 1, &["r1", "y1", "b1", "p1", "r5", "y5", "b5", "p5"]);
+
+	take_turn(&mut game, "Bob plays b5, drawing r4");
+
+	// Blue is now played downwards
+	let b4 = game.state.expand_short("b4");
+	assert!(game.state.is_playable(b4));
+	assert_eq!(game.state.next_playable_rank(b4.suit_index), Some(4));
+	assert!(game.state.is_basic_trash(game.state.expand_short("b5")));
+	assert_eq!(game.state.playable_away(game.state.expand_short("b1")), 3);
+	assert_eq!(game.state.max_ranks[b4.suit_index], 5);
+
+	// There is only one copy of each 1
+	assert!(game.state.is_critical(game.state.expand_short("r1")));
+}
+
+#[test]
+fn it_lets_a_locked_player_stall_with_half_clues_in_clue_starved() {
+	let discard_value = |clue_tokens: Fraction| {
+		let game = util::setup(Arc::new(Reactor), &[
+			&["xx", "xx", "xx", "xx", "xx"],
+			&["r5", "y5", "g5", "b5", "p5"],
+			&["r2", "y3", "g3", "b3", "p3"],
+		], TestOptions {
+			play_stacks: Some(&[1, 1, 1, 1, 1]),
+			clue_tokens,
+			variant: "Clue Starved (5 Suits)",
+			init: Box::new(|game: &mut Game| {
+				// Bob is locked on 5s, and Alice's slot 5 is known trash.
+				for slot in 1..=5 {
+					pre_clue(game, Player::Bob, slot, &[TestClue { kind: ClueKind::RANK, value: 5, giver: Player::Cathy }]);
+				}
+				pre_clue(game, Player::Alice, 5, &[TestClue { kind: ClueKind::RANK, value: 1, giver: Player::Bob }]);
+			}),
+			..TestOptions::default()
+		});
+
+		let order = game.state.hands[Player::Alice as usize][4];
+		Reactor::eval_action(&game, &Action::discard(Player::Alice as usize, order, -1, -1, false))
+	};
+
+	// Discarding from half a clue gives Bob a whole one to stall with, but discarding from none forces him to discard a 5.
+	assert!(discard_value(Fraction::new(1u64, 2u64)) > 0.0);
+	assert!(discard_value(Fraction::from(0)) < 0.0);
+}
+
+#[test]
+fn it_targets_a_reactive_play_on_an_undecided_stack() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["r3", "g2", "r2", "r3", "g3"],
+		&["g5", "b4", "p2", "b3", "g4"],
+	], TestOptions {
+		clue_tokens: Fraction::from(7),
+		variant: "Up or Down (5 Suits)",
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Alice clues blue to Cathy");
+
+	// Cathy's g5 can start the green stack, so Bob discards to make it play.
+	ex_asserts::has_status(&game, Player::Bob, 1, CardStatus::CalledToDiscard);
+
+	take_turn(&mut game, "Bob discards r3 (slot 1), drawing p3");
+
+	ex_asserts::has_status(&game, Player::Cathy, 1, CardStatus::CalledToPlay);
+	ex_asserts::has_inferences(&game, None, Player::Cathy, 1, &["r1", "r5", "y1", "y5", "g1", "g5", "p1", "p5"]);
+}
+
+#[test]
+fn it_eliminates_played_cards_on_a_down_stack() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b5", "g2", "r2", "r3", "g4"],
+		&["p4", "b1", "p2", "b3", "y4"],
+	], TestOptions {
+		starting: Player::Bob,
+		variant: "Up or Down (5 Suits)",
+		init: Box::new(|game: &mut Game| {
+			pre_clue(game, Player::Alice, 5, &[TestClue { kind: ClueKind::COLOUR, value: 3, giver: Player::Bob }]);
+		}),
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Bob plays b5, drawing r4");
+
+	// Blue is played downwards, so the b5 is on the stack and no b1 has been played.
+	assert_eq!(game.state.base_count(game.state.expand_short("b5")), 1);
+	assert_eq!(game.state.base_count(game.state.expand_short("b1")), 0);
+	ex_asserts::has_possible(&game, None, Player::Alice, 5, &["b1", "b2", "b3", "b4"]);
+}
+
+#[test]
+fn it_steps_along_a_down_stack() {
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b5", "g2", "r2", "r3", "g4"],
+		&["p4", "b1", "p2", "b3", "y4"],
+	], TestOptions {
+		starting: Player::Bob,
+		variant: "Up or Down (5 Suits)",
+		..TestOptions::default()
+	});
+
+	take_turn(&mut game, "Bob plays b5, drawing r4");
+
+	// Blue is played downwards, so a b4 follows the b5 and nothing follows a b1.
+	let state = &game.state;
+	let id = |short| state.expand_short(short);
+	assert_eq!(state.next_id(id("b5")), Some(id("b4")));
+	assert_eq!(state.prev_id(id("b4")), Some(id("b5")));
+	assert_eq!(state.next_id(id("b1")), None);
+	assert_eq!(state.prev_id(id("b5")), None);
+
+	// Red is still undecided, so it steps upwards.
+	assert_eq!(state.next_id(id("r2")), Some(id("r3")));
+	assert_eq!(state.next_id(id("r5")), None);
+}
@@ -0,0 +1,34 @@
+use rust_bot::basics::variant::{Variant, VariantOpts};
+use rust_bot::replay::load_shared_replay;
+use std::sync::Arc;
+
+static SAMPLE_REPLAY: &str = r#"{
+	"players": ["Alice", "Bob"],
+	"deck": [
+		{"suitIndex":0,"rank":1}, {"suitIndex":1,"rank":1}, {"suitIndex":2,"rank":1}, {"suitIndex":3,"rank":1}, {"suitIndex":4,"rank":1}, {"suitIndex":0,"rank":2}, {"suitIndex":1,"rank":2}, {"suitIndex":2,"rank":2}, {"suitIndex":3,"rank":2}, {"suitIndex":4,"rank":2},
+		{"suitIndex":0,"rank":1}, {"suitIndex":0,"rank":1}, {"suitIndex":0,"rank":2}, {"suitIndex":0,"rank":3}, {"suitIndex":0,"rank":3}, {"suitIndex":0,"rank":4}, {"suitIndex":0,"rank":4}, {"suitIndex":0,"rank":5}, {"suitIndex":1,"rank":1}, {"suitIndex":1,"rank":1},
+		{"suitIndex":1,"rank":2}, {"suitIndex":1,"rank":3}, {"suitIndex":1,"rank":3}, {"suitIndex":1,"rank":4}, {"suitIndex":1,"rank":4}, {"suitIndex":1,"rank":5}, {"suitIndex":2,"rank":1}, {"suitIndex":2,"rank":1}, {"suitIndex":2,"rank":2}, {"suitIndex":2,"rank":3},
+		{"suitIndex":2,"rank":3}, {"suitIndex":2,"rank":4}, {"suitIndex":2,"rank":4}, {"suitIndex":2,"rank":5}, {"suitIndex":3,"rank":1}, {"suitIndex":3,"rank":1}, {"suitIndex":3,"rank":2}, {"suitIndex":3,"rank":3}, {"suitIndex":3,"rank":3}, {"suitIndex":3,"rank":4},
+		{"suitIndex":3,"rank":4}, {"suitIndex":3,"rank":5}, {"suitIndex":4,"rank":1}, {"suitIndex":4,"rank":1}, {"suitIndex":4,"rank":2}, {"suitIndex":4,"rank":3}, {"suitIndex":4,"rank":3}, {"suitIndex":4,"rank":4}, {"suitIndex":4,"rank":4}, {"suitIndex":4,"rank":5}
+	],
+	"actions": [
+		{"type":0,"target":0},
+		{"type":0,"target":5},
+		{"type":3,"target":1,"value":2},
+		{"type":0,"target":9},
+		{"type":1,"target":2}
+	],
+	"options": {"variant":"No Variant"}
+}"#;
+
+#[test]
+fn it_loads_a_shared_replay() {
+	let variant = Variant::new(0, "No Variant", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts::default());
+
+	// Alice plays r1, Bob plays r2, Alice clues 2 to Bob, Bob bombs p2, Alice discards g1.
+	let game = load_shared_replay(SAMPLE_REPLAY, 0, Arc::new(variant)).unwrap();
+
+	assert_eq!(game.state.play_stacks, vec![2, 0, 0, 0, 0]);
+	assert_eq!(game.state.strikes, 1);
+	assert_eq!(game.state.cards_left, 36);
+}
@@ -7,7 +7,7 @@ use rust_bot::basics::clue::{BaseClue, CardClue, ClueKind};
 use rust_bot::basics::card::{Identifiable, Identity};
 use rust_bot::basics::game::{Convention, Game};
 use rust_bot::basics::identity_set::IdentitySet;
-use rust_bot::basics::util::visible_find;
+use rust_bot::basics::util::parse_clue;
 use rust_bot::basics::state::State;
 use rust_bot::basics::variant::{all_ids, id_touched, Variant, VariantOpts};
 use std::sync::{Arc, LazyLock};
@@ -18,21 +18,33 @@ pub enum Colour {
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum Player {
-	Alice,Bob,Cathy,Donald,Emily
+	Alice,Bob,Cathy,Donald,Emily,Frank
 }
 
 static VARIANTS: LazyLock<HashMap<&str, Variant>> = LazyLock::new(|| {
     HashMap::from([
         ("No Variant", Variant::new(0, "No Variant", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts::default())),
         ("6 Suits", Variant::new(0, "6 Suits", &["Red", "Yellow", "Green", "Blue", "Purple", "Teal"], &["r", "y", "g", "b", "p", "t"], VariantOpts::default())),
+        ("White (5 Suits)", Variant::new(22, "White", &["Red", "Yellow", "Green", "Blue", "White"], &["r", "y", "g", "b", "w"], VariantOpts::default())),
         ("Rainbow (5 Suits)", Variant::new(16, "Rainbow", &["Red", "Yellow", "Green", "Blue", "Rainbow"], &["r", "y", "g", "b", "m"], VariantOpts::default())),
+        ("Rainbow (6 Suits)", Variant::new(17, "Rainbow (6 Suits)", &["Red", "Yellow", "Green", "Blue", "Purple", "Rainbow"], &["r", "y", "g", "b", "p", "m"], VariantOpts::default())),
         ("Black (5 Suits)", Variant::new(2, "Black", &["Red", "Yellow", "Green", "Blue", "Black"], &["r", "y", "g", "b", "k"], VariantOpts::default())),
         ("Pink (5 Suits)", Variant::new(2, "Pink", &["Red", "Yellow", "Green", "Blue", "Pink"], &["r", "y", "g", "b", "i"], VariantOpts::default())),
         ("Brown (5 Suits)", Variant::new(2, "Brown", &["Red", "Yellow", "Green", "Blue", "Brown"], &["r", "y", "g", "b", "n"], VariantOpts::default())),
+        ("Muddy Rainbow (5 Suits)", Variant::new(2, "Muddy Rainbow", &["Red", "Yellow", "Green", "Blue", "Muddy Rainbow"], &["r", "y", "g", "b", "m"], VariantOpts::default())),
+        ("Dual-Color (5 Suits)", Variant::new(2, "Dual-Color", &["Red", "Yellow", "Green", "Blue", "Orange D"], &["r", "y", "g", "b", "o"], VariantOpts { clue_colours: HashMap::from([("Orange D".to_owned(), vec!["Red".to_owned(), "Yellow".to_owned()])]), ..VariantOpts::default() })),
+        ("White-Ones (5 Suits)", Variant::new(2, "White-Ones", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts { special_rank: Some(1), white_s: Some(true), ..VariantOpts::default() })),
+        ("Deceptive-Ones (5 Suits)", Variant::new(2, "Deceptive-Ones", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts { special_rank: Some(1), deceptive_s: Some(true), ..VariantOpts::default() })),
+        ("Prism (5 Suits)", Variant::new(2, "Prism", &["Red", "Yellow", "Green", "Blue", "Prism"], &["r", "y", "g", "b", "i"], VariantOpts::default())),
+        ("Critical Fours (5 Suits)", Variant::new(0, "Critical Fours", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts { critical_rank: Some(4), ..VariantOpts::default() })),
+        ("Up or Down (5 Suits)", Variant::new(0, "Up or Down", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts { up_or_down: Some(true), ..VariantOpts::default() })),
+        ("Throw It in a Hole (5 Suits)", Variant::new(0, "Throw It in a Hole", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts { no_five_regain: Some(true), ..VariantOpts::default() })),
+        ("Clue Starved (5 Suits)", Variant::new(0, "Clue Starved", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts { clue_starved: Some(true), ..VariantOpts::default() })),
+        ("Number Mute (5 Suits)", Variant::new(0, "Number Mute", &["Red", "Yellow", "Green", "Blue", "Purple"], &["r", "y", "g", "b", "p"], VariantOpts { clue_ranks: Some(Vec::new()), ..VariantOpts::default() })),
     ])
 });
 
-static NAMES: [&str; 5] = ["Alice", "Bob", "Cathy", "Donald", "Emily"];
+static NAMES: [&str; 6] = ["Alice", "Bob", "Cathy", "Donald", "Emily", "Frank"];
 
 pub struct TestOptions<'a> {
 	pub min_level: u8,
@@ -116,20 +128,15 @@ pub fn setup(convention: Arc<dyn Convention + Send + Sync + 'static>, hands: &[&
     	}
     }
 
-    let Game { players, state, .. } = &mut game;
+    let Game { state, .. } = &mut game;
 
 	for short in test_options.discarded {
 		let id = state.expand_short(short);
-		let Identity { suit_index, rank } = id;
-		state.discard_stacks[suit_index][rank - 1].push(99);
-
-		if state.discard_stacks[suit_index][rank - 1].len() > state.card_count(id) {
-			state.max_ranks[suit_index] = std::cmp::min(state.max_ranks[suit_index], rank - 1);
-		}
+		state.discard_to_stack(99, id);
 	}
 
 	for id in all_ids(&state.variant) {
-		let count = state.base_count(id) + visible_find(state, &players[state.our_player_index], id, Default::default(), |_, _| true).len();
+		let count = state.base_count(id) + state.visible_count(id);
 
 		if count > state.card_count(id) {
 			panic!("Found {count} copies of {}!", state.log_id(id));
@@ -189,7 +196,7 @@ pub fn take_turn(game: &mut Game, raw_action: &str) {
 					}
 
 					let Identity { suit_index, rank } = draw;
-					let count = state.base_count(draw) + visible_find(state, game.me(), draw, Default::default(), |_, _| true).len();
+					let count = state.base_count(draw) + state.visible_count(draw);
 
 					if count + 1 > state.card_count(draw) {
 						panic!("Found {} copies of {}!", count + 1, state.log_id(draw));
@@ -249,32 +256,7 @@ fn parse_action(state: &State, action: &str) -> (Action, Option<Identity>) {
 		.unwrap_or_else(|| panic!("Couldn't parse giver {player_name}, not in list of players {:?}", state.player_names));
 
 	match parts[1] {
-		"clues" => {
-			let clue = if "12345".contains(parts[2]) {
-				BaseClue { kind: ClueKind::RANK, value: parts[2].parse().unwrap() }
-			}
-			else {
-				BaseClue { kind: ClueKind::COLOUR, value: state.variant.suits.iter().position(|suit| suit.to_lowercase() == parts[2].to_lowercase())
-					.unwrap_or_else(|| panic!("Couldn't parse colour {}", parts[2])) }
-			};
-			let target_name = parts[4];
-			let target = state.player_names.iter().position(|name| name == target_name)
-				.unwrap_or_else(|| panic!("Couldn't parse target {target_name}, not in list of players {:?}", state.player_names));
-
-			if target != state.our_player_index {
-				let list = state.clue_touched(&state.hands[target], &clue);
-
-				if list.is_empty() {
-					panic!("No cards touched by clue {:?} to {}", clue, state.player_names[target]);
-				}
-				(Action::Clue(ClueAction { clue, giver: player_index, target, list }), None)
-			}
-			else {
-				let slots = parse_slots(state, &parts, 6, false, "(clue to us)");
-				let list = slots.iter().map(|slot| state.our_hand()[slot - 1]).collect();
-				(Action::Clue(ClueAction { clue, giver: player_index, target, list }), None)
-			}
-		},
+		"clues" => (Action::Clue(parse_clue(state, action).unwrap_or_else(|err| panic!("{err}"))), None),
 		"plays" => {
 			// Bob plays r5 (slot 1), drawing r1
 			let id = state.expand_short(parts[2]);
@@ -390,17 +372,15 @@ pub fn pre_clue(game: &mut Game, player_index: Player, slot: usize, clues: &[Tes
 }
 
 /**
- * Pre-clues the slot with both colour and rank (only works for simple variants).
+ * Pre-clues the slot with both colour and rank (only works for simple variants, panics otherwise).
  */
 pub fn fully_known(game: &mut Game, player_index: Player, slot: usize, short: &str) {
 	let Game { state, .. } = game;
 	let card = &state.deck[state.hands[player_index as usize][slot - 1]];
 	let id = state.expand_short(short);
 
-	if let Some(deck_id) = card.id() {
-		if deck_id != id {
-			panic!("{}'s card at slot {} is not {}! found {}", state.player_names[player_index as usize], slot, state.log_id(id), state.log_id(deck_id));
-		}
+	if let Some(deck_id) = card.id() && deck_id != id {
+		panic!("{}'s card at slot {} is not {}! found {}", state.player_names[player_index as usize], slot, state.log_id(id), state.log_id(deck_id));
 	}
 
 	let giver = if player_index == Player::Alice { Player::Bob } else { Player::Alice };
@@ -409,4 +389,10 @@ pub fn fully_known(game: &mut Game, player_index: Player, slot: usize, short: &s
 		TestClue { kind: ClueKind::RANK, value: id.rank, giver },
 		TestClue { kind: ClueKind::COLOUR, value: id.suit_index, giver },
 	]);
+
+	let Game { state, common, .. } = game;
+	let order = state.hands[player_index as usize][slot - 1];
+	if common.thoughts[order].possible != state.expand_shorts(&[short]) {
+		panic!("Colour and rank clues don't fully determine {short} in this variant! found {}", common.str_poss(state, order));
+	}
 }
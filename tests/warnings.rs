@@ -0,0 +1,92 @@
+use log::{Level, Metadata, Record};
+use rust_bot::basics::action::{Action, StatusAction};
+use rust_bot::reactor::Reactor;
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex, MutexGuard, Once};
+
+use crate::util::TestOptions;
+
+pub mod util;
+pub mod ex_asserts;
+
+/** Simulations silence the (global) logger, so tests that check for warnings can't run alongside each other. */
+static SERIAL: Mutex<()> = Mutex::new(());
+static INIT: Once = Once::new();
+
+thread_local! {
+	static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/** Records the warnings logged on each thread, in place of the bot's logger. */
+struct CaptureLogger;
+
+impl log::Log for CaptureLogger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= Level::Warn
+	}
+
+	fn log(&self, record: &Record) {
+		if self.enabled(record.metadata()) && record.level() == Level::Warn {
+			WARNINGS.with_borrow_mut(|warnings| warnings.push(record.args().to_string()));
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+/** Installs the capturing logger, which must happen before anything else sets up the bot's logger. Held for the rest of the test. */
+fn capture_logs() -> MutexGuard<'static, ()> {
+	INIT.call_once(|| log::set_logger(&CaptureLogger).expect("The capturing logger should be installed first"));
+	SERIAL.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/** Returns the warnings logged on this thread while running the function. */
+fn warnings(f: impl FnOnce()) -> Vec<String> {
+	log::set_max_level(log::LevelFilter::Info);
+	WARNINGS.with_borrow_mut(|warnings| warnings.clear());
+	f();
+	WARNINGS.take()
+}
+
+#[test]
+fn it_warns_when_the_score_is_out_of_sync() {
+	let _logs = capture_logs();
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions::default());
+
+	assert!(warnings(|| game.handle_action(&Action::Status(StatusAction { clues: 8, score: 0, max_score: 25 }))).is_empty());
+
+	let warned = warnings(|| game.handle_action(&Action::Status(StatusAction { clues: 8, score: 2, max_score: 25 })));
+	assert_eq!(warned, ["score out of sync! expected 2, found 0"]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn it_warns_about_inconsistent_empathy_after_elim() {
+	use rust_bot::basics::game::Game;
+	use crate::util::{take_turn, Player};
+
+	let _logs = capture_logs();
+	let mut game = util::setup(Arc::new(Reactor), &[
+		&["xx", "xx", "xx", "xx", "xx"],
+		&["b1", "g2", "r2", "r3", "g5"],
+		&["g1", "b5", "p2", "b1", "g4"],
+	], TestOptions {
+		init: Box::new(|game: &mut Game| {
+			// Bob's b1 has been over-eliminated
+			let order = game.state.hands[Player::Bob as usize][0];
+			let ids = game.state.expand_shorts(&["r2", "r3"]);
+			game.common.thoughts[order].possible = ids;
+			game.common.thoughts[order].inferred = ids;
+		}),
+		..TestOptions::default()
+	});
+
+	let order = game.state.hands[Player::Bob as usize][0];
+	let warned = warnings(|| take_turn(&mut game, "Alice clues 5 to Bob"));
+
+	assert!(warned.iter().any(|warning| warning.starts_with(&format!("inconsistent empathy! card {order} is b1"))), "{warned:?}");
+}